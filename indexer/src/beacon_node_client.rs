@@ -3,12 +3,13 @@ use std::time::Duration;
 use eth2::{
     lighthouse::{GlobalValidatorInclusionData, Peer},
     types::{
-        BlockId, CommitteeData, ForkVersionedResponse, GenericResponse, ProposerData, RootData,
-        StateId, ValidatorBalanceData, ValidatorData,
+        BlockHeaderData, BlockId, CommitteeData, EventKind, EventTopic, FinalityCheckpointsData,
+        ForkVersionedResponse, GenericResponse, GenesisData, ProposerData, RootData, StateId,
+        ValidatorBalanceData, ValidatorData,
     },
     BeaconNodeHttpClient, Timeouts,
 };
-use futures::Future;
+use futures::{Future, Stream, StreamExt};
 use lighthouse_types::{Epoch, EthSpec, SignedBeaconBlock};
 use sensitive_url::SensitiveUrl;
 
@@ -28,6 +29,18 @@ impl BeaconNodeClient {
         }
     }
 
+    pub fn get_genesis(&self) -> impl Future<Output = Result<GenesisData, IndexerError>> {
+        let client = self.client.clone();
+
+        async move {
+            client
+                .get_beacon_genesis()
+                .await
+                .map(|response| response.data)
+                .map_err(|inner_error| IndexerError::NodeError { inner_error })
+        }
+    }
+
     pub fn get_block<E: EthSpec>(
         &self,
         block: BlockId,
@@ -58,6 +71,39 @@ impl BeaconNodeClient {
         }
     }
 
+    pub fn get_block_header(
+        &self,
+        block: BlockId,
+    ) -> impl Future<Output = Result<BlockHeaderData, IndexerError>> {
+        let client = self.client.clone();
+
+        async move {
+            client
+                .get_beacon_headers_block_id(block)
+                .await
+                .map_err(|inner_error| IndexerError::NodeError { inner_error })?
+                .map(|response| response.data)
+                .ok_or_else(|| IndexerError::ElementNotFound(block.to_string()))
+        }
+    }
+
+    pub fn get_finality_checkpoints(
+        &self,
+        state: StateId,
+    ) -> impl Future<Output = Result<FinalityCheckpointsData, IndexerError>> {
+        let client = self.client.clone();
+
+        async move {
+            client
+                .get_beacon_states_finality_checkpoints(state)
+                .await
+                .transpose()
+                .ok_or_else(|| IndexerError::ElementNotFound(state.to_string()))?
+                .map(|response| response.data)
+                .map_err(|inner_error| IndexerError::NodeError { inner_error })
+        }
+    }
+
     pub fn get_validators(
         &self,
         state: StateId,
@@ -92,6 +138,25 @@ impl BeaconNodeClient {
         }
     }
 
+    /// Returns the number of deposits of the deposit contract processed by the chain as of
+    /// `state`, which is the index of the next deposit to be included.
+    pub fn get_eth1_deposit_index<E: EthSpec>(
+        &self,
+        state: StateId,
+    ) -> impl Future<Output = Result<u64, IndexerError>> {
+        let client = self.client.clone();
+
+        async move {
+            client
+                .get_debug_beacon_states::<E>(state)
+                .await
+                .transpose()
+                .ok_or_else(|| IndexerError::ElementNotFound(state.to_string()))?
+                .map(|response| response.data.eth1_deposit_index())
+                .map_err(|inner_error| IndexerError::NodeError { inner_error })
+        }
+    }
+
     pub fn get_validator_inclusion(
         &self,
         epoch: Epoch,
@@ -139,6 +204,23 @@ impl BeaconNodeClient {
         }
     }
 
+    /// Returns the members of the sync committee of `epoch`, read from `state`.
+    pub fn get_sync_committee(
+        &self,
+        state: StateId,
+        epoch: Epoch,
+    ) -> impl Future<Output = Result<Vec<u64>, IndexerError>> {
+        let client = self.client.clone();
+
+        async move {
+            client
+                .get_beacon_states_sync_committees(state, Some(epoch))
+                .await
+                .map(|response| response.data.validators)
+                .map_err(|inner_error| IndexerError::NodeError { inner_error })
+        }
+    }
+
     pub fn get_peers<E: EthSpec>(
         &self,
     ) -> impl Future<Output = Result<Vec<Peer<E>>, IndexerError>> {
@@ -164,4 +246,19 @@ impl BeaconNodeClient {
                 .map_err(|inner_error| IndexerError::NodeError { inner_error })
         }
     }
+
+    pub async fn get_events<'a, E: EthSpec>(
+        &'a self,
+        topics: &'a [EventTopic],
+    ) -> Result<impl Stream<Item = Result<EventKind<E>, IndexerError>> + 'a, IndexerError> {
+        self.client
+            .get_events::<E>(topics)
+            .await
+            .map(|stream| {
+                stream.map(|event| {
+                    event.map_err(|inner_error| IndexerError::NodeError { inner_error })
+                })
+            })
+            .map_err(|inner_error| IndexerError::NodeError { inner_error })
+    }
 }
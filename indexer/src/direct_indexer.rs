@@ -1,78 +1,92 @@
-use std::{env, pin::Pin};
+use std::{collections::BTreeMap, path::PathBuf};
 
 use environment::{Environment, EnvironmentBuilder, LoggerConfig};
-use eth2_network_config::{Eth2NetworkConfig, DEFAULT_HARDCODED_NETWORK};
-use futures::{Future, StreamExt};
-use libp2p::{
-    Multiaddr,
-};
-use lighthouse_network::{
-    rpc::{BlocksByRootRequest},
-    Request,
-};
-use store::{Hash256, MainnetEthSpec};
-
-use crate::{beacon_node_client::BeaconNodeClient, network::network_service::NetworkService};
-
+use eth2_network_config::Eth2NetworkConfig;
+use futures::StreamExt;
+use lighthouse_network::PubsubMessage;
+use shared::utils::clock::Clock;
+use slog::{debug, error, info, warn};
+use store::{ChainSpec, EthSpec, SignedBeaconBlock};
+use tokio::sync::mpsc;
 
-// use the executor for libp2p
-struct Executor(task_executor::TaskExecutor);
-
-impl libp2p::core::Executor for Executor {
-    fn exec(&self, f: Pin<Box<dyn Future<Output = ()> + Send>>) {
-        self.0.spawn(f, "libp2p");
-    }
-}
+use crate::{
+    beacon_node_client::BeaconNodeClient,
+    errors::IndexerError,
+    follower::is_recoverable,
+    network::{
+        gossip::gossip, network_service::NetworkService, peers_manager::PeersManager,
+        range_sync::blocks_by_range,
+    },
+    node_to_files_indexer::{Indexer as NodeToFilesIndexer, SortChunkSizes},
+    retriever::Retriever,
+    storage::{local::LocalStorage, Storage},
+};
 
 pub struct Indexer;
 
 impl Indexer {
-    pub fn start() -> Result<(), String> {
-        let endpoint = env::var("ENDPOINT_URL").unwrap();
-        let client = BeaconNodeClient::new(endpoint);
-        let (mut environment, network_config) = Self::build_environment().unwrap();
+    /// Retrieves the blocks up to the head from the peers of the p2p network, found through
+    /// discovery from the boot nodes of the network, then the new ones as they are gossiped, and
+    /// indexes them into `output_dir`. The beacon node at `endpoint_url` is asked for the rest of
+    /// each epoch, and for more peers to connect to.
+    pub fn start<E: EthSpec>(
+        endpoint_url: String,
+        output_dir: PathBuf,
+        spec: ChainSpec,
+        sort_chunk_sizes: SortChunkSizes,
+        environment_builder: EnvironmentBuilder<E>,
+        network_config: Eth2NetworkConfig,
+    ) -> Result<(), String> {
+        let client = BeaconNodeClient::new(endpoint_url.clone());
+        let mut environment = Self::build_environment(environment_builder, network_config.clone())?;
         let context = environment.core_context();
         let executor = context.executor.clone();
+        let log = context.log().clone();
 
         executor.spawn(
             async move {
-                let peers = client
-                    .get_connected_peers::<MainnetEthSpec>()
-                    .await
-                    .unwrap();
-
-                //println!("Peers: {:?}", peers);
-
-                let peer_id = "16Uiu2HAkwgkdraX5wvaCkuRi1YdU5VUvpdQH42Un2DXyADYXAD8Q".parse().unwrap();
-
-                let remote: Multiaddr =
-                    "/ip4/192.168.1.12/tcp/9000/p2p/16Uiu2HAkwgkdraX5wvaCkuRi1YdU5VUvpdQH42Un2DXyADYXAD8Q"
-                        .parse()
-                        .unwrap();
-
-                let root: Hash256 =
-                    "0x74442257e97a932c7b7427e4b0fa35b70e2e650fe6a4841ec22f706296797e70"
-                        .parse()
-                        .unwrap();
-
-                let mut service = NetworkService::new(context.clone(), network_config).await.unwrap();
-
-                service.connect(peer_id, &remote).await;
-
-                /*
-                service
-                    .send_request(
-                        Request::BlocksByRoot(BlocksByRootRequest {
-                            block_roots: vec![root].into(),
-                        }),
-                        peer_id,
-                    )
-                    .await.unwrap();
-                */
-                loop {
-                    service.next().await;
+                let storage = LocalStorage::new(output_dir);
+                let mut blocks_indexer =
+                    match BlocksIndexer::<E, _>::new(endpoint_url, storage, spec).await {
+                        Ok(blocks_indexer) => blocks_indexer,
+                        Err(err) => {
+                            error!(log, "Unable to start the indexer: {:?}", err);
+                            return;
+                        }
+                    };
+                blocks_indexer.set_sort_chunk_sizes(sort_chunk_sizes);
+
+                let boot_enrs = network_config.boot_enr.clone().unwrap_or_default();
+                let mut service = match NetworkService::<E>::new(context, network_config) {
+                    Ok(service) => service,
+                    Err(err) => {
+                        error!(log, "Unable to start the network service: {}", err);
+                        return;
+                    }
+                };
+
+                let mut peers_manager =
+                    match PeersManager::new(boot_enrs, service.fork_digests(), log.clone()).await {
+                        Ok(peers_manager) => peers_manager,
+                        Err(err) => {
+                            error!(log, "Unable to start the discovery: {}", err);
+                            return;
+                        }
+                    };
+
+                // The peers of the node complete those found through discovery
+                match client.get_connected_peers::<E>().await {
+                    Ok(peers) => peers_manager.add_node_peers(&peers),
+                    Err(err) => warn!(log, "Unable to get the peers of the node: {:?}", err),
+                }
+
+                let result =
+                    index_network_blocks(&mut service, &mut peers_manager, &mut blocks_indexer)
+                        .await;
+
+                if let Err(err) = result {
+                    error!(log, "Error while indexing the blocks: {:?}", err);
                 }
-                
             },
             "network",
         );
@@ -82,8 +96,10 @@ impl Indexer {
         Ok(())
     }
 
-    fn build_environment() -> Result<(Environment<MainnetEthSpec>, Eth2NetworkConfig), String> {
-        let environment_builder = EnvironmentBuilder::mainnet();
+    fn build_environment<E: EthSpec>(
+        environment_builder: EnvironmentBuilder<E>,
+        eth2_network_config: Eth2NetworkConfig,
+    ) -> Result<Environment<E>, String> {
         let logger_config = LoggerConfig {
             path: None,
             debug_level: "info",
@@ -93,14 +109,190 @@ impl Indexer {
             max_log_number: 0,
             compression: false,
         };
-        let eth2_network_config = Eth2NetworkConfig::constant(DEFAULT_HARDCODED_NETWORK)?
-            .ok_or("Failed to build Eth2 network config")?;
         let environment = environment_builder
-            .eth2_network_config(eth2_network_config.clone())?
+            .eth2_network_config(eth2_network_config)?
             .initialize_logger(logger_config)?
             .multi_threaded_tokio_runtime()?
             .build()?;
 
-        Ok((environment, eth2_network_config))
+        Ok(environment)
+    }
+}
+
+/// Indexes the blocks retrieved from the peers the manager connects to, from the next epoch to
+/// index on. Only the errors of the storage end the indexing.
+pub async fn index_network_blocks<E: EthSpec, S: Storage>(
+    service: &mut NetworkService<E>,
+    peers_manager: &mut PeersManager,
+    blocks_indexer: &mut BlocksIndexer<E, S>,
+) -> Result<(), IndexerError> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let start_slot = blocks_indexer.start_slot();
+
+    tokio::select! {
+        _ = retrieve_blocks(service, peers_manager, start_slot, sender) => Ok(()),
+        result = blocks_indexer.index(receiver) => result,
+    }
+}
+
+/// Sends the blocks from `start_slot` up to the head, retrieved from the peers, then the new
+/// ones as they are gossiped.
+async fn retrieve_blocks<E: EthSpec>(
+    service: &mut NetworkService<E>,
+    peers_manager: &mut PeersManager,
+    start_slot: u64,
+    sender: mpsc::UnboundedSender<SignedBeaconBlock<E>>,
+) {
+    let log = service.log().clone();
+    let mut blocks = Box::pin(blocks_by_range(service, peers_manager, start_slot));
+
+    while let Some(block) = blocks.next().await {
+        debug!(
+            log,
+            "Retrieved block {} {:?}",
+            block.slot(),
+            block.canonical_root()
+        );
+
+        if sender.send(block).is_err() {
+            return;
+        }
+    }
+
+    info!(log, "Caught up with the head");
+    drop(blocks);
+
+    // The chain is then followed through the objects published by the peers
+    let mut messages = Box::pin(gossip(service, peers_manager));
+
+    while let Some(message) = messages.next().await {
+        match message {
+            PubsubMessage::BeaconBlock(block) => {
+                info!(
+                    log,
+                    "New block {} {:?}",
+                    block.slot(),
+                    block.canonical_root()
+                );
+
+                if sender.send(*block).is_err() {
+                    return;
+                }
+            }
+            // The operations are indexed with the blocks including them
+            message => debug!(log, "Received {:?}", message.kind()),
+        }
+    }
+}
+
+/// Indexes the blocks received from the p2p network epoch by epoch. An epoch is indexed once
+/// the block of its last slot, or a block of a later epoch, is received, the rest of the epoch
+/// being retrieved from the beacon node.
+pub struct BlocksIndexer<E: EthSpec, S: Storage> {
+    retriever: Retriever<E>,
+    indexer: NodeToFilesIndexer<E, S>,
+    next_epoch: u64,
+    /// Blocks received of the epochs not indexed yet, by slot
+    blocks: BTreeMap<u64, SignedBeaconBlock<E>>,
+}
+
+impl<E: EthSpec, S: Storage> BlocksIndexer<E, S> {
+    /// Creates an indexer of the chain of the node at `endpoint_url`, which follows `spec`,
+    /// resuming from the epoch following the last one indexed into `storage`.
+    pub async fn new(
+        endpoint_url: String,
+        storage: S,
+        spec: ChainSpec,
+    ) -> Result<Self, IndexerError> {
+        let retriever = Retriever::new(endpoint_url);
+        let clock = Clock::new(spec, retriever.retrieve_genesis_time().await?);
+        let mut indexer = NodeToFilesIndexer::new(storage, clock)?;
+        indexer.set_finalized_epoch(retriever.retrieve_finalized_epoch().await?)?;
+
+        Ok(BlocksIndexer {
+            retriever,
+            next_epoch: indexer.start_epoch()?,
+            indexer,
+            blocks: BTreeMap::new(),
+        })
+    }
+
+    pub fn set_sort_chunk_sizes(&mut self, sort_chunk_sizes: SortChunkSizes) {
+        self.indexer.set_sort_chunk_sizes(sort_chunk_sizes);
+    }
+
+    /// Returns the first slot of the next epoch to index.
+    pub fn start_slot(&self) -> u64 {
+        self.next_epoch * E::slots_per_epoch()
+    }
+
+    /// Indexes the blocks of `receiver` until it closes. The epochs failing to be retrieved are
+    /// retried with the next block, only the errors of the storage end the indexing.
+    pub async fn index(
+        &mut self,
+        mut receiver: mpsc::UnboundedReceiver<SignedBeaconBlock<E>>,
+    ) -> Result<(), IndexerError> {
+        while let Some(block) = receiver.recv().await {
+            match self.on_block(block).await {
+                Err(err) if is_recoverable(&err) => {
+                    log::error!("Error while indexing epoch {}: {:?}", self.next_epoch, err)
+                }
+                result => result?,
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn on_block(&mut self, block: SignedBeaconBlock<E>) -> Result<(), IndexerError> {
+        let slot = block.slot().as_u64();
+        let epoch = slot / E::slots_per_epoch();
+
+        if epoch < self.next_epoch {
+            log::debug!("Ignoring block {} of an epoch already indexed", slot);
+            return Ok(());
+        }
+
+        self.blocks.insert(slot, block);
+
+        // No block of the epoch can follow the one of its last slot
+        let completed_epoch = match (slot + 1) % E::slots_per_epoch() {
+            0 => epoch + 1,
+            _ => epoch,
+        };
+
+        self.index_before(completed_epoch).await
+    }
+
+    /// Indexes the epochs before `end_epoch` not indexed yet, then updates the validators and
+    /// commits the data tree.
+    async fn index_before(&mut self, end_epoch: u64) -> Result<(), IndexerError> {
+        if self.next_epoch >= end_epoch {
+            return Ok(());
+        }
+
+        while self.next_epoch < end_epoch {
+            let end_slot = (self.next_epoch + 1) * E::slots_per_epoch();
+            // The blocks are kept until the epoch is indexed, to retry it on failure
+            let blocks = self
+                .blocks
+                .range(..end_slot)
+                .map(|(_, block)| block.clone())
+                .collect();
+
+            let epoch = self
+                .retriever
+                .retrieve_epoch_from_blocks(self.next_epoch, blocks)
+                .await?;
+            self.indexer.index_epoch(epoch)?;
+
+            self.blocks = self.blocks.split_off(&end_slot);
+            self.next_epoch += 1;
+        }
+
+        let validators = self.retriever.retrieve_validators().await?;
+        self.indexer.index_validators(validators)?;
+
+        self.indexer.commit()
     }
 }
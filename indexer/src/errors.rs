@@ -1,5 +1,8 @@
+use lighthouse_network::{rpc::RPCError, PeerId};
 use thiserror::Error;
 
+use crate::storage::StorageError;
+
 #[derive(Error, Debug)]
 pub enum IndexerError {
     #[error("Node error")]
@@ -13,4 +16,26 @@ pub enum IndexerError {
 
     #[error(transparent)]
     LockError(tokio::sync::TryLockError),
+
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+}
+
+/// Error ending a request sent to a peer of the p2p network
+#[derive(Error, Debug)]
+pub enum RequestError {
+    #[error("Peer {0} is not connected")]
+    NotConnected(PeerId),
+
+    #[error("The connection to the peer closed before the end of the response")]
+    Disconnected,
+
+    #[error("No response before the timeout")]
+    Timeout,
+
+    #[error("Unexpected response: {0}")]
+    UnexpectedResponse(String),
+
+    #[error(transparent)]
+    Rpc(#[from] RPCError),
 }
@@ -1,38 +1,135 @@
-use std::{collections::BinaryHeap, fs::File, io::BufWriter};
+use std::collections::{BinaryHeap, HashMap};
 
-use rmp_serde::Serializer;
-use serde::Serialize;
+use rmp_serde::decode::Error;
+use types::path::ToPath;
 
-use crate::{orderable::Orderable, persistable::Persistable, persistable_fields::PersistableField};
+use crate::{
+    layout::model_dir,
+    orderable::Orderable,
+    persistable::{encode, write_if_changed, Persistable},
+    persistable_fields::PersistableField,
+    storage::{Storage, StorageError},
+};
 
 pub struct FieldBinaryHeap<F: PersistableField<M>, M> {
     inner: BinaryHeap<Orderable<F::Field>>,
+    persisted_ids: Vec<u64>,
+    chunk_size: usize,
 }
 
-impl<F: PersistableField<M>, M> FieldBinaryHeap<F, M> {
-    pub fn from_model(model: &[M]) -> Self {
-        let mut heap = Self::default();
-        heap.inner.extend(model.iter().map(|x| F::get_value(x)));
-        heap
+impl<F: PersistableField<M>, M: ToPath<u64>> FieldBinaryHeap<F, M> {
+    /// Returns the directory of the sort index, next to the files of the model.
+    pub fn dir() -> String {
+        format!("{}/s/{}", model_dir::<M>(), F::FIELD_NAME)
+    }
+
+    /// Appends the sort keys of `model` to the pending keys, without loading the index. They
+    /// are merged into it on the next `restore`.
+    pub fn append(storage: &dyn Storage, model: &[M]) -> Result<(), StorageError> {
+        let path = Self::pending_keys_path();
+        let mut keys = Vec::new();
+
+        for x in model {
+            let orderable = F::get_value(x);
+            keys.extend(encode(&path, &(orderable.id, orderable.ordering))?);
+        }
+
+        storage.append(&path, &keys)
+    }
+
+    /// Restores the sort keys persisted by a previous run and merges the pending ones into
+    /// them. A pending key whose id was already persisted replaces the previous value. The keys
+    /// of the ids from `count` on, whose models were removed, are dropped.
+    ///
+    /// The index is persisted in chunks of `chunk_size` ids. They are all rewritten if the
+    /// persisted ones were of `persisted_chunk_size` ids instead.
+    pub fn restore(
+        storage: &dyn Storage,
+        chunk_size: usize,
+        persisted_chunk_size: Option<usize>,
+        count: usize,
+    ) -> Result<Self, StorageError> {
+        let persisted = Self::read_keys(storage, &Self::keys_path())?;
+        let persisted_ids = if persisted_chunk_size == Some(chunk_size) {
+            persisted.iter().map(|(id, _)| *id).collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut values = persisted.into_iter().collect::<HashMap<_, _>>();
+        values.extend(Self::read_keys(storage, &Self::pending_keys_path())?);
+        values.retain(|id, _| *id < count as u64);
+
+        Ok(FieldBinaryHeap {
+            inner: values.into_iter().map(Orderable::from).collect(),
+            persisted_ids,
+            chunk_size,
+        })
+    }
+
+    fn keys_path() -> String {
+        format!("{}/keys.msg", Self::dir())
     }
-}
 
-impl<F: PersistableField<M>, M> Default for FieldBinaryHeap<F, M> {
-    fn default() -> Self {
-        FieldBinaryHeap {
-            inner: BinaryHeap::new(),
+    fn pending_keys_path() -> String {
+        format!("{}/pending.msg", Self::dir())
+    }
+
+    fn read_keys(storage: &dyn Storage, path: &str) -> Result<Vec<(u64, F::Field)>, StorageError> {
+        let content = storage.read(path)?.unwrap_or_default();
+        let mut reader = content.as_slice();
+        let mut keys = Vec::new();
+
+        while !reader.is_empty() {
+            let key = rmp_serde::from_read::<_, (u64, F::Field)>(&mut reader).map_err(
+                |source: Error| StorageError::Decode {
+                    path: path.to_string(),
+                    source,
+                },
+            )?;
+            keys.push(key);
         }
+
+        Ok(keys)
     }
 }
 
-impl<F: PersistableField<M>, M> Persistable for FieldBinaryHeap<F, M> {
-    fn persist(self, base_dir: &str) {
-        for (i, chunk) in self.inner.into_sorted_vec().chunks(10).enumerate() {
+impl<F: PersistableField<M>, M: ToPath<u64>> Persistable for FieldBinaryHeap<F, M> {
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        let sorted = self.inner.into_sorted_vec();
+        let keys_path = Self::keys_path();
+
+        let mut keys = Vec::new();
+        for x in &sorted {
+            keys.extend(encode(&keys_path, &(x.id, &x.ordering))?);
+        }
+
+        storage.write(&keys_path, &keys)?;
+
+        // Chunks located before the first moved id are identical to the persisted ones
+        let unchanged = sorted
+            .iter()
+            .zip(self.persisted_ids.iter())
+            .take_while(|(x, id)| x.id == **id)
+            .count();
+
+        for (i, chunk) in sorted
+            .chunks(self.chunk_size)
+            .enumerate()
+            .skip(unchanged / self.chunk_size)
+        {
             let indexes: Vec<u64> = chunk.iter().map(|x| x.id).collect();
-            let mut f = BufWriter::new(
-                File::create(format!("{}/s/{}/{}.msg", base_dir, F::FIELD_NAME, i + 1)).unwrap(),
-            );
-            indexes.serialize(&mut Serializer::new(&mut f)).unwrap();
+            write_if_changed(storage, &format!("{}/{}.msg", Self::dir(), i + 1), &indexes)?;
         }
+
+        // Chunks following the last one are left over by the dropped keys
+        let chunks_count = (sorted.len() + self.chunk_size - 1) / self.chunk_size;
+        let persisted_chunks_count =
+            (self.persisted_ids.len() + self.chunk_size - 1) / self.chunk_size;
+        for i in chunks_count..persisted_chunks_count {
+            storage.remove(&format!("{}/{}.msg", Self::dir(), i + 1))?;
+        }
+
+        storage.remove(&Self::pending_keys_path())
     }
 }
@@ -0,0 +1,181 @@
+use std::{
+    cmp::{max, min},
+    time::Duration,
+};
+
+use eth2::types::EventKind;
+use futures::{pin_mut, StreamExt};
+use lighthouse_types::{ChainSpec, EthSpec};
+use shared::utils::clock::Clock;
+
+use crate::{
+    errors::IndexerError,
+    node_to_files_indexer::{Indexer, SortChunkSizes},
+    retriever::Retriever,
+    storage::Storage,
+};
+
+/// Delay before subscribing again to the events of the node, doubled after each failed attempt
+const MIN_RESUBSCRIPTION_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESUBSCRIPTION_DELAY: Duration = Duration::from_secs(60);
+
+/// Keeps the static data tree up to date with the chain, by indexing each epoch as soon as the
+/// beacon node announces that it is completed.
+pub struct Follower<E: EthSpec, S: Storage> {
+    retriever: Retriever<E>,
+    indexer: Indexer<E, S>,
+    next_epoch: u64,
+}
+
+impl<E: EthSpec, S: Storage> Follower<E, S> {
+    /// Creates a follower of the node at `endpoint_url`, whose chain follows `spec`.
+    pub async fn new(
+        endpoint_url: String,
+        storage: S,
+        spec: ChainSpec,
+    ) -> Result<Self, IndexerError> {
+        let retriever = Retriever::new(endpoint_url);
+        let clock = Clock::new(spec, retriever.retrieve_genesis_time().await?);
+        let indexer = Indexer::new(storage, clock)?;
+
+        Ok(Follower {
+            retriever,
+            next_epoch: indexer.start_epoch()?,
+            indexer,
+        })
+    }
+
+    pub fn set_sort_chunk_sizes(&mut self, sort_chunk_sizes: SortChunkSizes) {
+        self.indexer.set_sort_chunk_sizes(sort_chunk_sizes);
+    }
+
+    /// Indexes the epochs completed so far, then each new one as soon as the node announces it.
+    ///
+    /// The node is subscribed to again whenever its events fail or end, with a delay doubling
+    /// after each failed attempt, the epochs completed meanwhile being caught up with. Only the
+    /// errors of the storage end following.
+    pub async fn follow(&mut self) -> Result<(), IndexerError> {
+        let mut delay = MIN_RESUBSCRIPTION_DELAY;
+
+        loop {
+            match self.follow_events(&mut delay).await {
+                Ok(()) => log::warn!("The events of the node ended"),
+                Err(err) if is_recoverable(&err) => {
+                    log::warn!("Error while following the node: {:?}", err)
+                }
+                Err(err) => return Err(err),
+            }
+
+            log::info!("Subscribing again to the events of the node in {:?}", delay);
+            tokio::time::sleep(delay).await;
+            delay = min(delay * 2, MAX_RESUBSCRIPTION_DELAY);
+        }
+    }
+
+    /// Catches up with the head, from the finalized epoch on, then indexes the epochs announced
+    /// by the events of the node until they end. `delay` is reset once subscribed.
+    async fn follow_events(&mut self, delay: &mut Duration) -> Result<(), IndexerError> {
+        let retriever = self.retriever.clone();
+
+        self.indexer
+            .set_finalized_epoch(retriever.retrieve_finalized_epoch().await?)?;
+        // Reorgs may have happened while not subscribed
+        self.rewind(self.indexer.finalized_epoch());
+
+        self.catch_up(retriever.retrieve_head_epoch().await?)
+            .await?;
+
+        let events = retriever.events().await?;
+        pin_mut!(events);
+
+        // Epochs completed while subscribing to the events
+        self.catch_up(retriever.retrieve_head_epoch().await?)
+            .await?;
+
+        *delay = MIN_RESUBSCRIPTION_DELAY;
+
+        while let Some(event) = events.next().await {
+            match event? {
+                EventKind::Head(head) if head.epoch_transition => {
+                    let current_epoch = head.slot.epoch(E::slots_per_epoch());
+
+                    match self.catch_up(current_epoch.as_u64()).await {
+                        Err(err) if is_recoverable(&err) => log::error!(
+                            "Error while indexing epochs before {}: {:?}",
+                            current_epoch,
+                            err
+                        ),
+                        result => result?,
+                    }
+                }
+                EventKind::Block(block) => {
+                    log::debug!("New block {:?} at slot {}", block.block, block.slot);
+                }
+                EventKind::FinalizedCheckpoint(checkpoint) => {
+                    log::info!("Epoch {} finalized", checkpoint.epoch);
+                    self.indexer
+                        .set_finalized_epoch(checkpoint.epoch.as_u64())?;
+                }
+                EventKind::ChainReorg(reorg) => {
+                    log::warn!(
+                        "Chain reorg of depth {} at slot {}",
+                        reorg.depth,
+                        reorg.slot
+                    );
+
+                    let reorg_epoch = reorg
+                        .slot
+                        .saturating_sub(reorg.depth)
+                        .epoch(E::slots_per_epoch());
+                    self.rewind(reorg_epoch.as_u64());
+
+                    match self.catch_up(reorg.epoch.as_u64()).await {
+                        Err(err) if is_recoverable(&err) => {
+                            log::error!("Error while indexing epochs after reorg: {:?}", err)
+                        }
+                        result => result?,
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes the next catch up index again the epochs from `epoch`, as well as the previous one
+    /// whose blocks may have received votes from the reorganized blocks.
+    fn rewind(&mut self, epoch: u64) {
+        let epoch = max(epoch.saturating_sub(1), self.indexer.finalized_epoch());
+
+        if epoch < self.next_epoch {
+            log::info!("Indexing again from epoch {}", epoch);
+            self.next_epoch = epoch;
+        }
+    }
+
+    /// Indexes every epoch completed before `current_epoch` which is not indexed yet, then
+    /// updates the validators and commits the data tree.
+    async fn catch_up(&mut self, current_epoch: u64) -> Result<(), IndexerError> {
+        if self.next_epoch >= current_epoch {
+            return Ok(());
+        }
+
+        while self.next_epoch < current_epoch {
+            let epoch = self.retriever.retrieve_epoch(self.next_epoch).await?;
+            self.indexer.index_epoch(epoch)?;
+            self.next_epoch += 1;
+        }
+
+        let validators = self.retriever.retrieve_validators().await?;
+        self.indexer.index_validators(validators)?;
+
+        self.indexer.commit()
+    }
+}
+
+/// Returns whether following the node can go on after `err`, unlike after a failure of the
+/// storage.
+pub(crate) fn is_recoverable(err: &IndexerError) -> bool {
+    !matches!(err, IndexerError::StorageError(_))
+}
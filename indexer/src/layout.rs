@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use types::{
+    attestation::AttestationsModelWithId,
+    block::{BlockExtendedModelWithId, BlockModelWithId},
+    committee::{CommitteesModelWithId, SyncCommitteeModelWithId},
+    deposit::{DepositModelWithId, DepositsModelWithId},
+    epoch::{EpochExtendedModelWithId, EpochModelWithId},
+    graffiti::EpochGraffitiModelWithId,
+    lookup::LookupTable,
+    path::ToPath,
+    search::graffiti_dir,
+    slashing::{SlashingModelWithId, SlashingsModelWithId, ValidatorSlashingsModelWithId},
+    validator::ValidatorModelWithId,
+    voluntary_exit::{VoluntaryExitModelWithId, VoluntaryExitsModelWithId},
+    vote::VotesModelWithId,
+};
+
+use crate::{
+    persistable_fields::{
+        BlockSortFields, DepositSortFields, EpochSortFields, PersistableFields,
+        ValidatorSortFields, VoluntaryExitSortFields,
+    },
+    storage::{Storage, StorageError, ROOT},
+};
+
+/// Returns the directory holding the files of the model `M`.
+pub fn model_dir<M: ToPath<u64>>() -> String {
+    Path::new(&M::to_path(ROOT, 0))
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_else(|| ROOT.to_string())
+}
+
+/// Returns the directories of the data tree: those of the persisted models, of their sort
+/// indexes, of the lookup tables and of the search index.
+pub fn dirs() -> Vec<String> {
+    let mut dirs = vec![
+        model_dir::<EpochModelWithId>(),
+        model_dir::<EpochExtendedModelWithId>(),
+        model_dir::<EpochGraffitiModelWithId>(),
+        model_dir::<BlockModelWithId>(),
+        model_dir::<BlockExtendedModelWithId>(),
+        model_dir::<CommitteesModelWithId>(),
+        model_dir::<AttestationsModelWithId>(),
+        model_dir::<VotesModelWithId>(),
+        model_dir::<DepositsModelWithId>(),
+        model_dir::<VoluntaryExitsModelWithId>(),
+        model_dir::<SlashingsModelWithId>(),
+        model_dir::<SyncCommitteeModelWithId>(),
+        model_dir::<ValidatorModelWithId>(),
+        model_dir::<ValidatorSlashingsModelWithId>(),
+        model_dir::<DepositModelWithId>(),
+        model_dir::<VoluntaryExitModelWithId>(),
+        model_dir::<SlashingModelWithId>(),
+    ];
+
+    dirs.extend(<EpochSortFields as PersistableFields<EpochModelWithId>>::dirs());
+    dirs.extend(<BlockSortFields as PersistableFields<BlockModelWithId>>::dirs());
+    dirs.extend(<ValidatorSortFields as PersistableFields<
+        ValidatorModelWithId,
+    >>::dirs());
+    dirs.extend(<DepositSortFields as PersistableFields<
+        DepositModelWithId,
+    >>::dirs());
+    dirs.extend(<VoluntaryExitSortFields as PersistableFields<
+        VoluntaryExitModelWithId,
+    >>::dirs());
+    dirs.extend(LookupTable::ALL.iter().map(|table| table.dir(ROOT)));
+    dirs.push(graffiti_dir(ROOT));
+
+    dirs
+}
+
+/// Creates the directories of the data tree in `storage`.
+pub fn create_dirs(storage: &dyn Storage) -> Result<(), StorageError> {
+    for dir in dirs() {
+        storage.create_dir(&dir)?;
+    }
+
+    Ok(())
+}
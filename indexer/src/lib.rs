@@ -4,10 +4,15 @@ pub mod beacon_node_client;
 pub mod direct_indexer;
 pub mod errors;
 pub mod field_binary_heap;
-mod network;
+pub mod follower;
+pub mod layout;
+pub mod lookup;
+pub mod network;
 pub mod node_to_files_indexer;
 pub mod orderable;
 pub mod persistable;
 mod persistable_fields;
 pub mod retriever;
+pub mod search;
+pub mod storage;
 pub mod types;
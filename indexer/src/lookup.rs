@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Serialize};
+use types::lookup::{LookupShard, LookupTable};
+
+use crate::{
+    persistable::{read, write_if_changed},
+    storage::{Storage, StorageError, ROOT},
+};
+
+/// Inserts `entries` into `table`, each shard being read and written once. The value of a key
+/// already present is replaced.
+pub fn insert<V, I>(
+    storage: &dyn Storage,
+    table: LookupTable,
+    entries: I,
+) -> Result<(), StorageError>
+where
+    V: Serialize + DeserializeOwned,
+    I: IntoIterator<Item = (String, V)>,
+{
+    merge_into_shards(
+        storage,
+        entries
+            .into_iter()
+            .map(|(key, value)| (table.path(ROOT, &key), key, value)),
+        |persisted, value| *persisted = value,
+    )
+}
+
+/// Inserts `entries`, made of the path of their shard, their key and their value, into maps
+/// sharded over several files, each shard being read and written once. `merge` combines the
+/// value of a key already present with the inserted one.
+pub(crate) fn merge_into_shards<V, I, F>(
+    storage: &dyn Storage,
+    entries: I,
+    merge: F,
+) -> Result<(), StorageError>
+where
+    V: Serialize + DeserializeOwned,
+    I: IntoIterator<Item = (String, String, V)>,
+    F: Fn(&mut V, V),
+{
+    let mut shards = BTreeMap::<String, Vec<(String, V)>>::new();
+
+    for (path, key, value) in entries {
+        shards.entry(path).or_default().push((key, value));
+    }
+
+    for (path, entries) in shards {
+        let mut shard = read_shard::<V>(storage, &path)?.unwrap_or_default();
+
+        for (key, value) in entries {
+            match shard.get_mut(&key) {
+                Some(persisted) => merge(persisted, value),
+                None => {
+                    shard.insert(key, value);
+                }
+            }
+        }
+
+        write_if_changed(storage, &path, &shard)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the value of `key` in `table`.
+pub fn get<V: DeserializeOwned>(
+    storage: &dyn Storage,
+    table: LookupTable,
+    key: &str,
+) -> Result<Option<V>, StorageError> {
+    let path = table.path(ROOT, key);
+
+    Ok(read_shard::<V>(storage, &path)?.and_then(|mut shard| shard.remove(key)))
+}
+
+pub(crate) fn read_shard<V: DeserializeOwned>(
+    storage: &dyn Storage,
+    path: &str,
+) -> Result<Option<LookupShard<V>>, StorageError> {
+    read(storage, path)
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use libp2p::{
+    gossipsub::{
+        subscription_filter::AllowAllSubscriptionFilter, Gossipsub, GossipsubEvent,
+        MessageAuthenticity,
+    },
+    NetworkBehaviour,
+};
+use lighthouse_network::{
+    config::gossipsub_config,
+    rpc::{RPCMessage, RPC},
+    types::SnappyTransform,
+};
+use slog::Logger;
+use store::{EthSpec, ForkContext};
+
+/// Load of the network we are willing to handle, on the scale of Lighthouse (1 to 5)
+const NETWORK_LOAD: u8 = 3;
+
+/// Behaviour of the swarm: the request-response protocols and the gossip
+#[derive(NetworkBehaviour)]
+#[behaviour(out_event = "BehaviourEvent<E>", event_process = false)]
+pub struct Behaviour<E: EthSpec> {
+    pub rpc: RPC<E>,
+    /// Gossip messages are compressed with snappy
+    pub gossipsub: Gossipsub<SnappyTransform, AllowAllSubscriptionFilter>,
+}
+
+pub enum BehaviourEvent<E: EthSpec> {
+    Rpc(RPCMessage<E>),
+    Gossipsub(GossipsubEvent),
+}
+
+impl<E: EthSpec> Behaviour<E> {
+    pub fn new(fork_context: Arc<ForkContext>, log: Logger) -> Result<Self, String> {
+        let config = gossipsub_config(NETWORK_LOAD, fork_context.clone());
+        let transform = SnappyTransform::new(config.max_transmit_size());
+        // The messages of the beacon chain are neither signed by their author nor numbered
+        let gossipsub = Gossipsub::new_with_subscription_filter_and_transform(
+            MessageAuthenticity::Anonymous,
+            config,
+            None,
+            AllowAllSubscriptionFilter {},
+            transform,
+        )?;
+
+        Ok(Behaviour {
+            rpc: RPC::new(fork_context, log),
+            gossipsub,
+        })
+    }
+}
+
+impl<E: EthSpec> From<RPCMessage<E>> for BehaviourEvent<E> {
+    fn from(message: RPCMessage<E>) -> Self {
+        BehaviourEvent::Rpc(message)
+    }
+}
+
+impl<E: EthSpec> From<GossipsubEvent> for BehaviourEvent<E> {
+    fn from(event: GossipsubEvent) -> Self {
+        BehaviourEvent::Gossipsub(event)
+    }
+}
@@ -0,0 +1,67 @@
+use std::net::SocketAddr;
+
+use lighthouse_network::{
+    discv5::{
+        enr::{CombinedKey, EnrBuilder, NodeId},
+        Discv5, Discv5ConfigBuilder, Enr,
+    },
+    EnrExt, Eth2Enr, Multiaddr, PeerId,
+};
+
+/// Finds peers of the network through discv5, starting from its boot nodes. We only query the
+/// other nodes, our own record advertises no address.
+pub struct Discovery {
+    discv5: Discv5,
+    /// Fork digests of the network, those advertised by the nodes of other networks differ
+    fork_digests: Vec<[u8; 4]>,
+}
+
+impl Discovery {
+    pub async fn new(boot_enrs: Vec<Enr>, fork_digests: Vec<[u8; 4]>) -> Result<Self, String> {
+        let enr_key = CombinedKey::generate_secp256k1();
+        let local_enr = EnrBuilder::new("v4")
+            .build(&enr_key)
+            .map_err(|err| format!("Unable to build the local ENR: {:?}", err))?;
+        let config = Discv5ConfigBuilder::new().build();
+        let mut discv5 = Discv5::new(local_enr, enr_key, config)?;
+
+        for enr in boot_enrs {
+            discv5.add_enr(enr)?;
+        }
+
+        discv5
+            .start(SocketAddr::from(([0, 0, 0, 0], 0)))
+            .await
+            .map_err(|err| format!("Unable to start discv5: {:?}", err))?;
+
+        Ok(Discovery {
+            discv5,
+            fork_digests,
+        })
+    }
+
+    /// Looks up the nodes closest to a random id, and returns those of the network reachable over
+    /// TCP.
+    pub async fn find_peers(&self) -> Result<Vec<(PeerId, Multiaddr)>, String> {
+        let enrs = self
+            .discv5
+            .find_node(NodeId::random())
+            .await
+            .map_err(|err| format!("Discovery query failed: {:?}", err))?;
+
+        let peers = enrs
+            .iter()
+            .filter(|enr| {
+                enr.eth2()
+                    .map(|enr_fork_id| self.fork_digests.contains(&enr_fork_id.fork_digest))
+                    .unwrap_or(false)
+            })
+            .filter_map(|enr| {
+                let multiaddr = enr.multiaddr_tcp().into_iter().next()?;
+                Some((enr.peer_id(), multiaddr))
+            })
+            .collect();
+
+        Ok(peers)
+    }
+}
@@ -0,0 +1,40 @@
+use futures::{Stream, StreamExt};
+use lighthouse_network::PubsubMessage;
+use slog::warn;
+use store::EthSpec;
+
+use super::{
+    network_service::{NetworkEvent, NetworkService},
+    peers_manager::{PeersManager, MAINTENANCE_INTERVAL},
+};
+
+/// Yields the objects published on the topics the service subscribed to, while keeping the
+/// manager's peers connected and following the forks of the network.
+pub fn gossip<'a, E: EthSpec>(
+    service: &'a mut NetworkService<E>,
+    peers_manager: &'a mut PeersManager,
+) -> impl Stream<Item = PubsubMessage<E>> + 'a {
+    async_stream::stream! {
+        let mut maintenance = tokio::time::interval(MAINTENANCE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = maintenance.tick() => {
+                    if let Err(err) = service.update_fork() {
+                        warn!(service.log(), "{}", err);
+                    }
+                    peers_manager.maintain(service).await;
+                }
+                event = service.next() => match event {
+                    Some(NetworkEvent::Gossip(message)) => yield message,
+                    Some(NetworkEvent::PeerDisconnected(peer_id)) => {
+                        peers_manager.on_disconnected(service, peer_id).await;
+                        peers_manager.maintain(service).await;
+                    }
+                    Some(NetworkEvent::PeerReady { .. }) | Some(NetworkEvent::None) => {}
+                    None => break,
+                },
+            }
+        }
+    }
+}
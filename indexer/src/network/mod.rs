@@ -1,3 +1,7 @@
+pub mod behaviour;
+pub mod discovery;
+pub mod gossip;
 pub mod network_service;
 pub mod peers_manager;
+pub mod range_sync;
 pub mod request_handler;
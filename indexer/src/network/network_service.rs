@@ -1,226 +1,721 @@
 use std::{
     collections::HashMap,
-    marker::PhantomData,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use environment::RuntimeContext;
 use eth2_network_config::Eth2NetworkConfig;
-use futures::{future, join, Future, FutureExt, Stream, StreamExt};
+use futures::{Future, Stream, StreamExt};
 use libp2p::{
     bandwidth::BandwidthLogging,
-    core::{muxing::StreamMuxerBox, transport::Boxed},
+    core::{connection::ConnectionId, muxing::StreamMuxerBox, transport::Boxed, Executor},
     dns::TokioDnsConfig,
-    swarm::{SwarmBuilder, SwarmEvent},
+    gossipsub::{GossipsubEvent, GossipsubMessage, IdentTopic, MessageAcceptance, MessageId},
+    swarm::{dial_opts::DialOpts, SwarmBuilder, SwarmEvent},
     tcp::TokioTcpConfig,
     Multiaddr, Swarm, Transport,
 };
 use lighthouse_network::{
     peer_manager::Keypair,
-    rpc::{methods::Ping, outbound::OutboundRequest, RPCReceived, RequestId, RPC},
-    PeerId, Request,
+    rpc::{
+        handler::HandlerErr,
+        methods::{
+            BlocksByRangeRequest, MetaData, MetaDataV2, Ping, RPCCodedResponse, RPCResponse,
+            ResponseTermination, StatusMessage,
+        },
+        outbound::OutboundRequest,
+        protocol::InboundRequest,
+        RPCReceived, RequestId, SubstreamId,
+    },
+    types::{
+        EnrAttestationBitfield, EnrSyncCommitteeBitfield, GossipEncoding, GossipKind, GossipTopic,
+    },
+    PeerId, PubsubMessage,
 };
-use slog::{error, info, Logger};
-use store::{BeaconState, ForkContext, MainnetEthSpec};
-use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use slog::{debug, error, info, warn, Logger};
+use store::{BeaconState, ChainSpec, EthSpec, ForkContext, Hash256, SignedBeaconBlock, Slot};
+use tokio::sync::{mpsc::UnboundedSender, oneshot};
 
-use super::request_handler::SafeRequestHandler;
+use super::{
+    behaviour::{Behaviour, BehaviourEvent},
+    request_handler::{QueuedRequest, RequestHandler, ResponseSender},
+};
+use crate::errors::RequestError;
+
+/// Time a peer has to send all the responses to a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Number of epochs before a fork we subscribe to its topics, so that no message is missed
+const FORK_SUBSCRIPTION_EPOCHS: u64 = 2;
+
+/// Topics we subscribe to, those of the objects included in the blocks
+const GOSSIP_KINDS: [GossipKind; 5] = [
+    GossipKind::BeaconBlock,
+    GossipKind::BeaconAggregateAndProof,
+    GossipKind::VoluntaryExit,
+    GossipKind::ProposerSlashing,
+    GossipKind::AttesterSlashing,
+];
 
 type BoxedTransport = Boxed<(PeerId, StreamMuxerBox)>;
 
-struct Executor(task_executor::TaskExecutor);
+struct TaskExecutor(task_executor::TaskExecutor);
 
-impl libp2p::core::Executor for Executor {
+impl Executor for TaskExecutor {
     fn exec(&self, f: Pin<Box<dyn Future<Output = ()> + Send>>) {
         self.0.spawn(f, "libp2p");
     }
 }
 
-pub enum NetworkEvent {
+pub enum NetworkEvent<E: EthSpec> {
     None,
+    /// The peer answered our status with a compatible one, it can be sent requests
+    PeerReady {
+        peer_id: PeerId,
+        status: StatusMessage,
+    },
+    /// The connection to the peer closed, or it couldn't be reached
+    PeerDisconnected(PeerId),
+    /// Object published on one of the topics we subscribed to
+    Gossip(PubsubMessage<E>),
 }
 
-pub struct NetworkService {
-    swarm: Arc<Mutex<Swarm<RPC<MainnetEthSpec>>>>,
-    connected_peers: HashMap<PeerId, UnboundedSender<OutboundRequest<MainnetEthSpec>>>,
-    request_handler: SafeRequestHandler,
+/// Responses received so far to a request sent with an id
+struct PendingResponse<E: EthSpec> {
+    peer_id: PeerId,
+    responses: Vec<RPCResponse<E>>,
+    response_tx: ResponseSender<E>,
+}
+
+/// Connection to the p2p network, streaming its events. The requests are sent and their
+/// responses received while the service is polled.
+pub struct NetworkService<E: EthSpec> {
+    swarm: Swarm<Behaviour<E>>,
+    /// Used to decode the gossip messages of each fork
+    fork_context: Arc<ForkContext>,
+    spec: ChainSpec,
+    genesis_time: u64,
+    /// Fork digests whose topics we are subscribed to
+    subscribed_digests: Vec<[u8; 4]>,
+    connected_peers: HashMap<PeerId, UnboundedSender<QueuedRequest<E>>>,
+    request_handler: RequestHandler<E>,
+    /// Status sent to the peers, that of a node holding the genesis block only
+    local_status: StatusMessage,
+    /// Metadata served to the peers. We subscribe to no subnet, it never changes.
+    local_metadata: MetaData<E>,
+    /// Requests waiting for the end of their responses, by id
+    pending_responses: HashMap<usize, PendingResponse<E>>,
+    next_request_id: AtomicUsize,
     log: Logger,
 }
 
-impl NetworkService {
-    pub async fn new(
-        context: RuntimeContext<MainnetEthSpec>,
+impl<E: EthSpec> NetworkService<E> {
+    pub fn new(
+        context: RuntimeContext<E>,
         network_config: Eth2NetworkConfig,
     ) -> Result<Self, String> {
         let spec = context.eth2_config().spec.clone();
-        let genesis_state_bytes = network_config.genesis_state_bytes.unwrap();
-        let genesis_state =
-            BeaconState::<MainnetEthSpec>::from_ssz_bytes(&genesis_state_bytes, &spec)
-                .map_err(|e| format!("Unable to parse genesis state SSZ: {:?}", e))?;
-        let fork_context = Arc::new(ForkContext::new::<MainnetEthSpec>(
-            spec.genesis_slot,
-            genesis_state.genesis_validators_root(),
-            &spec,
-        ));
-        let executor = context.clone().executor;
-
-        //let connected_peers = Arc::new(vec![]);
-        let request_handler = SafeRequestHandler::new();
+        let genesis_state_bytes = network_config
+            .genesis_state_bytes
+            .ok_or("The network config holds no genesis state")?;
+        let genesis_state = BeaconState::<E>::from_ssz_bytes(&genesis_state_bytes, &spec)
+            .map_err(|e| format!("Unable to parse genesis state SSZ: {:?}", e))?;
+        // The peers only accept the fork digest of the current fork
+        let genesis_time = genesis_state.genesis_time();
+        let current_slot = Self::slot_at(&spec, genesis_time)?;
+        let fork_context =
+            ForkContext::new::<E>(current_slot, genesis_state.genesis_validators_root(), &spec);
+
+        let mut genesis_block_header = genesis_state.latest_block_header().clone();
+        genesis_block_header.state_root = genesis_state.canonical_root();
+
+        Self::with_fork_context(
+            Arc::new(fork_context),
+            spec,
+            genesis_time,
+            genesis_block_header.canonical_root(),
+            Box::new(TaskExecutor(context.executor.clone())),
+            context.log().clone(),
+        )
+    }
+
+    /// Creates the service of a node of the network of `fork_context`, holding only the genesis
+    /// block whose root is `genesis_block_root`. The connections are run by `executor`.
+    pub fn with_fork_context(
+        fork_context: Arc<ForkContext>,
+        spec: ChainSpec,
+        genesis_time: u64,
+        genesis_block_root: Hash256,
+        executor: Box<dyn Executor + Send>,
+        log: Logger,
+    ) -> Result<Self, String> {
+        let fork_digest = fork_context
+            .to_context_bytes(fork_context.current_fork())
+            .ok_or("The current fork has no fork digest")?;
+        let local_status = StatusMessage {
+            fork_digest,
+            finalized_root: Hash256::zero(),
+            finalized_epoch: spec.genesis_slot.epoch(E::slots_per_epoch()),
+            head_root: genesis_block_root,
+            head_slot: spec.genesis_slot,
+        };
 
         let local_key = Keypair::generate_ed25519();
         let local_peer_id = PeerId::from(local_key.public());
-        let transport = Self::build_transport(local_key).unwrap();
-        let behaviour = RPC::<MainnetEthSpec>::new(fork_context, executor.log().clone());
+        let transport = Self::build_transport(local_key)?;
+        let behaviour = Behaviour::new(fork_context.clone(), log.clone())?;
         let swarm = SwarmBuilder::new(transport, behaviour, local_peer_id)
-            .executor(Box::new(Executor(executor)))
+            .executor(executor)
             .build();
 
-        let indexer = Self {
-            swarm: Arc::new(Mutex::new(swarm)),
+        let mut service = Self {
+            swarm,
+            fork_context,
+            spec,
+            genesis_time,
+            subscribed_digests: Vec::new(),
             connected_peers: HashMap::new(),
-            request_handler,
-            log: context.log().clone(),
+            request_handler: RequestHandler::new(),
+            local_status,
+            local_metadata: MetaData::V2(MetaDataV2 {
+                seq_number: 0,
+                attnets: EnrAttestationBitfield::<E>::new(),
+                syncnets: EnrSyncCommitteeBitfield::<E>::new(),
+            }),
+            pending_responses: HashMap::new(),
+            next_request_id: AtomicUsize::new(0),
+            log,
         };
 
-        Ok(indexer)
+        service.subscribe(fork_digest)?;
+        service.update_fork()?;
+
+        Ok(service)
     }
 
-    pub async fn connect(
-        &mut self,
+    fn slot_at(spec: &ChainSpec, genesis_time: u64) -> Result<Slot, String> {
+        let seconds_since_genesis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| err.to_string())?
+            .as_secs()
+            .saturating_sub(genesis_time);
+
+        Ok(spec.genesis_slot + seconds_since_genesis / spec.seconds_per_slot)
+    }
+
+    /// Follows the forks of the network, to be called regularly. The topics of the next fork are
+    /// subscribed to `FORK_SUBSCRIPTION_EPOCHS` ahead of it. Once it is reached, our status
+    /// advertises its digest and the topics of the previous fork are left.
+    pub fn update_fork(&mut self) -> Result<(), String> {
+        let current_slot = Self::slot_at(&self.spec, self.genesis_time)?;
+        let current_fork = self.spec.fork_name_at_slot::<E>(current_slot);
+
+        if current_fork != self.fork_context.current_fork() {
+            info!(self.log, "Fork {:?} reached", current_fork);
+            self.fork_context.update_current_fork(current_fork);
+
+            let fork_digest = self
+                .fork_context
+                .to_context_bytes(current_fork)
+                .ok_or("The current fork has no fork digest")?;
+            self.local_status.fork_digest = fork_digest;
+            self.subscribe(fork_digest)?;
+
+            for digest in self.subscribed_digests.clone() {
+                if digest != fork_digest {
+                    self.unsubscribe(digest)?;
+                }
+            }
+        }
+
+        let next_fork = self.spec.next_fork_epoch::<E>(current_slot);
+
+        if let Some((next_fork, fork_epoch)) = next_fork {
+            let subscription_slot = fork_epoch
+                .saturating_sub(FORK_SUBSCRIPTION_EPOCHS)
+                .start_slot(E::slots_per_epoch());
+
+            if current_slot >= subscription_slot {
+                let fork_digest = self
+                    .fork_context
+                    .to_context_bytes(next_fork)
+                    .ok_or("The next fork has no fork digest")?;
+                self.subscribe(fork_digest)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn subscribe(&mut self, fork_digest: [u8; 4]) -> Result<(), String> {
+        if self.subscribed_digests.contains(&fork_digest) {
+            return Ok(());
+        }
+
+        for topic in Self::topics(fork_digest) {
+            self.swarm
+                .behaviour_mut()
+                .gossipsub
+                .subscribe(&topic)
+                .map_err(|err| format!("Unable to subscribe to {}: {:?}", topic, err))?;
+        }
+
+        info!(
+            self.log,
+            "Subscribed to the topics of fork {:?}", fork_digest
+        );
+        self.subscribed_digests.push(fork_digest);
+
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, fork_digest: [u8; 4]) -> Result<(), String> {
+        for topic in Self::topics(fork_digest) {
+            self.swarm
+                .behaviour_mut()
+                .gossipsub
+                .unsubscribe(&topic)
+                .map_err(|err| format!("Unable to unsubscribe from {}: {:?}", topic, err))?;
+        }
+
+        info!(
+            self.log,
+            "Unsubscribed from the topics of fork {:?}", fork_digest
+        );
+        self.subscribed_digests.retain(|x| *x != fork_digest);
+
+        Ok(())
+    }
+
+    fn topics(fork_digest: [u8; 4]) -> impl Iterator<Item = IdentTopic> {
+        GOSSIP_KINDS.iter().map(move |kind| {
+            IdentTopic::new(GossipTopic::new(
+                kind.clone(),
+                GossipEncoding::default(),
+                fork_digest,
+            ))
+        })
+    }
+
+    /// Returns the fork digests of the network, of the current fork and of the scheduled ones.
+    pub fn fork_digests(&self) -> Vec<[u8; 4]> {
+        self.fork_context.all_fork_digests()
+    }
+
+    pub fn log(&self) -> &Logger {
+        &self.log
+    }
+
+    /// Dials the peer, unless already connected, and creates its request channel. The channel is
+    /// activated once the connection is established.
+    pub fn connect(&mut self, peer_id: PeerId, multiaddr: &Multiaddr) -> Result<(), String> {
+        if self.connected_peers.contains_key(&peer_id) {
+            return Ok(());
+        }
+
+        let tx = self.request_handler.create_channel(peer_id)?;
+
+        // Dialing with the peer id gets us its id back if the connection fails
+        let opts = DialOpts::peer_id(peer_id)
+            .addresses(vec![multiaddr.clone()])
+            .build();
+
+        if let Err(err) = self.swarm.dial(opts) {
+            self.request_handler.close_channel(peer_id);
+            return Err(err.to_string());
+        }
+
+        self.connected_peers.insert(peer_id, tx);
+
+        Ok(())
+    }
+
+    /// Closes the connection to the peer, if still open, and drops its request channel. The peer
+    /// can be dialed again afterwards.
+    pub fn disconnect(&mut self, peer_id: PeerId) {
+        self.connected_peers.remove(&peer_id);
+        self.request_handler.close_channel(peer_id);
+        let _ = self.swarm.disconnect_peer_id(peer_id);
+    }
+
+    /// Sends `request` to the peer and returns its responses, once the peer sent them all.
+    ///
+    /// The returned future doesn't borrow the service, which has to be polled for the request
+    /// to be sent and for the responses to be received. It fails if the peer disconnects or
+    /// answers with an error, or after `REQUEST_TIMEOUT`.
+    pub fn request(
+        &self,
         peer_id: PeerId,
-        multiaddr: &Multiaddr,
-    ) -> &mut UnboundedSender<OutboundRequest<MainnetEthSpec>> {
-        let mut request_handler = self.request_handler.guard().await;
-        let is_connected = self.connected_peers.contains_key(&peer_id);
+        request: OutboundRequest<E>,
+    ) -> impl Future<Output = Result<Vec<RPCResponse<E>>, RequestError>> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (response_tx, response_rx) = oneshot::channel();
+        let queued = self.queue(peer_id, request, Some((id, response_tx)));
 
-        let tx = self
-            .connected_peers
-            .entry(peer_id)
-            .or_insert_with(|| request_handler.create_channel(peer_id).unwrap());
+        async move {
+            queued?;
 
-        if !is_connected {
-            self.swarm.lock().await.dial(multiaddr.clone()).unwrap();
+            // On timeout, the receiver is dropped and the service forgets the request
+            match tokio::time::timeout(REQUEST_TIMEOUT, response_rx).await {
+                Ok(Ok(result)) => result,
+                Ok(Err(_)) => Err(RequestError::Disconnected),
+                Err(_) => Err(RequestError::Timeout),
+            }
         }
+    }
 
-        tx
+    /// Requests the blocks of the `count` slots from `start_slot` on from the peer.
+    pub fn blocks_by_range(
+        &self,
+        peer_id: PeerId,
+        start_slot: u64,
+        count: u64,
+    ) -> impl Future<Output = Result<Vec<SignedBeaconBlock<E>>, RequestError>> {
+        let request = OutboundRequest::BlocksByRange(BlocksByRangeRequest {
+            start_slot,
+            count,
+            step: 1,
+        });
+        let responses = self.request(peer_id, request);
+
+        async move {
+            responses
+                .await?
+                .into_iter()
+                .map(|response| match response {
+                    RPCResponse::BlocksByRange(block) => Ok(*block),
+                    response => Err(RequestError::UnexpectedResponse(format!("{:?}", response))),
+                })
+                .collect()
+        }
     }
 
-    pub async fn send_request(
+    fn queue(
         &self,
-        request: OutboundRequest<MainnetEthSpec>,
         peer_id: PeerId,
-    ) -> Result<(), String> {
-        let tx = self.connected_peers.get(&peer_id).ok_or("err")?;
-        tx.send(request).map_err(|err| err.to_string())?;
+        request: OutboundRequest<E>,
+        response: Option<(usize, ResponseSender<E>)>,
+    ) -> Result<(), RequestError> {
+        let tx = self
+            .connected_peers
+            .get(&peer_id)
+            .ok_or(RequestError::NotConnected(peer_id))?;
 
-        Ok(())
+        tx.send(QueuedRequest { request, response })
+            .map_err(|_| RequestError::Disconnected)
     }
 
-    async fn poll_requests(&self) {
-        let mut request_handler = self.request_handler.guard().await;
+    /// Hands a request to the swarm, which sends it when polled. The responses to the requests
+    /// sent with an id are matched to them by it, the requests whose requester already gave up
+    /// are dropped.
+    fn send_request(&mut self, peer_id: PeerId, queued: QueuedRequest<E>) {
+        // The requests timed out since are forgotten, with the responses received so far
+        self.pending_responses
+            .retain(|_, pending| !pending.response_tx.is_closed());
 
-        while let Some((peer_id, r)) = request_handler.next().await {
-            info!(self.log, "Sending request to {:?}", peer_id);
-            self.swarm.lock().await.behaviour_mut().send_request(
-                peer_id,
-                RequestId::Behaviour,
-                r.into(),
-            );
+        // `Sync` is the only request id carrying a number
+        let request_id = match queued.response {
+            Some((id, response_tx)) => {
+                if response_tx.is_closed() {
+                    debug!(self.log, "Dropping request {} timed out before sending", id);
+                    return;
+                }
+
+                self.pending_responses.insert(
+                    id,
+                    PendingResponse {
+                        peer_id,
+                        responses: Vec::new(),
+                        response_tx,
+                    },
+                );
+                RequestId::Sync(id)
+            }
+            None => RequestId::Behaviour,
+        };
+
+        debug!(self.log, "Sending request to {:?}", peer_id);
+        self.swarm
+            .behaviour_mut()
+            .rpc
+            .send_request(peer_id, request_id, queued.request);
+    }
+
+    /// Records a response to the request `id`. The requests with a single response end with it,
+    /// the others with the end of their stream.
+    fn on_response(&mut self, id: usize, response: RPCResponse<E>) {
+        let is_stream = matches!(
+            response,
+            RPCResponse::BlocksByRange(_) | RPCResponse::BlocksByRoot(_)
+        );
+
+        if let Some(pending) = self.pending_responses.get_mut(&id) {
+            pending.responses.push(response);
+        }
+
+        if !is_stream {
+            self.end_request(id, Ok(()));
+        }
+    }
+
+    /// Sends the responses to the request `id` to the requester, or the error ending it.
+    fn end_request(&mut self, id: usize, result: Result<(), RequestError>) {
+        if let Some(pending) = self.pending_responses.remove(&id) {
+            let _ = pending.response_tx.send(result.map(|_| pending.responses));
+        }
+    }
+
+    /// Fails the requests waiting for responses from a peer which disconnected, and drops its
+    /// request channel.
+    fn end_requests_of(&mut self, peer_id: PeerId) {
+        self.request_handler.close_channel(peer_id);
+
+        let ids = self
+            .pending_responses
+            .iter()
+            .filter(|(_, x)| x.peer_id == peer_id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            self.end_request(id, Err(RequestError::Disconnected));
         }
     }
 
-    async fn poll_swarm(&self) -> Option<NetworkEvent> {
-        match self.swarm.lock().await.select_next_some().await {
+    fn on_swarm_event<H>(&mut self, event: SwarmEvent<BehaviourEvent<E>, H>) -> NetworkEvent<E> {
+        match event {
             SwarmEvent::NewListenAddr { address, .. } => {
-                println!("Listening on {:?}", address);
-                Some(NetworkEvent::None)
+                info!(self.log, "Listening on {:?}", address);
+                NetworkEvent::None
             }
-            SwarmEvent::Behaviour(e) => {
-                match e.event {
-                    Ok(event) => match event {
-                        RPCReceived::Request(_, request) => match request.protocol() {
-                            lighthouse_network::rpc::Protocol::Status => todo!(),
-                            lighthouse_network::rpc::Protocol::Goodbye => todo!(),
-                            lighthouse_network::rpc::Protocol::BlocksByRange => todo!(),
-                            lighthouse_network::rpc::Protocol::BlocksByRoot => todo!(),
-                            lighthouse_network::rpc::Protocol::Ping => {
-                                let request = OutboundRequest::Ping(Ping { data: 1 });
-                                self.send_request(request, e.peer_id).await.unwrap();
-                            }
-                            lighthouse_network::rpc::Protocol::MetaData => {
-                                let request = OutboundRequest::MetaData(PhantomData);
-                                self.send_request(request, e.peer_id).await.unwrap();
-                            }
-                        },
-                        RPCReceived::Response(_, response) => {
-                            info!(self.log, "Response: {:?}", response)
-                        }
-                        RPCReceived::EndOfStream(_, _) => todo!(),
-                    },
-                    Err(err) => error!(self.log, "{:?}", err),
+            SwarmEvent::Behaviour(BehaviourEvent::Rpc(e)) => match e.event {
+                Ok(event) => match event {
+                    RPCReceived::Request(substream_id, request) => {
+                        self.on_request(e.peer_id, (e.conn_id, substream_id), request)
+                    }
+                    RPCReceived::Response(RequestId::Sync(id), response) => {
+                        self.on_response(id, response);
+                        NetworkEvent::None
+                    }
+                    RPCReceived::Response(_, RPCResponse::Status(status)) => {
+                        self.on_status(e.peer_id, status)
+                    }
+                    RPCReceived::Response(_, response) => {
+                        info!(self.log, "Response: {:?}", response);
+                        NetworkEvent::None
+                    }
+                    RPCReceived::EndOfStream(RequestId::Sync(id), _) => {
+                        self.end_request(id, Ok(()));
+                        NetworkEvent::None
+                    }
+                    RPCReceived::EndOfStream(_, termination) => {
+                        debug!(self.log, "End of stream: {:?}", termination);
+                        NetworkEvent::None
+                    }
+                },
+                Err(HandlerErr::Outbound {
+                    id: RequestId::Sync(id),
+                    error,
+                    ..
+                }) => {
+                    debug!(
+                        self.log,
+                        "Request {} to {:?} failed: {}", id, e.peer_id, error
+                    );
+                    self.end_request(id, Err(error.into()));
+                    NetworkEvent::None
                 }
-                Some(NetworkEvent::None)
-            }
+                Err(err) => {
+                    error!(self.log, "{:?}", err);
+                    NetworkEvent::None
+                }
+            },
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(GossipsubEvent::Message {
+                propagation_source,
+                message_id,
+                message,
+            })) => self.on_gossip(propagation_source, message_id, message),
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(_)) => NetworkEvent::None,
             SwarmEvent::ConnectionEstablished { peer_id, .. } => {
-                self.request_handler
-                    .guard()
-                    .await
-                    .activate(peer_id)
-                    .unwrap();
                 info!(self.log, "Connected to {:?}", peer_id);
-                Some(NetworkEvent::None)
+
+                // Only the peers we dialed have a channel, we send them our status first
+                match self.request_handler.activate(peer_id) {
+                    Ok(()) => self.send_request(
+                        peer_id,
+                        QueuedRequest {
+                            request: OutboundRequest::Status(self.local_status.clone()),
+                            response: None,
+                        },
+                    ),
+                    Err(err) => debug!(self.log, "{}: {:?}", err, peer_id),
+                }
+                NetworkEvent::None
             }
-            SwarmEvent::OutgoingConnectionError { error, .. } => {
-                println!("Error {:?}", error);
-                Some(NetworkEvent::None)
+            SwarmEvent::OutgoingConnectionError { peer_id, error } => {
+                warn!(self.log, "Unable to connect to {:?}: {}", peer_id, error);
+
+                match peer_id {
+                    Some(peer_id) => {
+                        self.request_handler.close_channel(peer_id);
+                        NetworkEvent::PeerDisconnected(peer_id)
+                    }
+                    None => NetworkEvent::None,
+                }
             }
             SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                self.request_handler.guard().await.close_channel(peer_id);
+                self.end_requests_of(peer_id);
                 info!(self.log, "Connection to {:} closed", peer_id);
-                Some(NetworkEvent::None)
+                NetworkEvent::PeerDisconnected(peer_id)
             }
-            SwarmEvent::IncomingConnection { .. } => {
-                println!("Incoming connection");
-                Some(NetworkEvent::None)
+            SwarmEvent::IncomingConnection { send_back_addr, .. } => {
+                debug!(self.log, "Incoming connection from {:?}", send_back_addr);
+                NetworkEvent::None
             }
             SwarmEvent::IncomingConnectionError { error, .. } => {
-                println!("Incoming connection error: {:?}", error);
-                Some(NetworkEvent::None)
+                debug!(self.log, "Incoming connection failed: {}", error);
+                NetworkEvent::None
+            }
+            SwarmEvent::BannedPeer { peer_id, .. } => {
+                self.end_requests_of(peer_id);
+                warn!(self.log, "Connection to banned peer {:?} refused", peer_id);
+                NetworkEvent::PeerDisconnected(peer_id)
+            }
+            SwarmEvent::ExpiredListenAddr { address, .. } => {
+                debug!(self.log, "Listen address {:?} expired", address);
+                NetworkEvent::None
             }
-            SwarmEvent::BannedPeer { peer_id, endpoint } => todo!(),
-            SwarmEvent::ExpiredListenAddr {
-                listener_id,
-                address,
-            } => todo!(),
             SwarmEvent::ListenerClosed {
-                listener_id,
-                addresses,
-                reason,
-            } => todo!(),
+                addresses, reason, ..
+            } => {
+                warn!(self.log, "Listener on {:?} closed: {:?}", addresses, reason);
+                NetworkEvent::None
+            }
             SwarmEvent::ListenerError { error, .. } => {
-                println!("Listener error {:?}", error);
-                Some(NetworkEvent::None)
+                warn!(self.log, "Listener error: {}", error);
+                NetworkEvent::None
             }
-            SwarmEvent::Dialing(_) => {
-                println!("Dialing");
-                Some(NetworkEvent::None)
+            SwarmEvent::Dialing(peer_id) => {
+                debug!(self.log, "Dialing {:?}", peer_id);
+                NetworkEvent::None
             }
         }
     }
 
-    async fn poll_network(&self) -> Option<NetworkEvent> {
-        let (r, _) = join!(self.poll_swarm(), self.poll_requests());
-        r
+    /// Answers a request of a peer. The status of a peer is checked like the one it answers
+    /// ours with, and its goodbye closes its request channel.
+    fn on_request(
+        &mut self,
+        peer_id: PeerId,
+        id: (ConnectionId, SubstreamId),
+        request: InboundRequest<E>,
+    ) -> NetworkEvent<E> {
+        let response = match request {
+            InboundRequest::Status(status) => {
+                self.swarm.behaviour_mut().rpc.send_response(
+                    peer_id,
+                    id,
+                    RPCCodedResponse::Success(RPCResponse::Status(self.local_status.clone())),
+                );
+
+                return self.on_status(peer_id, status);
+            }
+            InboundRequest::Goodbye(reason) => {
+                info!(self.log, "Goodbye from {:?}: {}", peer_id, reason);
+                self.end_requests_of(peer_id);
+
+                return NetworkEvent::PeerDisconnected(peer_id);
+            }
+            // We hold no blocks, the requested ranges are empty
+            InboundRequest::BlocksByRange(_) => {
+                RPCCodedResponse::StreamTermination(ResponseTermination::BlocksByRange)
+            }
+            InboundRequest::BlocksByRoot(_) => {
+                RPCCodedResponse::StreamTermination(ResponseTermination::BlocksByRoot)
+            }
+            InboundRequest::Ping(_) => RPCCodedResponse::Success(RPCResponse::Pong(Ping {
+                data: *self.local_metadata.seq_number(),
+            })),
+            InboundRequest::MetaData(_) => {
+                RPCCodedResponse::Success(RPCResponse::MetaData(self.local_metadata.clone()))
+            }
+        };
+
+        self.swarm
+            .behaviour_mut()
+            .rpc
+            .send_response(peer_id, id, response);
+
+        NetworkEvent::None
     }
 
-    fn build_transport(local_private_key: Keypair) -> Result<BoxedTransport, String> {
+    /// Checks the status a peer answered ours with. Peers on another fork can't serve our
+    /// requests, they are disconnected.
+    fn on_status(&mut self, peer_id: PeerId, status: StatusMessage) -> NetworkEvent<E> {
+        if status.fork_digest != self.local_status.fork_digest {
+            warn!(
+                self.log,
+                "Disconnecting from {:?}, on fork {:?}", peer_id, status.fork_digest
+            );
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+
+            return NetworkEvent::None;
+        }
+
+        info!(
+            self.log,
+            "Status of {:?}: head slot {}, finalized epoch {}",
+            peer_id,
+            status.head_slot,
+            status.finalized_epoch
+        );
+
+        NetworkEvent::PeerReady { peer_id, status }
+    }
+
+    /// Decodes a gossip message. We don't validate the messages, so they are never forwarded
+    /// to other peers.
+    fn on_gossip(
+        &mut self,
+        source: PeerId,
+        id: MessageId,
+        message: GossipsubMessage,
+    ) -> NetworkEvent<E> {
+        let decoded = PubsubMessage::decode(&message.topic, &message.data, &self.fork_context);
+        let acceptance = match decoded {
+            Ok(_) => MessageAcceptance::Ignore,
+            Err(_) => MessageAcceptance::Reject,
+        };
+
+        let _ = self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .report_message_validation_result(&id, &source, acceptance);
+
+        match decoded {
+            Ok(message) => NetworkEvent::Gossip(message),
+            Err(err) => {
+                debug!(
+                    self.log,
+                    "Invalid message on {} from {:?}: {}", message.topic, source, err
+                );
+                NetworkEvent::None
+            }
+        }
+    }
+
+    /// Builds the transport of the service, also used by the peers stubbed in the tests.
+    pub fn build_transport(local_private_key: Keypair) -> Result<BoxedTransport, String> {
         let tcp = TokioTcpConfig::new().nodelay(true);
-        let transport = TokioDnsConfig::system(tcp).map_err(|err| err.to_string());
+        let transport = TokioDnsConfig::system(tcp).map_err(|err| err.to_string())?;
 
-        let (transport, _) = BandwidthLogging::new(transport.unwrap());
+        let (transport, _) = BandwidthLogging::new(transport);
 
         // mplex config
         let mut mplex_config = libp2p::mplex::MplexConfig::new();
@@ -254,18 +749,23 @@ impl NetworkService {
     }
 }
 
-impl Stream for NetworkService {
-    type Item = NetworkEvent;
+impl<E: EthSpec> Stream for NetworkService<E> {
+    type Item = NetworkEvent<E>;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let future = self.poll_network();
-        if let Poll::Ready(p) = Box::pin(future).poll_unpin(cx) {
-            match p {
-                Some(p) => Poll::Ready(Some(p)),
-                None => Poll::Pending,
-            }
-        } else {
-            Poll::Pending
+    /// Hands the queued requests to the swarm, then polls it for its next event. Nothing is
+    /// awaited, so that no request nor event is lost when the caller stops polling.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let service = &mut *self;
+
+        while let Poll::Ready(Some((peer_id, queued))) = service.request_handler.poll_next_unpin(cx)
+        {
+            service.send_request(peer_id, queued);
+        }
+
+        match service.swarm.poll_next_unpin(cx) {
+            Poll::Ready(Some(event)) => Poll::Ready(Some(service.on_swarm_event(event))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
         }
     }
 }
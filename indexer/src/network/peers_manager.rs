@@ -1,55 +1,306 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
-use super::network_service::NetworkService;
+use super::{discovery::Discovery, network_service::NetworkService};
 use eth2::lighthouse::Peer;
 use libp2p::{Multiaddr, PeerId};
-use rand::{seq::IteratorRandom, thread_rng};
-use store::MainnetEthSpec;
-use tokio::sync::Mutex;
+use lighthouse_network::discv5::Enr;
+use ordered_float::OrderedFloat;
+use rand::{seq::SliceRandom, thread_rng};
+use slog::{debug, info, warn, Logger};
+use store::EthSpec;
 
-type PeerMap<'a> = HashMap<PeerId, &'a Multiaddr>;
+/// Number of peers we keep connections to
+const TARGET_PEERS: usize = 10;
+/// Number of requests a peer is sent before its failure rate is taken into account
+const MIN_REQUESTS: u32 = 4;
+/// Failure rate above which a peer is dropped for good
+const MAX_FAILURE_RATE: f64 = 0.5;
+/// Interval between two checks of the number of connected peers
+pub const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(30);
 
-pub struct PeersManager<'a> {
-    elligible_peers: PeerMap<'a>,
-    connected_peers: PeerMap<'a>,
+/// Outcome of the requests sent to a peer
+#[derive(Default)]
+struct PeerScore {
+    requests: u32,
+    failures: u32,
+    /// Sum of the times the successful requests took to be served
+    total_latency: Duration,
 }
 
-impl<'a> PeersManager<'a> {
+impl PeerScore {
+    fn failure_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+
+    fn mean_latency(&self) -> Duration {
+        match self.requests - self.failures {
+            0 => Duration::ZERO,
+            successes => self.total_latency / successes,
+        }
+    }
+
+    fn is_misbehaving(&self) -> bool {
+        self.requests >= MIN_REQUESTS && self.failure_rate() > MAX_FAILURE_RATE
+    }
+
+    /// Key ordering the peers from the best to the worst. Peers never sent a request come first.
+    fn rank(&self) -> (OrderedFloat<f64>, Duration) {
+        (OrderedFloat(self.failure_rate()), self.mean_latency())
+    }
+}
+
+/// Keeps connections to `TARGET_PEERS` peers. Peers which disconnect are replaced by the best
+/// ones of the eligible pool, which is refilled through discovery when it runs low. Peers failing
+/// too many requests are banned.
+pub struct PeersManager {
+    eligible_peers: HashMap<PeerId, Multiaddr>,
+    /// Peers connected or being dialed
+    connected_peers: HashMap<PeerId, Multiaddr>,
+    banned_peers: HashSet<PeerId>,
+    scores: HashMap<PeerId, PeerScore>,
+    discovery: Option<Discovery>,
+    log: Logger,
+}
+
+impl PeersManager {
+    /// Creates a manager discovering peers on one of `fork_digests` from `boot_enrs`, if any.
+    /// Without boot nodes, the peers have to be added with `add_node_peers`.
     pub async fn new(
-        peers: &'a [Peer<MainnetEthSpec>],
-        network_manager: Arc<Mutex<NetworkService>>,
-    ) -> PeersManager<'a> {
-        let mut rng = thread_rng();
-        let elligible_peers = peers
-            .iter()
-            .map(|p| {
-                (
-                    p.peer_id.parse::<PeerId>().unwrap(),
-                    p.peer_info.listening_addresses().first().unwrap(),
-                )
-            })
-            .collect::<PeerMap>();
-
-        let peers_to_connect = elligible_peers
-            .iter()
-            .map(|(p, _)| *p)
-            .choose_multiple(&mut rng, 10);
-
-        let (elligible_peers, peers_to_connect) = elligible_peers
-            .into_iter()
-            .partition::<PeerMap, _>(|(p, _)| peers_to_connect.contains(p));
-
-        for (peer_id, multiaddr) in &peers_to_connect {
-            network_manager
-                .lock()
-                .await
-                .connect(*peer_id, multiaddr)
-                .await;
+        boot_enrs: Vec<Enr>,
+        fork_digests: Vec<[u8; 4]>,
+        log: Logger,
+    ) -> Result<Self, String> {
+        let discovery = if boot_enrs.is_empty() {
+            None
+        } else {
+            Some(Discovery::new(boot_enrs, fork_digests).await?)
+        };
+
+        Ok(PeersManager {
+            eligible_peers: HashMap::new(),
+            connected_peers: HashMap::new(),
+            banned_peers: HashSet::new(),
+            scores: HashMap::new(),
+            discovery,
+            log,
+        })
+    }
+
+    /// Adds the peers of a Lighthouse node to the eligible pool. Those without a listening
+    /// address can't be dialed, they are skipped.
+    pub fn add_node_peers<E: EthSpec>(&mut self, peers: &[Peer<E>]) {
+        for peer in peers {
+            let peer_id = match peer.peer_id.parse::<PeerId>() {
+                Ok(peer_id) => peer_id,
+                Err(err) => {
+                    debug!(self.log, "Invalid peer id {}: {:?}", peer.peer_id, err);
+                    continue;
+                }
+            };
+
+            match peer.peer_info.listening_addresses().first() {
+                Some(multiaddr) => self.add_peer(peer_id, multiaddr.clone()),
+                None => debug!(self.log, "Peer {:?} has no listening address", peer_id),
+            }
         }
+    }
+
+    /// Adds a peer to the eligible pool, unless already connected or banned.
+    pub fn add_peer(&mut self, peer_id: PeerId, multiaddr: Multiaddr) {
+        if !self.connected_peers.contains_key(&peer_id) && !self.banned_peers.contains(&peer_id) {
+            self.eligible_peers.insert(peer_id, multiaddr);
+        }
+    }
+
+    /// Connects to eligible peers until `TARGET_PEERS` are connected, the best ranked first.
+    /// The pool is refilled through discovery beforehand if it can't make up for the missing
+    /// connections.
+    pub async fn maintain<E: EthSpec>(&mut self, service: &mut NetworkService<E>) {
+        let missing = TARGET_PEERS.saturating_sub(self.connected_peers.len());
+
+        if missing == 0 {
+            return;
+        }
+
+        if self.eligible_peers.len() < missing {
+            self.discover().await;
+        }
+
+        let mut candidates = self.eligible_peers.keys().copied().collect::<Vec<_>>();
+        candidates.shuffle(&mut thread_rng());
+        candidates.sort_by_key(|peer_id| {
+            self.scores
+                .get(peer_id)
+                .map(PeerScore::rank)
+                .unwrap_or((OrderedFloat(0.0), Duration::ZERO))
+        });
+
+        for peer_id in candidates.into_iter().take(missing) {
+            let multiaddr = match self.eligible_peers.remove(&peer_id) {
+                Some(multiaddr) => multiaddr,
+                None => continue,
+            };
+
+            match service.connect(peer_id, &multiaddr) {
+                Ok(()) => {
+                    self.connected_peers.insert(peer_id, multiaddr);
+                }
+                Err(err) => warn!(self.log, "Unable to connect to {:?}: {}", peer_id, err),
+            }
+        }
+    }
+
+    async fn discover(&mut self) {
+        let peers = match &self.discovery {
+            Some(discovery) => discovery.find_peers().await,
+            None => return,
+        };
+
+        match peers {
+            Ok(peers) => {
+                info!(self.log, "Discovered {} peers", peers.len());
 
+                for (peer_id, multiaddr) in peers {
+                    self.add_peer(peer_id, multiaddr);
+                }
+            }
+            Err(err) => warn!(self.log, "{}", err),
+        }
+    }
+
+    /// Frees the slot of a peer which disconnected or couldn't be reached. Unless banned, the
+    /// peer goes back to the eligible pool with its score.
+    pub async fn on_disconnected<E: EthSpec>(
+        &mut self,
+        service: &mut NetworkService<E>,
+        peer_id: PeerId,
+    ) {
+        service.disconnect(peer_id);
+
+        if let Some(multiaddr) = self.connected_peers.remove(&peer_id) {
+            self.add_peer(peer_id, multiaddr);
+        }
+    }
+
+    pub fn record_success(&mut self, peer_id: PeerId, latency: Duration) {
+        let score = self.scores.entry(peer_id).or_default();
+        score.requests += 1;
+        score.total_latency += latency;
+    }
+
+    /// Records a failed request, and returns whether the peer is now banned for failing too
+    /// many of them. A banned peer has to be disconnected.
+    pub fn record_failure(&mut self, peer_id: PeerId) -> bool {
+        let score = self.scores.entry(peer_id).or_default();
+        score.requests += 1;
+        score.failures += 1;
+
+        if score.is_misbehaving() {
+            warn!(
+                self.log,
+                "Banning {:?}, {} of its {} requests failed",
+                peer_id,
+                score.failures,
+                score.requests
+            );
+            self.banned_peers.insert(peer_id);
+            self.eligible_peers.remove(&peer_id);
+        }
+
+        self.banned_peers.contains(&peer_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        time::Duration,
+    };
+
+    use libp2p::PeerId;
+    use slog::{o, Discard, Logger};
+
+    use super::{PeerScore, PeersManager, MIN_REQUESTS};
+
+    fn peers_manager() -> PeersManager {
         PeersManager {
-            elligible_peers,
-            connected_peers: peers_to_connect,
+            eligible_peers: HashMap::new(),
+            connected_peers: HashMap::new(),
+            banned_peers: HashSet::new(),
+            scores: HashMap::new(),
+            discovery: None,
+            log: Logger::root(Discard, o!()),
+        }
+    }
+
+    #[test]
+    fn no_ban_before_min_requests() {
+        let mut peers_manager = peers_manager();
+        let peer_id = PeerId::random();
+
+        for _ in 1..MIN_REQUESTS {
+            assert!(!peers_manager.record_failure(peer_id));
+        }
+        assert!(peers_manager.record_failure(peer_id));
+    }
+
+    #[test]
+    fn ban_above_max_failure_rate() {
+        let mut peers_manager = peers_manager();
+        let peer_id = PeerId::random();
+
+        peers_manager.record_success(peer_id, Duration::from_secs(1));
+        peers_manager.record_success(peer_id, Duration::from_secs(1));
+        assert!(!peers_manager.record_failure(peer_id));
+        // Half of the requests failed, the rate has to be exceeded
+        assert!(!peers_manager.record_failure(peer_id));
+        assert!(peers_manager.record_failure(peer_id));
+        assert!(peers_manager.banned_peers.contains(&peer_id));
+    }
+
+    #[test]
+    fn banned_peer_not_eligible_again() {
+        let mut peers_manager = peers_manager();
+        let peer_id = PeerId::random();
+
+        for _ in 0..MIN_REQUESTS {
+            peers_manager.record_failure(peer_id);
         }
+        peers_manager.add_peer(peer_id, "/ip4/127.0.0.1/tcp/9000".parse().unwrap());
+        assert!(peers_manager.eligible_peers.is_empty());
+    }
+
+    #[test]
+    fn rank_order() {
+        let unknown = PeerScore::default();
+        let fast = PeerScore {
+            requests: 2,
+            failures: 0,
+            total_latency: Duration::from_secs(2),
+        };
+        let slow = PeerScore {
+            requests: 2,
+            failures: 0,
+            total_latency: Duration::from_secs(10),
+        };
+        let failing = PeerScore {
+            requests: 2,
+            failures: 1,
+            total_latency: Duration::from_millis(1),
+        };
+
+        assert!(unknown.rank() < fast.rank());
+        assert!(fast.rank() < slow.rank());
+        assert!(slow.rank() < failing.rank());
+        assert_eq!(slow.mean_latency(), Duration::from_secs(5));
+        assert_eq!(failing.mean_latency(), Duration::from_millis(1));
     }
 }
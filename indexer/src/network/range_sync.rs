@@ -0,0 +1,362 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::Instant,
+};
+
+use futures::{stream::FuturesUnordered, Stream, StreamExt};
+use lighthouse_network::{PeerId, PubsubMessage};
+use slog::warn;
+use store::{EthSpec, SignedBeaconBlock};
+
+use super::{
+    network_service::{NetworkEvent, NetworkService},
+    peers_manager::{PeersManager, MAINTENANCE_INTERVAL},
+};
+
+/// Number of slots requested at once from a peer
+const BATCH_SIZE: u64 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Batch {
+    start_slot: u64,
+    count: u64,
+}
+
+/// Peer having answered our status, with the batch it is requested, if any
+struct SyncPeer {
+    /// Slot following the head of the peer
+    end_slot: u64,
+    batch: Option<Batch>,
+}
+
+/// Splits the slots from a start slot up to the heads of the peers into batches, and
+/// spreads them over the peers ready to serve them.
+struct RangeSync<E: EthSpec> {
+    peers: HashMap<PeerId, SyncPeer>,
+    /// Batches to request again, their peer having failed to serve them
+    failed: BTreeSet<Batch>,
+    /// Start slot of the first batch never requested
+    next_slot: u64,
+    /// Blocks of the retrieved batches which aren't yielded yet, by start slot
+    retrieved: BTreeMap<u64, (Batch, Vec<SignedBeaconBlock<E>>)>,
+    /// Start slot of the next batch to yield
+    next_yielded_slot: u64,
+}
+
+impl<E: EthSpec> RangeSync<E> {
+    fn new(start_slot: u64) -> Self {
+        RangeSync {
+            peers: HashMap::new(),
+            failed: BTreeSet::new(),
+            next_slot: start_slot,
+            retrieved: BTreeMap::new(),
+            next_yielded_slot: start_slot,
+        }
+    }
+
+    /// Adds a peer, or updates its head slot if it sent its status again.
+    fn add_peer(&mut self, peer_id: PeerId, end_slot: u64) {
+        self.peers
+            .entry(peer_id)
+            .and_modify(|peer| peer.end_slot = end_slot)
+            .or_insert(SyncPeer {
+                end_slot,
+                batch: None,
+            });
+    }
+
+    /// Removes a peer, its pending batch being requested again from another one.
+    fn remove_peer(&mut self, peer_id: &PeerId) {
+        if let Some(batch) = self.peers.remove(peer_id).and_then(|peer| peer.batch) {
+            self.failed.insert(batch);
+        }
+    }
+
+    /// Frees the peer from its pending batch, which is requested again from another one.
+    fn release(&mut self, peer_id: &PeerId) {
+        if let Some(batch) = self
+            .peers
+            .get_mut(peer_id)
+            .and_then(|peer| peer.batch.take())
+        {
+            self.failed.insert(batch);
+        }
+    }
+
+    fn complete(&mut self, peer_id: &PeerId, blocks: Vec<SignedBeaconBlock<E>>) {
+        if let Some(batch) = self
+            .peers
+            .get_mut(peer_id)
+            .and_then(|peer| peer.batch.take())
+        {
+            self.retrieved.insert(batch.start_slot, (batch, blocks));
+        }
+    }
+
+    /// Assigns a batch to each idle peer, the failed ones first, and returns the assignments.
+    fn assign(&mut self) -> Vec<(PeerId, Batch)> {
+        let mut assignments = Vec::new();
+
+        for (peer_id, peer) in self.peers.iter_mut().filter(|(_, x)| x.batch.is_none()) {
+            let failed = self
+                .failed
+                .iter()
+                .find(|x| x.start_slot + x.count <= peer.end_slot)
+                .copied();
+
+            let batch = match failed {
+                Some(batch) => {
+                    self.failed.remove(&batch);
+                    batch
+                }
+                None if self.next_slot < peer.end_slot => {
+                    let batch = Batch {
+                        start_slot: self.next_slot,
+                        count: BATCH_SIZE.min(peer.end_slot - self.next_slot),
+                    };
+                    self.next_slot += batch.count;
+                    batch
+                }
+                None => continue,
+            };
+
+            peer.batch = Some(batch);
+            assignments.push((*peer_id, batch));
+        }
+
+        assignments
+    }
+
+    /// Returns the blocks of the retrieved batches following the last yielded one, in order of
+    /// slot.
+    fn take_blocks(&mut self) -> Vec<SignedBeaconBlock<E>> {
+        let mut blocks = Vec::new();
+
+        while let Some((batch, batch_blocks)) = self.retrieved.remove(&self.next_yielded_slot) {
+            blocks.extend(batch_blocks);
+            self.next_yielded_slot += batch.count;
+        }
+
+        blocks
+    }
+
+    /// Returns whether all the slots up to the heads of the peers were retrieved.
+    fn is_done(&self) -> bool {
+        !self.peers.is_empty()
+            && self.failed.is_empty()
+            && self.retrieved.is_empty()
+            && self.peers.values().all(|x| x.batch.is_none())
+            && self.peers.values().all(|x| x.end_slot <= self.next_slot)
+    }
+}
+
+/// Retrieves the blocks from `start_slot` up to the head from the peers the manager connects to,
+/// by sending them `BlocksByRange` requests once they answered our status. The batch of a failed
+/// request is requested from another peer, the peers failing too many of them being
+/// disconnected. The blocks are yielded in order of slot, followed by those gossiped during the
+/// sync beyond the heads of the peers.
+pub fn blocks_by_range<'a, E: EthSpec>(
+    service: &'a mut NetworkService<E>,
+    peers_manager: &'a mut PeersManager,
+    start_slot: u64,
+) -> impl Stream<Item = SignedBeaconBlock<E>> + 'a {
+    async_stream::stream! {
+        let log = service.log().clone();
+        let mut sync = RangeSync::<E>::new(start_slot);
+        let mut requests = FuturesUnordered::new();
+        let mut maintenance = tokio::time::interval(MAINTENANCE_INTERVAL);
+        // Blocks published during the sync, by slot
+        let mut gossiped = BTreeMap::new();
+
+        while !sync.is_done() {
+            tokio::select! {
+                _ = maintenance.tick() => {
+                    if let Err(err) = service.update_fork() {
+                        warn!(log, "{}", err);
+                    }
+                    peers_manager.maintain(service).await;
+                }
+                event = service.next() => match event {
+                    Some(NetworkEvent::PeerReady { peer_id, status }) => {
+                        sync.add_peer(peer_id, status.head_slot.as_u64() + 1);
+                    }
+                    Some(NetworkEvent::PeerDisconnected(peer_id)) => {
+                        sync.remove_peer(&peer_id);
+                        peers_manager.on_disconnected(service, peer_id).await;
+                        peers_manager.maintain(service).await;
+                    }
+                    Some(NetworkEvent::Gossip(PubsubMessage::BeaconBlock(block))) => {
+                        gossiped.insert(block.slot().as_u64(), (*block).clone());
+                    }
+                    // The operations are included in the blocks
+                    Some(NetworkEvent::Gossip(_)) | Some(NetworkEvent::None) => {}
+                    None => break,
+                },
+                Some((peer_id, result, sent_at)) = requests.next(), if !requests.is_empty() => {
+                    match result {
+                        Ok(blocks) => {
+                            peers_manager.record_success(peer_id, sent_at.elapsed());
+                            sync.complete(&peer_id, blocks);
+                        }
+                        Err(err) => {
+                            warn!(log, "Request to {:?} failed: {}", peer_id, err);
+                            sync.release(&peer_id);
+
+                            if peers_manager.record_failure(peer_id) {
+                                sync.remove_peer(&peer_id);
+                                peers_manager.on_disconnected(service, peer_id).await;
+                                peers_manager.maintain(service).await;
+                            }
+                        }
+                    }
+                }
+            }
+
+            for (peer_id, batch) in sync.assign() {
+                let blocks = service.blocks_by_range(peer_id, batch.start_slot, batch.count);
+                let sent_at = Instant::now();
+                requests.push(async move { (peer_id, blocks.await, sent_at) });
+            }
+
+            for block in sync.take_blocks() {
+                yield block;
+            }
+        }
+
+        for (slot, block) in gossiped {
+            if slot >= sync.next_yielded_slot {
+                yield block;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lighthouse_network::PeerId;
+    use store::{BeaconBlock, EthSpec, MainnetEthSpec, Signature, SignedBeaconBlock, Slot};
+
+    use super::{Batch, RangeSync, BATCH_SIZE};
+
+    type E = MainnetEthSpec;
+
+    fn blocks(batch: Batch) -> Vec<SignedBeaconBlock<E>> {
+        (batch.start_slot..batch.start_slot + batch.count)
+            .map(|slot| {
+                let mut block = BeaconBlock::empty(&E::default_spec());
+                *block.slot_mut() = Slot::new(slot);
+                SignedBeaconBlock::from_block(block, Signature::empty())
+            })
+            .collect()
+    }
+
+    fn slots(blocks: &[SignedBeaconBlock<E>]) -> Vec<u64> {
+        blocks.iter().map(|x| x.slot().as_u64()).collect()
+    }
+
+    #[test]
+    fn batches_up_to_end_slot() {
+        let mut sync = RangeSync::<E>::new(10);
+        let peer_id = PeerId::random();
+        sync.add_peer(peer_id, 100);
+
+        let first = Batch {
+            start_slot: 10,
+            count: BATCH_SIZE,
+        };
+        assert_eq!(sync.assign(), vec![(peer_id, first)]);
+        // A peer is requested a single batch at once
+        assert!(sync.assign().is_empty());
+
+        sync.complete(&peer_id, blocks(first));
+        let last = Batch {
+            start_slot: 10 + BATCH_SIZE,
+            count: 100 - 10 - BATCH_SIZE,
+        };
+        assert_eq!(sync.assign(), vec![(peer_id, last)]);
+        assert!(!sync.is_done());
+
+        sync.complete(&peer_id, blocks(last));
+        assert!(sync.assign().is_empty());
+        assert!(!sync.is_done());
+        assert_eq!(slots(&sync.take_blocks()), (10..100).collect::<Vec<_>>());
+        assert!(sync.is_done());
+    }
+
+    #[test]
+    fn failed_batch_assigned_again() {
+        let mut sync = RangeSync::<E>::new(0);
+        let failing = PeerId::random();
+        let serving = PeerId::random();
+        sync.add_peer(failing, 2 * BATCH_SIZE);
+        sync.add_peer(serving, 2 * BATCH_SIZE);
+
+        let assignments = sync.assign();
+        assert_eq!(assignments.len(), 2);
+        let failed_batch = assignments
+            .iter()
+            .find(|(peer_id, _)| *peer_id == failing)
+            .map(|(_, batch)| *batch)
+            .unwrap();
+        let served_batch = assignments
+            .iter()
+            .find(|(peer_id, _)| *peer_id == serving)
+            .map(|(_, batch)| *batch)
+            .unwrap();
+
+        sync.remove_peer(&failing);
+        sync.complete(&serving, blocks(served_batch));
+        assert_eq!(sync.assign(), vec![(serving, failed_batch)]);
+
+        sync.complete(&serving, blocks(failed_batch));
+        assert_eq!(
+            slots(&sync.take_blocks()),
+            (0..2 * BATCH_SIZE).collect::<Vec<_>>()
+        );
+        assert!(sync.is_done());
+    }
+
+    #[test]
+    fn released_batch_assigned_again() {
+        let mut sync = RangeSync::<E>::new(0);
+        let peer_id = PeerId::random();
+        sync.add_peer(peer_id, BATCH_SIZE);
+
+        let assignments = sync.assign();
+        sync.release(&peer_id);
+        assert_eq!(sync.assign(), assignments);
+    }
+
+    #[test]
+    fn blocks_taken_in_order() {
+        let mut sync = RangeSync::<E>::new(0);
+        let first_peer = PeerId::random();
+        let second_peer = PeerId::random();
+        sync.add_peer(first_peer, 2 * BATCH_SIZE);
+        sync.add_peer(second_peer, 2 * BATCH_SIZE);
+
+        let assignments = sync.assign();
+        let (first, second) = if assignments[0].1.start_slot == 0 {
+            (assignments[0], assignments[1])
+        } else {
+            (assignments[1], assignments[0])
+        };
+
+        // The second batch is held until the first one is retrieved
+        sync.complete(&second.0, blocks(second.1));
+        assert!(sync.take_blocks().is_empty());
+
+        sync.complete(&first.0, blocks(first.1));
+        assert_eq!(
+            slots(&sync.take_blocks()),
+            (0..2 * BATCH_SIZE).collect::<Vec<_>>()
+        );
+        assert!(sync.take_blocks().is_empty());
+    }
+
+    #[test]
+    fn not_done_without_peers() {
+        let sync = RangeSync::<E>::new(0);
+        assert!(!sync.is_done());
+    }
+}
@@ -1,32 +1,46 @@
 use std::{
     collections::HashMap,
     pin::{self, Pin},
-    sync::Arc,
     task::{Context, Poll},
 };
 
-use futures::{Stream, StreamExt};
+use futures::Stream;
 use libp2p::PeerId;
-use lighthouse_network::{rpc::outbound::OutboundRequest, Request};
+use lighthouse_network::{
+    rpc::{methods::RPCResponse, outbound::OutboundRequest},
+    Request,
+};
 use pin_project::pin_project;
-use store::MainnetEthSpec;
+use store::EthSpec;
 use tokio::sync::{
     mpsc::{self, UnboundedSender},
-    Mutex, MutexGuard,
+    oneshot,
 };
 use tokio_stream::StreamMap;
 
-type RequestStream = Pin<Box<dyn Stream<Item = OutboundRequest<MainnetEthSpec>> + Send>>;
-type RequestStreamMap = StreamMap<PeerId, RequestStream>;
+use crate::errors::RequestError;
+
+/// Sender of the responses to a request, or of the error ending it
+pub type ResponseSender<E> = oneshot::Sender<Result<Vec<RPCResponse<E>>, RequestError>>;
+
+/// Request waiting to be sent to a peer. The responses to a request without id and sender are
+/// handled by the network service itself.
+pub struct QueuedRequest<E: EthSpec> {
+    pub request: OutboundRequest<E>,
+    pub response: Option<(usize, ResponseSender<E>)>,
+}
+
+type RequestStream<E> = Pin<Box<dyn Stream<Item = QueuedRequest<E>> + Send>>;
+type RequestStreamMap<E> = StreamMap<PeerId, RequestStream<E>>;
 
 #[pin_project]
-pub struct RequestHandler {
-    pending_channels: HashMap<PeerId, RequestStream>,
+pub struct RequestHandler<E: EthSpec> {
+    pending_channels: HashMap<PeerId, RequestStream<E>>,
     #[pin]
-    streams: RequestStreamMap,
+    streams: RequestStreamMap<E>,
 }
 
-impl RequestHandler {
+impl<E: EthSpec> RequestHandler<E> {
     pub fn new() -> Self {
         RequestHandler {
             pending_channels: HashMap::new(),
@@ -37,26 +51,29 @@ impl RequestHandler {
     pub fn create_channel(
         &mut self,
         peer_id: PeerId,
-    ) -> Result<UnboundedSender<OutboundRequest<MainnetEthSpec>>, String> {
+    ) -> Result<UnboundedSender<QueuedRequest<E>>, String> {
         if self.pending_channels.contains_key(&peer_id) {
             return Err("A channel has already been created for this peer".to_string());
         }
 
-        let (tx, mut rx) = mpsc::unbounded_channel::<OutboundRequest<MainnetEthSpec>>();
+        let (tx, mut rx) = mpsc::unbounded_channel::<QueuedRequest<E>>();
 
         let rx = Box::pin(async_stream::stream! {
               while let Some(item) = rx.recv().await {
                   yield item;
               }
-        }) as RequestStream;
+        }) as RequestStream<E>;
 
         self.pending_channels.insert(peer_id, rx);
 
         Ok(tx)
     }
 
+    /// Drops the channel of the peer, whether it is activated or not. The requests sent to it
+    /// afterwards fail.
     pub fn close_channel(&mut self, peer_id: PeerId) {
         self.pending_channels.remove(&peer_id);
+        self.streams.remove(&peer_id);
     }
 
     pub fn activate(&mut self, peer_id: PeerId) -> Result<(), String> {
@@ -70,8 +87,8 @@ impl RequestHandler {
     }
 }
 
-impl Stream for RequestHandler {
-    type Item = (PeerId, OutboundRequest<MainnetEthSpec>);
+impl<E: EthSpec> Stream for RequestHandler<E> {
+    type Item = (PeerId, QueuedRequest<E>);
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
@@ -84,33 +101,3 @@ impl Stream for RequestHandler {
         }
     }
 }
-
-pub struct SafeRequestHandler(Arc<Mutex<RequestHandler>>);
-
-impl SafeRequestHandler {
-    pub fn new() -> Self {
-        SafeRequestHandler(Arc::new(Mutex::new(RequestHandler::new())))
-    }
-
-    pub async fn activate(&mut self, peer_id: PeerId) -> Result<(), String> {
-        self.0.lock().await.activate(peer_id)
-    }
-
-    pub async fn close_channel(&mut self, peer_id: PeerId) {
-        self.0.lock().await.close_channel(peer_id)
-    }
-
-    pub async fn next(&mut self) -> Option<(PeerId, OutboundRequest<MainnetEthSpec>)> {
-        self.0.lock().await.next().await
-    }
-
-    pub async fn guard(&self) -> MutexGuard<'_, RequestHandler> {
-        self.0.lock().await
-    }
-}
-
-impl Clone for SafeRequestHandler {
-    fn clone(&self) -> Self {
-        Self(self.0.clone())
-    }
-}
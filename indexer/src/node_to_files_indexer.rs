@@ -1,156 +1,829 @@
-use std::collections::HashMap;
+use std::{
+    cmp::{max, min},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::Range,
+};
 
-use itertools::Itertools;
-use lighthouse_types::MainnetEthSpec;
+use lighthouse_types::{Attestation, EthSpec, Hash256, Slot};
+use shared::utils::clock::Clock;
 use types::{
     attestation::AttestationsModelWithId,
-    block::{BlockExtendedModelWithId, BlockModelWithId, BlocksMeta},
-    committee::CommitteesModelWithId,
-    epoch::{EpochExtendedModelWithId, EpochModelWithId, EpochsMeta},
-    validator::{ValidatorModelWithId, ValidatorsMeta},
+    block::{BlockExtendedModel, BlockExtendedModelWithId, BlockModelWithId, BlocksMeta},
+    committee::{CommitteesModelWithId, SyncCommitteeModel, SyncCommitteeModelWithId},
+    deposit::{DepositModelWithId, DepositsMeta, DepositsModelWithId},
+    duty::{AttestationDutyModel, ProposalDutyModel, SyncDutyModel, ValidatorDutiesModel},
+    epoch::{
+        EpochExtendedModel, EpochExtendedModelWithId, EpochModel, EpochModelWithId,
+        EpochsCheckpoint, EpochsMeta,
+    },
+    graffiti::{EpochGraffitiModel, EpochGraffitiModelWithId},
+    lookup::{to_key, LookupTable},
+    meta::DEFAULT_SORT_CHUNK_SIZE,
+    path::{balance_history_dir, balance_history_path, duties_dir, duties_path, ToPath},
+    slashing::{
+        SlashingModel, SlashingModelWithId, SlashingsMeta, SlashingsModelWithId,
+        ValidatorSlashingsModel, ValidatorSlashingsModelWithId,
+    },
+    validator::{history_chunk, ValidatorBalanceModel, ValidatorModelWithId, ValidatorsMeta},
+    voluntary_exit::{VoluntaryExitModelWithId, VoluntaryExitsMeta, VoluntaryExitsModelWithId},
     vote::VotesModelWithId,
 };
 
 use crate::{
     errors::IndexerError,
-    field_binary_heap::FieldBinaryHeap,
-    persistable::Persistable,
+    layout, lookup,
+    persistable::{encode, read, write_history_record, Persistable, Restorable},
     persistable_fields::{
-        EpochAttestationsCount, EpochAttesterSlashingsCount, EpochDepositsCount,
-        EpochEligibleEther, EpochGlobalParticipationRate, EpochProposerSlashingsCount,
-        EpochVotedEther,
+        BlockSortFields, DepositSortFields, EpochSortFields, PersistableFields,
+        ValidatorSortFields, VoluntaryExitSortFields,
+    },
+    search,
+    storage::{Storage, ROOT},
+    types::{
+        consolidated_block::attesting_indices, consolidated_epoch::ConsolidatedEpoch,
+        consolidated_validator::ConsolidatedValidator,
     },
-    retriever::Retriever,
-    types::{consolidated_epoch::ConsolidatedEpoch, consolidated_validator::ConsolidatedValidator},
 };
 
-pub struct Indexer {
-    epochs: Vec<ConsolidatedEpoch<MainnetEthSpec>>,
-    validators: Vec<ConsolidatedValidator>,
+/// Blocks of the last indexed epoch, kept until the votes they receive from the attestations of
+/// the following epoch are known. So are the duties of its validators, whose attestations can
+/// be included in the blocks of the following epoch.
+struct PendingEpoch<E: EthSpec> {
+    epoch: u64,
+    block_roots_to_slots: HashMap<Hash256, Slot>,
+    extended_blocks: Vec<BlockExtendedModelWithId>,
+    votes: HashMap<Slot, Vec<Attestation<E>>>,
+    duties: BTreeMap<u64, ValidatorDutiesModel>,
 }
 
-impl Indexer {
-    pub fn index(self, base_dir: &str) -> Result<(), IndexerError> {
-        let epochs_dir = format!("{}/epochs", base_dir);
+/// Ids following the last deposit, voluntary exit and slashing in the lists of all of them, that
+/// is their numbers up to some epoch.
+#[derive(Debug, Clone, Copy, Default)]
+struct OperationIds {
+    deposits: u64,
+    voluntary_exits: u64,
+    slashings: u64,
+}
 
-        let epochs = self
-            .epochs
-            .iter()
-            .map(EpochModelWithId::from)
-            .collect::<Vec<_>>();
+/// Number of ids per chunk of the sort indexes of each model. They are recorded in the meta
+/// files, the frontend reads the chunks accordingly.
+#[derive(Debug, Clone, Copy)]
+pub struct SortChunkSizes {
+    pub epochs: usize,
+    pub blocks: usize,
+    pub validators: usize,
+}
 
-        let epochs_extended = self
-            .epochs
-            .iter()
-            .map(EpochExtendedModelWithId::from)
-            .collect::<Vec<_>>();
+impl Default for SortChunkSizes {
+    fn default() -> Self {
+        SortChunkSizes {
+            epochs: DEFAULT_SORT_CHUNK_SIZE,
+            blocks: DEFAULT_SORT_CHUNK_SIZE,
+            validators: DEFAULT_SORT_CHUNK_SIZE,
+        }
+    }
+}
 
-        let all_blocks = self
-            .epochs
-            .into_iter()
-            .flat_map(|x| x.blocks)
-            .collect::<Vec<_>>();
+pub struct Indexer<E: EthSpec, S: Storage> {
+    storage: S,
+    clock: Clock,
+    sort_chunk_sizes: SortChunkSizes,
+    pending: Option<PendingEpoch<E>>,
+    last_epoch: Option<u64>,
+    /// Ids following the operations of the last indexed epoch
+    next_ids: OperationIds,
+    /// Epoch of the last balances appended to the histories of the validators, unknown until an
+    /// epoch is indexed or if appending them failed
+    balances_epoch: Option<u64>,
+    validators_count: Option<usize>,
+    finalized_epoch: u64,
+}
+
+impl<E: EthSpec, S: Storage> Indexer<E, S> {
+    /// Creates an indexer writing to `storage`, laying out the data tree if needed. `clock`
+    /// dates the epochs, it has to be built from the genesis time of the indexed network.
+    pub fn new(storage: S, clock: Clock) -> Result<Self, IndexerError> {
+        layout::create_dirs(&storage)?;
+
+        let finalized_epoch = EpochsCheckpoint::restore(&storage)?
+            .and_then(|checkpoint| checkpoint.finalized_epoch)
+            .unwrap_or(0);
+
+        Ok(Indexer {
+            storage,
+            clock,
+            sort_chunk_sizes: SortChunkSizes::default(),
+            pending: None,
+            last_epoch: None,
+            next_ids: OperationIds::default(),
+            balances_epoch: None,
+            validators_count: None,
+            finalized_epoch,
+        })
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Sets the chunk sizes of the sort indexes built on the next commit. Indexes persisted
+    /// with other chunk sizes are rewritten entirely.
+    pub fn set_sort_chunk_sizes(&mut self, sort_chunk_sizes: SortChunkSizes) {
+        self.sort_chunk_sizes = sort_chunk_sizes;
+    }
+
+    /// Returns the epoch from which the indexing has to resume.
+    ///
+    /// The last persisted epoch is indexed again, as its blocks may receive votes from
+    /// attestations included in the following epoch. So are the epochs which weren't finalized
+    /// when they were indexed, as a reorg may have changed them since. Those of a checkpoint
+    /// without finalized epoch aren't known, only the last persisted epoch is indexed again.
+    pub fn start_epoch(&self) -> Result<u64, IndexerError> {
+        Ok(EpochsCheckpoint::restore(&self.storage)?
+            .map(|checkpoint| {
+                let finalized_epoch = checkpoint.finalized_epoch.unwrap_or(checkpoint.last_epoch);
+                min(checkpoint.last_epoch, finalized_epoch)
+            })
+            .unwrap_or(0))
+    }
+
+    pub fn finalized_epoch(&self) -> u64 {
+        self.finalized_epoch
+    }
+
+    /// Records the finalized checkpoint of the chain. Epochs indexed from now on and older
+    /// than `epoch` won't be indexed again.
+    pub fn set_finalized_epoch(&mut self, epoch: u64) -> Result<(), IndexerError> {
+        self.finalized_epoch = epoch;
+
+        if let Some(last_epoch) = self.last_epoch {
+            EpochsCheckpoint::new(last_epoch, self.finalized_epoch).persist(&self.storage)?;
+        }
+
+        Ok(())
+    }
+
+    /// Persists a retrieved epoch and its blocks right away. Only the votes of its blocks are
+    /// kept until the next epoch is indexed.
+    ///
+    /// An epoch already indexed can be indexed again, the files whose content changed being
+    /// rewritten.
+    pub fn index_epoch(&mut self, epoch: ConsolidatedEpoch<E>) -> Result<(), IndexerError> {
+        let epoch_number = epoch.epoch.as_u64();
+
+        log::info!("Indexing epoch {}", epoch_number);
+
+        let first_ids = self.first_ids(epoch_number)?;
+        let indexed_ids = self.indexed_ids()?;
+
+        let epoch_model = EpochModelWithId::from((&epoch, &self.clock));
+        let mut epoch_extended = EpochExtendedModelWithId::from(&epoch);
+        epoch_extended.model.first_deposit_id = first_ids.deposits;
+        epoch_extended.model.first_voluntary_exit_id = first_ids.voluntary_exits;
+        epoch_extended.model.first_slashing_id = first_ids.slashings;
+
+        EpochSortFields::append(&self.storage, std::slice::from_ref(&epoch_model))?;
+
+        epoch_model.persist(&self.storage)?;
+        epoch_extended.persist(&self.storage)?;
+        self.append_balances(&epoch)?;
 
-        let block_roots_to_slots = all_blocks
+        let block_roots_to_slots = epoch
+            .blocks
             .iter()
             .filter_map(|x| x.block_root.map(|block_root| (block_root, x.slot)))
             .collect::<HashMap<_, _>>();
 
-        let blocks = all_blocks
+        let mut votes = HashMap::<Slot, Vec<Attestation<E>>>::new();
+        let mut duties = Self::duties(&epoch);
+
+        // Votes can only be attributed to the blocks of the previous epoch
+        if let Some(pending) = &self.pending {
+            if pending.epoch + 1 != epoch_number {
+                self.finish_pending()?;
+            }
+        }
+
+        for block in &epoch.blocks {
+            let attestations = match &block.block {
+                Some(beacon_block) => beacon_block.body().attestations(),
+                None => continue,
+            };
+
+            for attestation in attestations.iter() {
+                let root = &attestation.data.beacon_block_root;
+
+                if let Some(slot) = block_roots_to_slots.get(root) {
+                    votes.entry(*slot).or_default().push(attestation.clone());
+                } else if let Some(pending) = &mut self.pending {
+                    if let Some(slot) = pending.block_roots_to_slots.get(root) {
+                        pending
+                            .votes
+                            .entry(*slot)
+                            .or_default()
+                            .push(attestation.clone());
+                    }
+                }
+
+                let attesting_indices =
+                    attesting_indices(block.committees_of(attestation), attestation);
+
+                if attestation.data.slot.epoch(E::slots_per_epoch()) == epoch.epoch {
+                    record_inclusion(&mut duties, &attesting_indices, block.slot);
+                } else if let Some(pending) = &mut self.pending {
+                    record_inclusion(&mut pending.duties, &attesting_indices, block.slot);
+                }
+            }
+        }
+
+        self.finish_pending()?;
+
+        let block_models = epoch
+            .blocks
             .iter()
             .map(BlockModelWithId::from)
             .collect::<Vec<_>>();
 
-        let mut extended_blocks = all_blocks
+        BlockSortFields::append(&self.storage, &block_models)?;
+        block_models.persist(&self.storage)?;
+
+        // Only the roots of the proposed blocks are looked up, an orphaned slot has no block
+        let proposed_slots = epoch
+            .blocks
             .iter()
-            .map(BlockExtendedModelWithId::from)
-            .collect::<Vec<_>>();
+            .filter(|x| x.block.is_some())
+            .map(|x| x.slot)
+            .collect::<HashSet<_>>();
+        lookup::insert(
+            &self.storage,
+            LookupTable::BlockRoots,
+            block_roots_to_slots
+                .iter()
+                .filter(|(_, slot)| proposed_slots.contains(slot))
+                .map(|(root, slot)| (to_key(root.as_bytes()), slot.as_u64())),
+        )?;
+        lookup::insert(
+            &self.storage,
+            LookupTable::StateRoots,
+            epoch.blocks.iter().filter_map(|x| {
+                x.block
+                    .as_ref()
+                    .map(|block| (to_key(block.state_root().as_bytes()), x.slot.as_u64()))
+            }),
+        )?;
 
-        let committees = all_blocks
+        let graffiti = epoch
+            .blocks
             .iter()
-            .map(CommitteesModelWithId::from)
+            .filter_map(|x| x.graffiti_text().map(|text| (text, x.slot.as_u64())))
             .collect::<Vec<_>>();
 
-        let all_attestations = all_blocks
+        // The graffiti of a re-indexed epoch are still those it had when indexed before
+        let previous_graffiti = read::<EpochGraffitiModel>(
+            &self.storage,
+            &EpochGraffitiModelWithId::to_path(ROOT, epoch_number),
+        )?
+        .map(|x| x.graffiti.into_iter().map(|x| x.name).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+        search::insert_graffiti(&self.storage, graffiti.iter().cloned())?;
+        search::insert_graffiti_tokens::<E>(
+            &self.storage,
+            epoch_number,
+            &previous_graffiti,
+            &graffiti,
+        )?;
+        EpochGraffitiModelWithId {
+            id: epoch_number,
+            model: EpochGraffitiModel::from_graffiti(
+                graffiti.iter().map(|(text, _)| text.as_str()),
+            ),
+        }
+        .persist(&self.storage)?;
+
+        if let Some(sync_committee) = &epoch.sync_committee {
+            SyncCommitteeModelWithId {
+                id: epoch.sync_committee_period(),
+                model: SyncCommitteeModel {
+                    validators: sync_committee.to_vec(),
+                },
+            }
+            .persist(&self.storage)?;
+        }
+
+        for block in &epoch.blocks {
+            CommitteesModelWithId::from(block).persist(&self.storage)?;
+            AttestationsModelWithId::from(block).persist(&self.storage)?;
+        }
+
+        // The deposits, the voluntary exits and the slashings are listed both by block and all
+        // together
+        let deposits = epoch
+            .blocks
+            .iter()
+            .map(DepositsModelWithId::from)
+            .collect::<Vec<_>>();
+        let deposit_models = deposits
             .iter()
-            .filter_map(|x| x.block.clone())
-            .flat_map(|x| x.body().attestations().to_vec())
+            .flat_map(|x| x.model.iter().cloned())
+            .zip(first_ids.deposits..)
+            .map(|(model, id)| DepositModelWithId { id, model })
             .collect::<Vec<_>>();
 
-        let attestations = all_blocks
+        DepositSortFields::append(&self.storage, &deposit_models)?;
+        self.next_ids.deposits = first_ids.deposits + deposit_models.len() as u64;
+        deposits.persist(&self.storage)?;
+        deposit_models.persist(&self.storage)?;
+
+        let voluntary_exits = epoch
+            .blocks
             .iter()
-            .map(AttestationsModelWithId::from)
+            .map(VoluntaryExitsModelWithId::from)
             .collect::<Vec<_>>();
+        let voluntary_exit_models = voluntary_exits
+            .iter()
+            .flat_map(|x| x.model.iter().cloned())
+            .zip(first_ids.voluntary_exits..)
+            .map(|(model, id)| VoluntaryExitModelWithId { id, model })
+            .collect::<Vec<_>>();
+
+        VoluntaryExitSortFields::append(&self.storage, &voluntary_exit_models)?;
+        self.next_ids.voluntary_exits =
+            first_ids.voluntary_exits + voluntary_exit_models.len() as u64;
+        voluntary_exits.persist(&self.storage)?;
+        voluntary_exit_models.persist(&self.storage)?;
 
-        let votes = all_attestations
+        let slashings = epoch
+            .blocks
             .iter()
-            .map(|x| {
-                (
-                    block_roots_to_slots.get(&x.data.beacon_block_root),
-                    x.clone(),
-                )
-            })
-            .into_group_map()
-            .into_iter()
-            .filter_map(|x| match x.0 {
-                Some(slot) => Some((slot, x.1)),
-                _ => None,
-            })
-            .map(VotesModelWithId::from)
+            .map(SlashingsModelWithId::from)
             .collect::<Vec<_>>();
+        let slashing_models = slashings
+            .iter()
+            .flat_map(|x| x.model.iter().cloned())
+            .zip(first_ids.slashings..)
+            .map(|(model, id)| SlashingModelWithId { id, model })
+            .collect::<Vec<_>>();
+
+        self.record_slashings(first_ids.slashings..indexed_ids.slashings, &slashing_models)?;
+        self.next_ids.slashings = first_ids.slashings + slashing_models.len() as u64;
+        slashings.persist(&self.storage)?;
+        slashing_models.persist(&self.storage)?;
 
-        extended_blocks
-            .iter_mut()
-            .zip(votes.iter())
-            .for_each(|(ext, votes)| ext.model.votes_count = votes.model.len());
+        // The epochs following a re-indexed one are indexed again after it, so that the
+        // operations it held beyond its new ones are left over
+        self.remove_operations(self.next_ids, indexed_ids)?;
 
-        let validators = self
-            .validators
+        self.pending = Some(PendingEpoch {
+            epoch: epoch_number,
+            block_roots_to_slots,
+            extended_blocks: epoch
+                .blocks
+                .iter()
+                .map(BlockExtendedModelWithId::from)
+                .collect(),
+            votes,
+            duties,
+        });
+
+        EpochsCheckpoint::new(epoch_number, self.finalized_epoch).persist(&self.storage)?;
+        self.last_epoch = Some(epoch_number);
+
+        Ok(())
+    }
+
+    pub fn index_validators(
+        &mut self,
+        validators: Vec<ConsolidatedValidator>,
+    ) -> Result<(), IndexerError> {
+        self.validators_count = Some(validators.len());
+
+        let validator_models = validators
             .iter()
             .map(ValidatorModelWithId::from)
             .collect::<Vec<_>>();
 
-        EpochsMeta::new(epochs.len()).persist(base_dir);
+        ValidatorSortFields::append(&self.storage, &validator_models)?;
+
+        lookup::insert(
+            &self.storage,
+            LookupTable::Pubkeys,
+            validator_models
+                .iter()
+                .map(|x| (to_key(&x.model.pubkey), x.id)),
+        )?;
+
+        // The validators are all retrieved at once, so the lists of indices are complete
+        let mut withdrawal_credentials = BTreeMap::<String, Vec<u64>>::new();
+        for x in &validator_models {
+            withdrawal_credentials
+                .entry(to_key(&x.model.withdrawal_credentials))
+                .or_default()
+                .push(x.id);
+        }
+        lookup::insert(
+            &self.storage,
+            LookupTable::WithdrawalCredentials,
+            withdrawal_credentials,
+        )?;
+
+        validator_models.persist(&self.storage)?;
 
-        FieldBinaryHeap::<EpochAttestationsCount, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
-        FieldBinaryHeap::<EpochDepositsCount, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
-        FieldBinaryHeap::<EpochAttesterSlashingsCount, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
-        FieldBinaryHeap::<EpochProposerSlashingsCount, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
-        FieldBinaryHeap::<EpochEligibleEther, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
-        FieldBinaryHeap::<EpochVotedEther, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
-        FieldBinaryHeap::<EpochGlobalParticipationRate, EpochModelWithId>::from_model(&epochs)
-            .persist(&epochs_dir);
+        Ok(())
+    }
+
+    /// Persists the votes known so far for the last indexed epoch, the metadata and builds the
+    /// sort indexes from the keys appended while indexing.
+    ///
+    /// The indexing can go on afterwards, the files being updated in place on the next commit.
+    pub fn commit(&mut self) -> Result<(), IndexerError> {
+        self.persist_pending()?;
+
+        let chunk_sizes = self.sort_chunk_sizes;
+
+        // The metas are written after the sort indexes, so that they always record the chunk
+        // size of the persisted chunks
+        if let Some(last_epoch) = self.last_epoch {
+            let persisted = EpochsMeta::restore(&self.storage)?.map(|x| x.sort_chunk_size);
+            <EpochSortFields as PersistableFields<EpochModelWithId>>::build(
+                &self.storage,
+                chunk_sizes.epochs,
+                persisted,
+                last_epoch as usize + 1,
+            )?;
+            EpochsMeta::new(last_epoch as usize + 1, chunk_sizes.epochs).persist(&self.storage)?;
+
+            let blocks_count = (last_epoch as usize + 1) * E::slots_per_epoch() as usize;
+            let persisted = BlocksMeta::restore(&self.storage)?.map(|x| x.sort_chunk_size);
+            <BlockSortFields as PersistableFields<BlockModelWithId>>::build(
+                &self.storage,
+                chunk_sizes.blocks,
+                persisted,
+                blocks_count,
+            )?;
+            BlocksMeta::new(blocks_count, chunk_sizes.blocks).persist(&self.storage)?;
+
+            // The deposits and the voluntary exits are listed block after block, their sort
+            // indexes are chunked like those of the blocks. Those left over by a re-indexed
+            // epoch holding fewer of them are dropped.
+            let persisted = DepositsMeta::restore(&self.storage)?.map(|x| x.sort_chunk_size);
+            <DepositSortFields as PersistableFields<DepositModelWithId>>::build(
+                &self.storage,
+                chunk_sizes.blocks,
+                persisted,
+                self.next_ids.deposits as usize,
+            )?;
+            DepositsMeta::new(self.next_ids.deposits as usize, chunk_sizes.blocks)
+                .persist(&self.storage)?;
+
+            let persisted = VoluntaryExitsMeta::restore(&self.storage)?.map(|x| x.sort_chunk_size);
+            <VoluntaryExitSortFields as PersistableFields<VoluntaryExitModelWithId>>::build(
+                &self.storage,
+                chunk_sizes.blocks,
+                persisted,
+                self.next_ids.voluntary_exits as usize,
+            )?;
+            VoluntaryExitsMeta::new(self.next_ids.voluntary_exits as usize, chunk_sizes.blocks)
+                .persist(&self.storage)?;
+
+            SlashingsMeta::new(self.next_ids.slashings as usize).persist(&self.storage)?;
+        }
+
+        let persisted = ValidatorsMeta::restore(&self.storage)?;
+
+        if let Some(count) = self
+            .validators_count
+            .or(persisted.as_ref().map(|x| x.count))
+        {
+            <ValidatorSortFields as PersistableFields<ValidatorModelWithId>>::build(
+                &self.storage,
+                chunk_sizes.validators,
+                persisted.map(|x| x.sort_chunk_size),
+                count,
+            )?;
+            ValidatorsMeta::new(count, chunk_sizes.validators).persist(&self.storage)?;
+        }
+
+        self.storage.flush()?;
+
+        Ok(())
+    }
+
+    /// Returns the ids of the first deposit, of the first voluntary exit and of the first
+    /// slashing of `epoch`, which follow those of the previous epoch. An epoch indexed again keeps
+    /// its ids.
+    fn first_ids(&self, epoch: u64) -> Result<OperationIds, IndexerError> {
+        if self
+            .last_epoch
+            .map_or(false, |last_epoch| last_epoch + 1 == epoch)
+        {
+            return Ok(self.next_ids);
+        }
+
+        let extended = read::<EpochExtendedModel>(
+            &self.storage,
+            &EpochExtendedModelWithId::to_path(ROOT, epoch),
+        )?;
+        if let Some(extended) = extended {
+            return Ok(OperationIds {
+                deposits: extended.first_deposit_id,
+                voluntary_exits: extended.first_voluntary_exit_id,
+                slashings: extended.first_slashing_id,
+            });
+        }
+
+        if epoch == 0 {
+            return Ok(OperationIds::default());
+        }
+
+        let previous_epoch =
+            read::<EpochModel>(&self.storage, &EpochModelWithId::to_path(ROOT, epoch - 1))?;
+        let previous_extended = read::<EpochExtendedModel>(
+            &self.storage,
+            &EpochExtendedModelWithId::to_path(ROOT, epoch - 1),
+        )?;
+
+        Ok(match (previous_epoch, previous_extended) {
+            (Some(model), Some(extended)) => OperationIds {
+                deposits: extended.first_deposit_id + model.deposits_count as u64,
+                voluntary_exits: extended.first_voluntary_exit_id
+                    + extended.voluntary_exits_count as u64,
+                slashings: extended.first_slashing_id
+                    + (model.proposer_slashings_count + model.attester_slashings_count) as u64,
+            },
+            _ => OperationIds::default(),
+        })
+    }
+
+    /// Returns the ids following the deposits, the voluntary exits and the slashings indexed so
+    /// far, committed or not.
+    fn indexed_ids(&self) -> Result<OperationIds, IndexerError> {
+        let deposits = DepositsMeta::restore(&self.storage)?.map_or(0, |x| x.count as u64);
+        let voluntary_exits =
+            VoluntaryExitsMeta::restore(&self.storage)?.map_or(0, |x| x.count as u64);
+        let slashings = SlashingsMeta::restore(&self.storage)?.map_or(0, |x| x.count as u64);
+
+        Ok(OperationIds {
+            deposits: max(deposits, self.next_ids.deposits),
+            voluntary_exits: max(voluntary_exits, self.next_ids.voluntary_exits),
+            slashings: max(slashings, self.next_ids.slashings),
+        })
+    }
+
+    /// Removes the deposits, the voluntary exits and the slashings whose ids are from `from` to
+    /// `to`, their sort keys being dropped on the next commit.
+    fn remove_operations(&self, from: OperationIds, to: OperationIds) -> Result<(), IndexerError> {
+        for id in from.deposits..to.deposits {
+            self.storage
+                .remove(&DepositModelWithId::to_path(ROOT, id))?;
+        }
+        for id in from.voluntary_exits..to.voluntary_exits {
+            self.storage
+                .remove(&VoluntaryExitModelWithId::to_path(ROOT, id))?;
+        }
+        for id in from.slashings..to.slashings {
+            self.storage
+                .remove(&SlashingModelWithId::to_path(ROOT, id))?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the ids of `slashings` to the records of their offenders, replacing the ids of
+    /// `indexed`, those of the slashings indexed before from the first one of `slashings` on.
+    fn record_slashings(
+        &self,
+        indexed: Range<u64>,
+        slashings: &[SlashingModelWithId],
+    ) -> Result<(), IndexerError> {
+        let mut offenders = BTreeMap::<u64, Vec<u64>>::new();
+
+        // The offenders of the replaced slashings may not be those of the new ones
+        for id in indexed.clone() {
+            if let Some(slashing) =
+                read::<SlashingModel>(&self.storage, &SlashingModelWithId::to_path(ROOT, id))?
+            {
+                for offender in slashing.offenders {
+                    offenders.entry(offender).or_default();
+                }
+            }
+        }
+
+        for slashing in slashings {
+            for offender in &slashing.model.offenders {
+                offenders.entry(*offender).or_default().push(slashing.id);
+            }
+        }
+
+        for (validator, ids) in offenders {
+            let path = ValidatorSlashingsModelWithId::to_path(ROOT, validator);
+            let mut model =
+                read::<ValidatorSlashingsModel>(&self.storage, &path)?.unwrap_or_default();
+
+            model.slashings.retain(|id| *id < indexed.start);
+            model.slashings.extend(ids);
+
+            if model.slashings.is_empty() {
+                self.storage.remove(&path)?;
+            } else {
+                ValidatorSlashingsModelWithId {
+                    id: validator,
+                    model,
+                }
+                .persist(&self.storage)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends the balances of the validators at the start of `epoch` to their balance history,
+    /// replacing those recorded if the epoch is indexed again.
+    fn append_balances(&mut self, epoch: &ConsolidatedEpoch<E>) -> Result<(), IndexerError> {
+        let epoch_number = epoch.epoch.as_u64();
+        let chunk = history_chunk(epoch_number);
+
+        self.storage.create_dir(&balance_history_dir(ROOT, chunk))?;
+
+        // The histories only have to be read for the records to replace when the epoch doesn't
+        // follow the last one appended
+        let follows = self
+            .balances_epoch
+            .map_or(false, |balances_epoch| balances_epoch + 1 == epoch_number);
+        self.balances_epoch = None;
+
+        for balance in &epoch.validator_balances {
+            let path = balance_history_path(ROOT, chunk, balance.index);
+            let record = ValidatorBalanceModel {
+                epoch: epoch_number,
+                balance: balance.balance,
+            };
+
+            if follows {
+                self.storage.append(&path, &encode(&path, &record)?)?;
+            } else {
+                write_history_record(
+                    &self.storage,
+                    &path,
+                    epoch_number,
+                    |x: &ValidatorBalanceModel| x.epoch,
+                    &record,
+                )?;
+            }
+        }
+
+        self.balances_epoch = Some(epoch_number);
+
+        Ok(())
+    }
+
+    /// Returns the duties of the validators during `epoch`, their attestations not being
+    /// included yet. The sync committee members are told apart in each block by its sync
+    /// aggregate.
+    fn duties(epoch: &ConsolidatedEpoch<E>) -> BTreeMap<u64, ValidatorDutiesModel> {
+        let epoch_number = epoch.epoch.as_u64();
+        let mut duties = BTreeMap::new();
+
+        for committee in epoch.committees.iter() {
+            for validator in &committee.validators {
+                let duty = AttestationDutyModel {
+                    slot: committee.slot.as_u64(),
+                    committee_index: committee.index,
+                    inclusion_slot: None,
+                };
 
-        epochs.persist(base_dir);
-        epochs_extended.persist(base_dir);
+                duties
+                    .entry(*validator)
+                    .or_insert_with(|| ValidatorDutiesModel::new(epoch_number))
+                    .attestation = Some(duty);
+            }
+        }
+
+        for block in &epoch.blocks {
+            duties
+                .entry(block.proposer)
+                .or_insert_with(|| ValidatorDutiesModel::new(epoch_number))
+                .proposals
+                .push(ProposalDutyModel {
+                    slot: block.slot.as_u64(),
+                    status: block.status.to_string(),
+                });
+        }
+
+        if let Some(sync_committee) = &epoch.sync_committee {
+            let period = epoch.sync_committee_period();
+            let members = sync_committee.iter().copied().collect::<BTreeSet<_>>();
+
+            for validator in &members {
+                duties
+                    .entry(*validator)
+                    .or_insert_with(|| ValidatorDutiesModel::new(epoch_number))
+                    .sync = Some(SyncDutyModel::new(period));
+            }
+
+            for block in &epoch.blocks {
+                let missed = match block.sync_missed() {
+                    Some(missed) => missed,
+                    None => continue,
+                };
+
+                for validator in &members {
+                    if let Some(sync) = duties.get_mut(validator).and_then(|x| x.sync.as_mut()) {
+                        match missed.binary_search(validator) {
+                            Ok(_) => sync.missed_slots.push(block.slot.as_u64()),
+                            Err(_) => sync.signed_slots.push(block.slot.as_u64()),
+                        }
+                    }
+                }
+            }
+        }
+
+        duties
+    }
 
-        BlocksMeta::new(blocks.len()).persist(base_dir);
+    /// Persists the last indexed epoch for good and appends the duties of its validators to
+    /// their history, their attestations having been included by now. The duties recorded if
+    /// the epoch is indexed again are replaced.
+    fn finish_pending(&mut self) -> Result<(), IndexerError> {
+        self.persist_pending()?;
 
-        blocks.persist(base_dir);
-        extended_blocks.persist(base_dir);
-        committees.persist(base_dir);
-        attestations.persist(base_dir);
-        votes.persist(base_dir);
+        if let Some(pending) = self.pending.take() {
+            let chunk = history_chunk(pending.epoch);
 
-        ValidatorsMeta::new(self.validators.len()).persist(base_dir);
+            self.storage.create_dir(&duties_dir(ROOT, chunk))?;
 
-        validators.persist(base_dir);
+            for (validator, duties) in &pending.duties {
+                write_history_record(
+                    &self.storage,
+                    &duties_path(ROOT, chunk, *validator),
+                    pending.epoch,
+                    |x: &ValidatorDutiesModel| x.epoch,
+                    duties,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn persist_pending(&self) -> Result<(), IndexerError> {
+        if let Some(pending) = &self.pending {
+            let votes = pending
+                .votes
+                .iter()
+                .map(|(slot, attestations)| VotesModelWithId::from((slot, attestations.clone())))
+                .collect::<Vec<_>>();
+
+            let votes_counts = votes
+                .iter()
+                .map(|x| (x.id, x.model.len()))
+                .collect::<HashMap<_, _>>();
+
+            let extended_blocks = pending
+                .extended_blocks
+                .iter()
+                .map(|ext| BlockExtendedModelWithId {
+                    id: ext.id,
+                    model: BlockExtendedModel {
+                        votes_count: votes_counts.get(&ext.id).copied().unwrap_or(0),
+                        ..ext.model.clone()
+                    },
+                })
+                .collect::<Vec<_>>();
+
+            // An epoch indexed again after a reorg may have lost votes recorded before
+            for ext in &pending.extended_blocks {
+                if !votes_counts.contains_key(&ext.id) {
+                    self.storage
+                        .remove(&VotesModelWithId::to_path(ROOT, ext.id))?;
+                }
+            }
+
+            extended_blocks.persist(&self.storage)?;
+            votes.persist(&self.storage)?;
+        }
 
         Ok(())
     }
 }
 
-impl From<Retriever> for Indexer {
-    fn from(retriever: Retriever) -> Self {
-        Indexer {
-            epochs: retriever.epochs,
-            validators: retriever.validators,
+/// Records `inclusion_slot` as the inclusion slot of the attestations of `attesting_indices`,
+/// unless an earlier block included them already.
+fn record_inclusion(
+    duties: &mut BTreeMap<u64, ValidatorDutiesModel>,
+    attesting_indices: &[u64],
+    inclusion_slot: Slot,
+) {
+    for validator in attesting_indices {
+        if let Some(duty) = duties
+            .get_mut(validator)
+            .and_then(|x| x.attestation.as_mut())
+        {
+            let inclusion_slot = inclusion_slot.as_u64();
+            duty.inclusion_slot = Some(min(
+                duty.inclusion_slot.unwrap_or(inclusion_slot),
+                inclusion_slot,
+            ));
         }
     }
 }
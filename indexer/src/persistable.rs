@@ -1,34 +1,66 @@
-use std::{fs::File, io::BufWriter};
-
 use rmp_serde::Serializer;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use types::{
-    block::BlocksMeta, epoch::EpochsMeta, meta::Meta, model::ModelWithId, path::AsPath,
+    block::BlocksMeta,
+    deposit::DepositsMeta,
+    epoch::{EpochsCheckpoint, EpochsMeta},
+    meta::Meta,
+    model::ModelWithId,
+    path::AsPath,
+    slashing::SlashingsMeta,
     validator::ValidatorsMeta,
+    voluntary_exit::VoluntaryExitsMeta,
 };
 
+use crate::storage::{Storage, StorageError, ROOT};
+
 pub trait Persistable: Send {
-    fn persist(self, base_dir: &str);
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError>;
+}
+
+pub trait Restorable: Sized {
+    fn restore(storage: &dyn Storage) -> Result<Option<Self>, StorageError>;
 }
 
 impl Persistable for EpochsMeta {
-    fn persist(self, base_dir: &str) {
-        let mut f = BufWriter::new(File::create(Self::to_path(base_dir)).unwrap());
-        self.serialize(&mut Serializer::new(&mut f)).unwrap();
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
+    }
+}
+
+impl Persistable for EpochsCheckpoint {
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
     }
 }
 
 impl Persistable for BlocksMeta {
-    fn persist(self, base_dir: &str) {
-        let mut f = BufWriter::new(File::create(Self::to_path(base_dir)).unwrap());
-        self.serialize(&mut Serializer::new(&mut f)).unwrap();
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
     }
 }
 
 impl Persistable for ValidatorsMeta {
-    fn persist(self, base_dir: &str) {
-        let mut f = BufWriter::new(File::create(Self::to_path(base_dir)).unwrap());
-        self.serialize(&mut Serializer::new(&mut f)).unwrap();
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
+    }
+}
+
+impl Persistable for DepositsMeta {
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
+    }
+}
+
+impl Persistable for VoluntaryExitsMeta {
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
+    }
+}
+
+impl Persistable for SlashingsMeta {
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &Self::to_path(ROOT), &self)
     }
 }
 
@@ -37,9 +69,8 @@ where
     M: Serialize + Send,
     ModelWithId<M>: AsPath,
 {
-    fn persist(self, base_dir: &str) {
-        let mut f = BufWriter::new(File::create(self.as_path(base_dir)).unwrap());
-        self.model.serialize(&mut Serializer::new(&mut f)).unwrap();
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
+        write_if_changed(storage, &self.as_path(ROOT), &self.model)
     }
 }
 
@@ -48,9 +79,111 @@ where
     M: Serialize + Send,
     ModelWithId<M>: AsPath,
 {
-    fn persist(self, base_dir: &str) {
+    fn persist(self, storage: &dyn Storage) -> Result<(), StorageError> {
         for m in self {
-            m.persist(base_dir);
+            m.persist(storage)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<M: Meta + DeserializeOwned> Restorable for M {
+    fn restore(storage: &dyn Storage) -> Result<Option<Self>, StorageError> {
+        read(storage, &Self::to_path(ROOT))
+    }
+}
+
+/// Reads the value persisted at `path`, if any.
+pub(crate) fn read<T: DeserializeOwned>(
+    storage: &dyn Storage,
+    path: &str,
+) -> Result<Option<T>, StorageError> {
+    storage
+        .read(path)?
+        .map(|content| {
+            rmp_serde::from_read_ref(&content).map_err(|source| StorageError::Decode {
+                path: path.to_string(),
+                source,
+            })
+        })
+        .transpose()
+}
+
+pub(crate) fn encode<T: Serialize + ?Sized>(
+    path: &str,
+    value: &T,
+) -> Result<Vec<u8>, StorageError> {
+    let mut content = Vec::new();
+    value
+        .serialize(&mut Serializer::new(&mut content))
+        .map_err(|source| StorageError::Encode {
+            path: path.to_string(),
+            source,
+        })?;
+
+    Ok(content)
+}
+
+/// Writes `value` to `path` unless the file already holds the same content, so that unchanged
+/// files keep their modification date when an epoch is indexed again.
+pub(crate) fn write_if_changed<T: Serialize + ?Sized>(
+    storage: &dyn Storage,
+    path: &str,
+    value: &T,
+) -> Result<(), StorageError> {
+    let content = encode(path, value)?;
+
+    if storage
+        .read(path)?
+        .map_or(false, |persisted| persisted == content)
+    {
+        return Ok(());
+    }
+
+    storage.write(path, &content)
+}
+
+/// Writes the record of `epoch` to the history at `path`, a sequence of records appended epoch
+/// after epoch. The records of `epoch` and of the following epochs left by a previous indexing
+/// are dropped first, so that an epoch indexed again keeps a single record.
+pub(crate) fn write_history_record<T: Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    path: &str,
+    epoch: u64,
+    record_epoch: fn(&T) -> u64,
+    record: &T,
+) -> Result<(), StorageError> {
+    let record_content = encode(path, record)?;
+    let mut content = storage.read(path)?.unwrap_or_default();
+    let mut reader = content.as_slice();
+    let mut reindexed_offset = None;
+
+    while !reader.is_empty() {
+        let offset = content.len() - reader.len();
+        let persisted =
+            rmp_serde::from_read::<_, T>(&mut reader).map_err(|source| StorageError::Decode {
+                path: path.to_string(),
+                source,
+            })?;
+
+        if record_epoch(&persisted) >= epoch {
+            reindexed_offset = Some(offset);
+            break;
+        }
+    }
+
+    match reindexed_offset {
+        Some(offset) => {
+            if content[offset..] == record_content[..] {
+                return Ok(());
+            }
+
+            content.truncate(offset);
+            content.extend(record_content);
+            storage.write(path, &content)
         }
+        // The epoch follows those of the history
+        None => storage.append(path, &record_content),
     }
 }
@@ -2,17 +2,91 @@ use std::ops::Div;
 
 use indexer_macro::persistable_field;
 use ordered_float::OrderedFloat;
-use types::epoch::EpochModelWithId;
+use serde::{de::DeserializeOwned, Serialize};
+use types::{
+    block::BlockModelWithId, deposit::DepositModelWithId, epoch::EpochModelWithId, path::ToPath,
+    validator::ValidatorModelWithId, voluntary_exit::VoluntaryExitModelWithId,
+};
 
-use crate::orderable::Orderable;
+use crate::{
+    field_binary_heap::FieldBinaryHeap,
+    orderable::Orderable,
+    persistable::Persistable,
+    storage::{Storage, StorageError},
+};
 
 pub trait PersistableField<M> {
-    type Field: Ord + Eq + Send + Clone;
+    type Field: Ord + Eq + Send + Clone + Serialize + DeserializeOwned;
     const FIELD_NAME: &'static str;
 
     fn get_value(model: &M) -> Orderable<Self::Field>;
 }
 
+/// Fields of `M` having a sort index, implemented for tuples of `PersistableField`s.
+pub trait PersistableFields<M> {
+    /// Returns the directories of the sort indexes.
+    fn dirs() -> Vec<String>;
+
+    /// Appends the sort keys of `models` to the pending keys of every index.
+    fn append(storage: &dyn Storage, models: &[M]) -> Result<(), StorageError>;
+
+    /// Merges the pending keys into every index and persists them in chunks of `chunk_size`
+    /// ids, the previous chunks being of `persisted_chunk_size` ids. Only the keys of the first
+    /// `count` ids are kept.
+    fn build(
+        storage: &dyn Storage,
+        chunk_size: usize,
+        persisted_chunk_size: Option<usize>,
+        count: usize,
+    ) -> Result<(), StorageError>;
+}
+
+macro_rules! impl_persistable_fields {
+    ($($field:ident),+) => {
+        impl<M, $($field),+> PersistableFields<M> for ($($field,)+)
+        where
+            M: ToPath<u64>,
+            $($field: PersistableField<M>),+
+        {
+            fn dirs() -> Vec<String> {
+                vec![$(FieldBinaryHeap::<$field, M>::dir()),+]
+            }
+
+            fn append(storage: &dyn Storage, models: &[M]) -> Result<(), StorageError> {
+                $(FieldBinaryHeap::<$field, M>::append(storage, models)?;)+
+
+                Ok(())
+            }
+
+            fn build(
+                storage: &dyn Storage,
+                chunk_size: usize,
+                persisted_chunk_size: Option<usize>,
+                count: usize,
+            ) -> Result<(), StorageError> {
+                $(FieldBinaryHeap::<$field, M>::restore(
+                    storage,
+                    chunk_size,
+                    persisted_chunk_size,
+                    count,
+                )?
+                .persist(storage)?;)+
+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_persistable_fields!(A);
+impl_persistable_fields!(A, B);
+impl_persistable_fields!(A, B, C);
+impl_persistable_fields!(A, B, C, D);
+impl_persistable_fields!(A, B, C, D, E);
+impl_persistable_fields!(A, B, C, D, E, F);
+impl_persistable_fields!(A, B, C, D, E, F, G);
+impl_persistable_fields!(A, B, C, D, E, F, G, H);
+
 #[persistable_field(EpochModelWithId, attestations_count, usize)]
 pub struct EpochAttestationsCount;
 
@@ -43,3 +117,101 @@ impl PersistableField<EpochModelWithId> for EpochGlobalParticipationRate {
         (value.id, OrderedFloat(global_participation_rate)).into()
     }
 }
+
+#[persistable_field(BlockModelWithId, proposer, u64)]
+pub struct BlockProposer;
+
+#[persistable_field(BlockModelWithId, attestations_count, usize)]
+pub struct BlockAttestationsCount;
+
+#[persistable_field(BlockModelWithId, deposits_count, usize)]
+pub struct BlockDepositsCount;
+
+#[persistable_field(BlockModelWithId, proposer_slashings_count, usize)]
+pub struct BlockProposerSlashingsCount;
+
+#[persistable_field(BlockModelWithId, attester_slashings_count, usize)]
+pub struct BlockAttesterSlashingsCount;
+
+pub struct BlockStatus;
+
+impl PersistableField<BlockModelWithId> for BlockStatus {
+    type Field = String;
+    const FIELD_NAME: &'static str = "status";
+
+    fn get_value(value: &BlockModelWithId) -> Orderable<Self::Field> {
+        (value.id, value.model.status.clone()).into()
+    }
+}
+
+#[persistable_field(ValidatorModelWithId, balance, u64)]
+pub struct ValidatorBalance;
+
+#[persistable_field(ValidatorModelWithId, effective_balance, u64)]
+pub struct ValidatorEffectiveBalance;
+
+#[persistable_field(ValidatorModelWithId, activation_epoch, u64)]
+pub struct ValidatorActivationEpoch;
+
+#[persistable_field(ValidatorModelWithId, exit_epoch, Option<u64>)]
+pub struct ValidatorExitEpoch;
+
+#[persistable_field(ValidatorModelWithId, slashed, bool)]
+pub struct ValidatorSlashed;
+
+pub struct ValidatorStatus;
+
+impl PersistableField<ValidatorModelWithId> for ValidatorStatus {
+    type Field = String;
+    const FIELD_NAME: &'static str = "status";
+
+    fn get_value(value: &ValidatorModelWithId) -> Orderable<Self::Field> {
+        (value.id, value.model.status.clone()).into()
+    }
+}
+
+#[persistable_field(DepositModelWithId, amount, u64)]
+pub struct DepositAmount;
+
+#[persistable_field(VoluntaryExitModelWithId, validator_index, u64)]
+pub struct VoluntaryExitValidatorIndex;
+
+#[persistable_field(VoluntaryExitModelWithId, epoch, u64)]
+pub struct VoluntaryExitEpoch;
+
+/// Sort indexes of the epochs
+pub type EpochSortFields = (
+    EpochAttestationsCount,
+    EpochDepositsCount,
+    EpochAttesterSlashingsCount,
+    EpochProposerSlashingsCount,
+    EpochEligibleEther,
+    EpochVotedEther,
+    EpochGlobalParticipationRate,
+);
+
+/// Sort indexes of the blocks
+pub type BlockSortFields = (
+    BlockProposer,
+    BlockAttestationsCount,
+    BlockDepositsCount,
+    BlockProposerSlashingsCount,
+    BlockAttesterSlashingsCount,
+    BlockStatus,
+);
+
+/// Sort indexes of the validators
+pub type ValidatorSortFields = (
+    ValidatorBalance,
+    ValidatorEffectiveBalance,
+    ValidatorActivationEpoch,
+    ValidatorExitEpoch,
+    ValidatorSlashed,
+    ValidatorStatus,
+);
+
+/// Sort indexes of the deposits
+pub type DepositSortFields = (DepositAmount,);
+
+/// Sort indexes of the voluntary exits
+pub type VoluntaryExitSortFields = (VoluntaryExitValidatorIndex, VoluntaryExitEpoch);
@@ -1,5 +1,8 @@
-use eth2::types::StateId;
-use lighthouse_types::{Epoch, MainnetEthSpec};
+use std::marker::PhantomData;
+
+use eth2::types::{BlockId, EventKind, EventTopic, StateId};
+use futures::Stream;
+use lighthouse_types::{Epoch, EthSpec, SignedBeaconBlock};
 
 use crate::{
     beacon_node_client::BeaconNodeClient,
@@ -7,42 +10,90 @@ use crate::{
     types::{consolidated_epoch::ConsolidatedEpoch, consolidated_validator::ConsolidatedValidator},
 };
 
-pub struct Retriever {
+pub struct Retriever<E: EthSpec> {
     beacon_client: BeaconNodeClient,
-    pub epochs: Vec<ConsolidatedEpoch<MainnetEthSpec>>,
-    pub validators: Vec<ConsolidatedValidator>,
+    _phantom: PhantomData<E>,
+}
+
+impl<E: EthSpec> Clone for Retriever<E> {
+    fn clone(&self) -> Self {
+        Retriever {
+            beacon_client: self.beacon_client.clone(),
+            _phantom: PhantomData,
+        }
+    }
 }
 
-impl Retriever {
+impl<E: EthSpec> Retriever<E> {
     pub fn new(endpoint_url: String) -> Self {
         Retriever {
             beacon_client: BeaconNodeClient::new(endpoint_url),
-            epochs: Vec::new(),
-            validators: Vec::new(),
+            _phantom: PhantomData,
         }
     }
 
-    pub async fn retrieve_epoch(&mut self, number: u64) -> Result<(), IndexerError> {
+    pub async fn retrieve_genesis_time(&self) -> Result<u64, IndexerError> {
+        self.beacon_client
+            .get_genesis()
+            .await
+            .map(|genesis| genesis.genesis_time)
+    }
+
+    pub async fn retrieve_epoch(&self, number: u64) -> Result<ConsolidatedEpoch<E>, IndexerError> {
         log::info!("Retrieving epoch {}", number);
 
-        self.epochs.push(
-            ConsolidatedEpoch::<MainnetEthSpec>::new(
-                Epoch::new(number),
-                self.beacon_client.clone(),
-            )
-            .await?,
-        );
+        ConsolidatedEpoch::<E>::new(Epoch::new(number), self.beacon_client.clone()).await
+    }
+
+    /// Retrieves the epoch whose blocks were received from the p2p network.
+    pub async fn retrieve_epoch_from_blocks(
+        &self,
+        number: u64,
+        blocks: Vec<SignedBeaconBlock<E>>,
+    ) -> Result<ConsolidatedEpoch<E>, IndexerError> {
+        log::info!("Retrieving epoch {} of {} blocks", number, blocks.len());
 
-        Ok(())
+        ConsolidatedEpoch::<E>::from_blocks(Epoch::new(number), blocks, self.beacon_client.clone())
+            .await
     }
 
-    pub async fn retrieve_validators(&mut self) -> Result<(), IndexerError> {
+    pub async fn retrieve_validators(&self) -> Result<Vec<ConsolidatedValidator>, IndexerError> {
         log::info!("Retrieving validators");
 
-        self.validators.extend(
-            ConsolidatedValidator::from_state(StateId::Head, self.beacon_client.clone()).await?,
-        );
+        ConsolidatedValidator::from_state(StateId::Head, self.beacon_client.clone()).await
+    }
+
+    pub async fn retrieve_head_epoch(&self) -> Result<u64, IndexerError> {
+        self.beacon_client
+            .get_block_header(BlockId::Head)
+            .await
+            .map(|header| {
+                header
+                    .header
+                    .message
+                    .slot
+                    .epoch(E::slots_per_epoch())
+                    .as_u64()
+            })
+    }
+
+    pub async fn retrieve_finalized_epoch(&self) -> Result<u64, IndexerError> {
+        self.beacon_client
+            .get_finality_checkpoints(StateId::Head)
+            .await
+            .map(|checkpoints| checkpoints.finalized.epoch.as_u64())
+    }
 
-        Ok(())
+    pub async fn events(
+        &self,
+    ) -> Result<impl Stream<Item = Result<EventKind<E>, IndexerError>> + '_, IndexerError> {
+        self.beacon_client
+            .get_events::<E>(&[
+                EventTopic::Head,
+                EventTopic::Block,
+                EventTopic::FinalizedCheckpoint,
+                EventTopic::ChainReorg,
+            ])
+            .await
     }
 }
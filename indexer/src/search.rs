@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use lighthouse_types::EthSpec;
+use types::{
+    graffiti::{graffiti_tokens_dir, graffiti_tokens_path, ngrams},
+    search::{graffiti_shard_path, normalize_graffiti, GraffitiEntryModel},
+    validator::history_chunk,
+};
+
+use crate::{
+    lookup::{merge_into_shards, read_shard},
+    persistable::write_if_changed,
+    storage::{Storage, StorageError, ROOT},
+};
+
+/// Adds `graffiti`, the texts of the graffiti along with the slot of their block, to the search
+/// index. A graffiti already there keeps the last slot bearing it.
+pub fn insert_graffiti<I>(storage: &dyn Storage, graffiti: I) -> Result<(), StorageError>
+where
+    I: IntoIterator<Item = (String, u64)>,
+{
+    let entries = graffiti.into_iter().filter_map(|(text, slot)| {
+        let normalized = normalize_graffiti(&text);
+
+        graffiti_shard_path(ROOT, &normalized).map(|path| {
+            (
+                path,
+                normalized,
+                GraffitiEntryModel {
+                    text: text.trim().to_string(),
+                    last_slot: slot,
+                },
+            )
+        })
+    });
+
+    merge_into_shards(
+        storage,
+        entries,
+        |persisted: &mut GraffitiEntryModel, entry| {
+            if entry.last_slot >= persisted.last_slot {
+                *persisted = entry;
+            }
+        },
+    )
+}
+
+/// Replaces the slots of the blocks of `epoch` in the graffiti index by those of `graffiti`,
+/// indexed under every n-gram of their text. The slots of the epoch are first removed from the
+/// n-grams of `previous_graffiti`, the texts of its graffiti when it was indexed before.
+pub fn insert_graffiti_tokens<E: EthSpec>(
+    storage: &dyn Storage,
+    epoch: u64,
+    previous_graffiti: &[String],
+    graffiti: &[(String, u64)],
+) -> Result<(), StorageError> {
+    let chunk = history_chunk(epoch);
+    let epoch_slots = epoch * E::slots_per_epoch()..(epoch + 1) * E::slots_per_epoch();
+
+    storage.create_dir(&graffiti_tokens_dir(ROOT, chunk))?;
+
+    let mut tokens = BTreeMap::<String, Vec<u64>>::new();
+    for token in previous_graffiti.iter().flat_map(|text| ngrams(text)) {
+        tokens.entry(token).or_default();
+    }
+    for (text, slot) in graffiti {
+        for token in ngrams(text) {
+            tokens.entry(token).or_default().push(*slot);
+        }
+    }
+
+    let mut shards = BTreeMap::<String, Vec<(String, Vec<u64>)>>::new();
+    for (token, slots) in tokens {
+        shards
+            .entry(graffiti_tokens_path(ROOT, chunk, &token))
+            .or_default()
+            .push((token, slots));
+    }
+
+    for (path, tokens) in shards {
+        let mut shard = read_shard::<Vec<u64>>(storage, &path)?.unwrap_or_default();
+
+        for (token, slots) in tokens {
+            let persisted = shard.entry(token.clone()).or_default();
+            persisted.retain(|slot| !epoch_slots.contains(slot));
+            persisted.extend(slots);
+            persisted.sort_unstable();
+            persisted.dedup();
+
+            if persisted.is_empty() {
+                shard.remove(&token);
+            }
+        }
+
+        write_if_changed(storage, &path, &shard)?;
+    }
+
+    Ok(())
+}
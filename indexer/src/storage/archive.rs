@@ -0,0 +1,258 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use tar::{Archive, Builder, Header};
+
+use super::{memory::normalize, Storage, StorageError};
+
+/// Size of the blocks of an archive, the content of each entry is padded to a number of them
+const BLOCK_SIZE: u64 = 512;
+/// An archive ends with two empty blocks
+const END_OF_ARCHIVE_SIZE: u64 = 2 * BLOCK_SIZE;
+
+/// Location of the content of an entry in the archive
+#[derive(Clone, Copy)]
+struct EntryLocation {
+    offset: u64,
+    size: u64,
+}
+
+struct ArchiveState {
+    file: File,
+    /// Latest entry of each file, by path
+    entries: BTreeMap<String, EntryLocation>,
+    /// Offset of the end of the last entry, where the next one is written
+    end: u64,
+    /// Total size of the entries replaced by later ones
+    stale_size: u64,
+    /// Whether files were removed since the archive was last compacted
+    removed: bool,
+}
+
+impl ArchiveState {
+    /// Locates the entries of the archive, the last one of each path holding its content.
+    fn load(file: File, display: &str) -> Result<Self, StorageError> {
+        let mut entries = BTreeMap::new();
+        let mut end = 0;
+        let mut stale_size = 0;
+
+        for entry in Archive::new(&file)
+            .entries()
+            .map_err(StorageError::io(display))?
+        {
+            let entry = entry.map_err(StorageError::io(display))?;
+            let path = normalize(
+                &entry
+                    .path()
+                    .map_err(StorageError::io(display))?
+                    .to_string_lossy(),
+            );
+            let location = EntryLocation {
+                offset: entry.raw_file_position(),
+                size: entry.size(),
+            };
+
+            end = location.offset + padded(location.size);
+
+            if let Some(replaced) = entries.insert(path, location) {
+                stale_size += replaced.size;
+            }
+        }
+
+        Ok(ArchiveState {
+            file,
+            entries,
+            end,
+            stale_size,
+            removed: false,
+        })
+    }
+
+    fn read_entry(&self, location: EntryLocation) -> io::Result<Vec<u8>> {
+        let mut file = &self.file;
+        let mut content = vec![0; location.size as usize];
+
+        file.seek(SeekFrom::Start(location.offset))?;
+        file.read_exact(&mut content)?;
+
+        Ok(content)
+    }
+
+    /// Appends an entry at the end of the archive, which stays readable as a whole.
+    fn append_entry(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(self.end))?;
+
+        let mut builder = Builder::new(&self.file);
+        builder.append_data(&mut header(content.len() as u64), path, content)?;
+        // Writes the end of the archive, overwritten by the next entry
+        builder.into_inner()?;
+
+        self.end = self.file.stream_position()? - END_OF_ARCHIVE_SIZE;
+        let location = EntryLocation {
+            offset: self.end - padded(content.len() as u64),
+            size: content.len() as u64,
+        };
+
+        if let Some(replaced) = self.entries.insert(path.to_string(), location) {
+            self.stale_size += replaced.size;
+        }
+
+        Ok(())
+    }
+}
+
+/// Stores the whole data tree in a single tar archive, convenient to ship or to serve from an
+/// object store.
+///
+/// Each write appends an entry to the archive, of which only the locations of the latest entry
+/// of each file are held in memory. Extracting the archive leaves the latest content of each
+/// file, the entries being extracted in order. The replaced entries are dropped on `flush` once
+/// they outweigh the others, and on the first `flush` following a removal, which an archive
+/// can't record otherwise.
+pub struct ArchiveStorage {
+    path: PathBuf,
+    state: Mutex<ArchiveState>,
+}
+
+impl ArchiveStorage {
+    /// Opens the archive at `path`, locating the files it already holds. The archive is created
+    /// if it doesn't exist.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let path = path.into();
+        let display = path.display().to_string();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(StorageError::io(&display))?;
+
+        Ok(ArchiveStorage {
+            path,
+            state: Mutex::new(ArchiveState::load(file, &display)?),
+        })
+    }
+
+    /// Rewrites the archive with the latest entry of each file only.
+    fn compact(&self, state: &mut ArchiveState) -> Result<(), StorageError> {
+        let display = self.path.display().to_string();
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        let mut builder =
+            Builder::new(File::create(&tmp_path).map_err(StorageError::io(&display))?);
+
+        for (path, location) in &state.entries {
+            let mut file = &state.file;
+            file.seek(SeekFrom::Start(location.offset))
+                .map_err(StorageError::io(path))?;
+
+            builder
+                .append_data(&mut header(location.size), path, file.take(location.size))
+                .map_err(StorageError::io(path))?;
+        }
+
+        builder
+            .into_inner()
+            .and_then(|file| file.sync_all())
+            .map_err(StorageError::io(&display))?;
+        fs::rename(&tmp_path, &self.path).map_err(StorageError::io(&display))?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.path)
+            .map_err(StorageError::io(&display))?;
+        *state = ArchiveState::load(file, &display)?;
+
+        Ok(())
+    }
+}
+
+impl Storage for ArchiveStorage {
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let state = self.state.lock().map_err(|_| StorageError::Poisoned)?;
+
+        match state.entries.get(&normalize(path)) {
+            Some(location) => state
+                .read_entry(*location)
+                .map(Some)
+                .map_err(StorageError::io(path)),
+            None => Ok(None),
+        }
+    }
+
+    fn write(&self, path: &str, content: &[u8]) -> Result<(), StorageError> {
+        let mut state = self.state.lock().map_err(|_| StorageError::Poisoned)?;
+
+        state
+            .append_entry(&normalize(path), content)
+            .map_err(StorageError::io(path))
+    }
+
+    /// Entries can't be extended in place, the file is written again with `content` appended.
+    fn append(&self, path: &str, content: &[u8]) -> Result<(), StorageError> {
+        let mut state = self.state.lock().map_err(|_| StorageError::Poisoned)?;
+        let normalized = normalize(path);
+
+        let mut appended = match state.entries.get(&normalized) {
+            Some(location) => state
+                .read_entry(*location)
+                .map_err(StorageError::io(path))?,
+            None => Vec::new(),
+        };
+        appended.extend_from_slice(content);
+
+        state
+            .append_entry(&normalized, &appended)
+            .map_err(StorageError::io(path))
+    }
+
+    fn remove(&self, path: &str) -> Result<(), StorageError> {
+        let mut state = self.state.lock().map_err(|_| StorageError::Poisoned)?;
+
+        if let Some(removed) = state.entries.remove(&normalize(path)) {
+            state.stale_size += removed.size;
+            state.removed = true;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        let mut state = self.state.lock().map_err(|_| StorageError::Poisoned)?;
+        let live_size = state.entries.values().map(|x| x.size).sum::<u64>();
+
+        if state.removed || state.stale_size > live_size {
+            self.compact(&mut state)?;
+        }
+
+        state
+            .file
+            .sync_all()
+            .map_err(StorageError::io(self.path.display()))
+    }
+}
+
+fn header(size: u64) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_cksum();
+
+    header
+}
+
+/// Returns the size of a content of `size` bytes padded to a whole number of blocks.
+fn padded(size: u64) -> u64 {
+    match size % BLOCK_SIZE {
+        0 => size,
+        rest => size + BLOCK_SIZE - rest,
+    }
+}
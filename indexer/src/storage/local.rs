@@ -0,0 +1,62 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use super::{Storage, StorageError};
+
+/// Stores the files in a directory of the local filesystem, to be served as is.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+
+    fn full_path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl Storage for LocalStorage {
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match fs::read(self.full_path(path)) {
+            Ok(content) => Ok(Some(content)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(StorageError::io(path)(err)),
+        }
+    }
+
+    fn write(&self, path: &str, content: &[u8]) -> Result<(), StorageError> {
+        let full_path = self.full_path(path);
+        let mut tmp_path = full_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        // The file is written next to its destination then renamed over it
+        fs::write(&tmp_path, content).map_err(StorageError::io(path))?;
+        fs::rename(&tmp_path, &full_path).map_err(StorageError::io(path))
+    }
+
+    fn append(&self, path: &str, content: &[u8]) -> Result<(), StorageError> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.full_path(path))
+            .and_then(|mut f| f.write_all(content))
+            .map_err(StorageError::io(path))
+    }
+
+    fn remove(&self, path: &str) -> Result<(), StorageError> {
+        match fs::remove_file(self.full_path(path)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(StorageError::io(path)(err)),
+            _ => Ok(()),
+        }
+    }
+
+    fn create_dir(&self, path: &str) -> Result<(), StorageError> {
+        fs::create_dir_all(self.full_path(path)).map_err(StorageError::io(path))
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+    sync::RwLock,
+};
+
+use super::{Storage, StorageError};
+
+/// Keeps the files in memory, mostly for tests.
+#[derive(Default)]
+pub struct MemoryStorage {
+    files: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the paths of the stored files, in lexicographic order.
+    pub fn paths(&self) -> Result<Vec<String>, StorageError> {
+        let files = self.files.read().map_err(|_| StorageError::Poisoned)?;
+
+        Ok(files.keys().cloned().collect())
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let files = self.files.read().map_err(|_| StorageError::Poisoned)?;
+
+        Ok(files.get(&normalize(path)).cloned())
+    }
+
+    fn write(&self, path: &str, content: &[u8]) -> Result<(), StorageError> {
+        let mut files = self.files.write().map_err(|_| StorageError::Poisoned)?;
+        files.insert(normalize(path), content.to_vec());
+
+        Ok(())
+    }
+
+    fn append(&self, path: &str, content: &[u8]) -> Result<(), StorageError> {
+        let mut files = self.files.write().map_err(|_| StorageError::Poisoned)?;
+        files
+            .entry(normalize(path))
+            .or_default()
+            .extend_from_slice(content);
+
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> Result<(), StorageError> {
+        let mut files = self.files.write().map_err(|_| StorageError::Poisoned)?;
+        files.remove(&normalize(path));
+
+        Ok(())
+    }
+}
+
+/// Drops the `.` components, so that `./epochs/1.msg` and `epochs/1.msg` are the same file.
+pub(super) fn normalize(path: &str) -> String {
+    Path::new(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
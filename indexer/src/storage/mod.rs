@@ -0,0 +1,69 @@
+use thiserror::Error;
+
+pub mod archive;
+pub mod local;
+pub mod memory;
+
+/// Base directory the indexer builds the paths of the persisted files from. The paths are then
+/// relative to the root of the storage.
+pub const ROOT: &str = ".";
+
+/// Store of the files making up the data tree, addressed by their path relative to its root.
+pub trait Storage: Send + Sync {
+    /// Returns the content of the file at `path`, or `None` if there is no such file.
+    fn read(&self, path: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Replaces the content of the file at `path`. A reader never sees the file half written.
+    fn write(&self, path: &str, content: &[u8]) -> Result<(), StorageError>;
+
+    /// Appends `content` to the file at `path`, which is created if needed.
+    fn append(&self, path: &str, content: &[u8]) -> Result<(), StorageError>;
+
+    /// Removes the file at `path`, if any.
+    fn remove(&self, path: &str) -> Result<(), StorageError>;
+
+    /// Creates the directory at `path` and its parents, for the backends which need them to
+    /// exist before writing files into them.
+    fn create_dir(&self, _path: &str) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    /// Makes the previous writes durable, for the backends which buffer them.
+    fn flush(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("I/O error on {path}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Unable to encode {path}")]
+    Encode {
+        path: String,
+        #[source]
+        source: rmp_serde::encode::Error,
+    },
+
+    #[error("Unable to decode {path}")]
+    Decode {
+        path: String,
+        #[source]
+        source: rmp_serde::decode::Error,
+    },
+
+    #[error("Storage lock poisoned")]
+    Poisoned,
+}
+
+impl StorageError {
+    pub(crate) fn io(path: impl ToString) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.to_string();
+        move |source| StorageError::Io { path, source }
+    }
+}
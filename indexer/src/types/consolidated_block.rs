@@ -1,12 +1,17 @@
 use std::{sync::Arc, time::Instant};
 
 use eth2::types::{BlockId, CommitteeData, ProposerData};
-use lighthouse_types::{BeaconBlock, Epoch, EthSpec, Hash256, Signature, Slot};
+use lighthouse_types::{
+    Attestation, BeaconBlock, Epoch, EthSpec, Hash256, Signature, SignedBeaconBlock, Slot,
+};
 use tokio::sync::RwLock;
 use types::{
     attestation::{AttestationModel, AttestationsModelWithId},
     block::{BlockExtendedModel, BlockExtendedModelWithId, BlockModel, BlockModelWithId},
     committee::{CommitteeModel, CommitteesModelWithId},
+    deposit::{DepositModel, DepositsModelWithId},
+    slashing::{SlashingConflictModel, SlashingModel, SlashingsModelWithId},
+    voluntary_exit::{VoluntaryExitModel, VoluntaryExitsModelWithId},
 };
 
 use crate::{beacon_node_client::BeaconNodeClient, errors::IndexerError};
@@ -22,6 +27,12 @@ pub struct ConsolidatedBlock<E: EthSpec> {
     pub proposer: u64,
     pub sync_participation_rate: Option<f64>,
     pub committees: Arc<Vec<CommitteeData>>,
+    pub previous_committees: Arc<Vec<CommitteeData>>,
+    /// Members of the sync committee, set by the epoch once known
+    pub sync_committee: Option<Arc<Vec<u64>>>,
+    /// Index of the first deposit of the block among all the deposits of the deposit contract,
+    /// set by the epoch once known
+    pub first_deposit_index: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +55,7 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
         slot: Slot,
         proposer_duties_lock: Arc<RwLock<Option<Vec<ProposerData>>>>,
         committees: Arc<Vec<CommitteeData>>,
+        previous_committees: Arc<Vec<CommitteeData>>,
         client: BeaconNodeClient,
     ) -> Result<Self, IndexerError> {
         let start = Instant::now();
@@ -53,10 +65,35 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
         log::trace!("get_block duration: {:?}", duration);
         let block_root = client.get_block_root(block).await.ok().map(|x| x.data.root);
 
-        if let Some(block_response) = block_response {
-            let (beacon_block, signature) = block_response.data.deconstruct();
-            let start = Instant::now();
-            let duration = start.elapsed();
+        Self::from_block(
+            epoch,
+            slot,
+            block_response.map(|x| x.data),
+            block_root,
+            proposer_duties_lock,
+            committees,
+            previous_committees,
+            client,
+        )
+        .await
+    }
+
+    /// Builds the block of `slot` from the block proposed for it, if any. Without block, the
+    /// proposer is taken from the duties of the epoch, and the slot is orphaned if it still has
+    /// a root, missed otherwise.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn from_block(
+        epoch: Epoch,
+        slot: Slot,
+        signed_block: Option<SignedBeaconBlock<E>>,
+        block_root: Option<Hash256>,
+        proposer_duties_lock: Arc<RwLock<Option<Vec<ProposerData>>>>,
+        committees: Arc<Vec<CommitteeData>>,
+        previous_committees: Arc<Vec<CommitteeData>>,
+        client: BeaconNodeClient,
+    ) -> Result<Self, IndexerError> {
+        if let Some(signed_block) = signed_block {
+            let (beacon_block, signature) = signed_block.deconstruct();
             let sync_participation_rate = beacon_block
                 .body()
                 .sync_aggregate()
@@ -65,7 +102,6 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
                         / sync_aggregate.sync_committee_bits.len() as f64
                 })
                 .ok();
-            log::trace!("get_block_root duration: {:?}", duration);
             let consolidated_block = ConsolidatedBlock {
                 epoch,
                 slot: beacon_block.slot(),
@@ -76,6 +112,9 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
                 proposer: beacon_block.proposer_index(),
                 sync_participation_rate,
                 committees,
+                previous_committees,
+                sync_committee: None,
+                first_deposit_index: 0,
             };
 
             return Ok(consolidated_block);
@@ -106,6 +145,9 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
                             proposer: proposer.validator_index,
                             sync_participation_rate: None,
                             committees,
+                            previous_committees,
+                            sync_committee: None,
+                            first_deposit_index: 0,
                         };
 
                         return Ok(consolidated_block);
@@ -114,7 +156,9 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
             }
         }
 
-        Err(IndexerError::ElementNotFound(block.to_string()))
+        Err(IndexerError::ElementNotFound(
+            BlockId::Slot(slot).to_string(),
+        ))
     }
 
     pub fn get_attestations_count(&self) -> usize {
@@ -151,6 +195,63 @@ impl<E: EthSpec> ConsolidatedBlock<E> {
             Some(block) => block.body().attester_slashings().len(),
         }
     }
+
+    /// Returns the graffiti of the block decoded as text, without its trailing zeros.
+    pub fn graffiti_text(&self) -> Option<String> {
+        self.block.as_ref().map(|block| {
+            String::from_utf8_lossy(&block.body().graffiti().0)
+                .trim_end_matches('\0')
+                .to_string()
+        })
+    }
+
+    /// Returns the members of the sync committee whose signature is missing from the sync
+    /// aggregate of the block, sorted and deduplicated. It is `None` before Altair, or if the
+    /// sync committee is unknown.
+    pub fn sync_missed(&self) -> Option<Vec<u64>> {
+        let sync_committee = self.sync_committee.as_ref()?;
+        let sync_aggregate = self.block.as_ref()?.body().sync_aggregate().ok()?;
+
+        let mut missed = sync_committee
+            .iter()
+            .zip(sync_aggregate.sync_committee_bits.iter())
+            .filter_map(|(validator, bit)| if bit { None } else { Some(*validator) })
+            .collect::<Vec<_>>();
+        missed.sort_unstable();
+        missed.dedup();
+
+        Some(missed)
+    }
+
+    /// Returns the committees of the epoch `attestation` was made during, the current one or
+    /// the previous one.
+    pub fn committees_of(&self, attestation: &Attestation<E>) -> &[CommitteeData] {
+        if attestation.data.slot.epoch(E::slots_per_epoch()) == self.epoch {
+            &self.committees
+        } else {
+            &self.previous_committees
+        }
+    }
+}
+
+/// Returns the indices of the validators whose attestations are aggregated in `attestation`,
+/// resolved from its committee. It is empty if the committee isn't part of `committees`.
+pub fn attesting_indices<E: EthSpec>(
+    committees: &[CommitteeData],
+    attestation: &Attestation<E>,
+) -> Vec<u64> {
+    committees
+        .iter()
+        .find(|x| x.slot == attestation.data.slot && x.index == attestation.data.index)
+        .map(|committee| {
+            committee
+                .validators
+                .iter()
+                .zip(attestation.aggregation_bits.iter())
+                .filter_map(|(validator, bit)| if bit { Some(*validator) } else { None })
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 impl<E: EthSpec> From<&ConsolidatedBlock<E>> for BlockModelWithId {
@@ -165,6 +266,7 @@ impl<E: EthSpec> From<&ConsolidatedBlock<E>> for BlockModelWithId {
                 voluntary_exits_count: block.body().voluntary_exits().len(),
                 proposer: value.proposer,
                 status: value.status.to_string(),
+                sync_participation_rate: value.sync_participation_rate,
             },
             None => BlockModel {
                 epoch: value.epoch.as_u64(),
@@ -175,6 +277,7 @@ impl<E: EthSpec> From<&ConsolidatedBlock<E>> for BlockModelWithId {
                 voluntary_exits_count: 0,
                 proposer: value.proposer,
                 status: value.status.to_string(),
+                sync_participation_rate: None,
             },
         };
 
@@ -195,11 +298,12 @@ impl<E: EthSpec> From<&ConsolidatedBlock<E>> for BlockExtendedModelWithId {
                 randao_reveal: block.body().randao_reveal().to_string().as_bytes().to_vec(),
                 signature: value.signature.to_string().as_bytes().to_vec(),
                 graffiti: block.body().graffiti().to_string().as_bytes().to_vec(),
-                graffiti_text: block.body().graffiti().to_string(),
+                graffiti_text: value.graffiti_text().unwrap_or_default(),
                 votes_count: 0,
                 eth1data_deposit_root: block.body().eth1_data().deposit_root.as_bytes().to_vec(),
                 eth1data_deposit_count: block.body().eth1_data().deposit_count,
                 eth1data_block_hash: block.body().eth1_data().block_hash.as_bytes().to_vec(),
+                sync_missed: value.sync_missed().unwrap_or_default(),
             },
             None => BlockExtendedModel {
                 block_root: value.block_root.unwrap_or_default().as_bytes().to_vec(),
@@ -213,6 +317,7 @@ impl<E: EthSpec> From<&ConsolidatedBlock<E>> for BlockExtendedModelWithId {
                 eth1data_deposit_root: vec![],
                 eth1data_deposit_count: 0,
                 eth1data_block_hash: vec![],
+                sync_missed: vec![],
             },
         };
 
@@ -258,7 +363,13 @@ impl<E: EthSpec> From<&ConsolidatedBlock<E>> for AttestationsModelWithId {
                 .body()
                 .attestations()
                 .iter()
-                .map(AttestationModel::from)
+                .map(|attestation| AttestationModel {
+                    attesting_indices: attesting_indices(
+                        value.committees_of(attestation),
+                        attestation,
+                    ),
+                    ..AttestationModel::from(attestation)
+                })
                 .collect::<Vec<AttestationModel>>(),
             None => Vec::new(),
         };
@@ -269,3 +380,99 @@ impl<E: EthSpec> From<&ConsolidatedBlock<E>> for AttestationsModelWithId {
         }
     }
 }
+
+impl<E: EthSpec> From<&ConsolidatedBlock<E>> for DepositsModelWithId {
+    fn from(value: &ConsolidatedBlock<E>) -> Self {
+        let slot = value.slot.as_u64();
+
+        let r = match &value.block {
+            Some(block) => block
+                .body()
+                .deposits()
+                .iter()
+                .enumerate()
+                .map(|(position, deposit)| DepositModel {
+                    slot,
+                    index: value.first_deposit_index + position as u64,
+                    pubkey: deposit.data.pubkey.as_serialized().to_vec(),
+                    withdrawal_credentials: deposit.data.withdrawal_credentials.as_bytes().to_vec(),
+                    amount: deposit.data.amount,
+                })
+                .collect::<Vec<DepositModel>>(),
+            None => Vec::new(),
+        };
+
+        DepositsModelWithId { id: slot, model: r }
+    }
+}
+
+impl<E: EthSpec> From<&ConsolidatedBlock<E>> for VoluntaryExitsModelWithId {
+    fn from(value: &ConsolidatedBlock<E>) -> Self {
+        let slot = value.slot.as_u64();
+
+        let r = match &value.block {
+            Some(block) => block
+                .body()
+                .voluntary_exits()
+                .iter()
+                .map(|exit| VoluntaryExitModel {
+                    slot,
+                    validator_index: exit.message.validator_index,
+                    epoch: exit.message.epoch.as_u64(),
+                })
+                .collect::<Vec<VoluntaryExitModel>>(),
+            None => Vec::new(),
+        };
+
+        VoluntaryExitsModelWithId { id: slot, model: r }
+    }
+}
+
+impl<E: EthSpec> From<&ConsolidatedBlock<E>> for SlashingsModelWithId {
+    fn from(value: &ConsolidatedBlock<E>) -> Self {
+        let slot = value.slot.as_u64();
+
+        let r = match &value.block {
+            Some(block) => {
+                let body = block.body();
+                let proposer_slashings = body.proposer_slashings().iter().map(|slashing| {
+                    let header_1 = &slashing.signed_header_1.message;
+                    let header_2 = &slashing.signed_header_2.message;
+
+                    SlashingModel {
+                        slot,
+                        offenders: vec![header_1.proposer_index],
+                        conflict: SlashingConflictModel::Proposer(header_1.into(), header_2.into()),
+                    }
+                });
+
+                // Only the validators attesting both times are slashed
+                let attester_slashings = body.attester_slashings().iter().map(|slashing| {
+                    let mut offenders = slashing
+                        .attestation_1
+                        .attesting_indices
+                        .iter()
+                        .filter(|x| slashing.attestation_2.attesting_indices.contains(x))
+                        .copied()
+                        .collect::<Vec<_>>();
+                    offenders.sort_unstable();
+                    offenders.dedup();
+
+                    SlashingModel {
+                        slot,
+                        offenders,
+                        conflict: SlashingConflictModel::Attester(
+                            (&slashing.attestation_1.data).into(),
+                            (&slashing.attestation_2.data).into(),
+                        ),
+                    }
+                });
+
+                proposer_slashings.chain(attester_slashings).collect()
+            }
+            None => Vec::new(),
+        };
+
+        SlashingsModelWithId { id: slot, model: r }
+    }
+}
@@ -3,8 +3,8 @@ use std::sync::Arc;
 
 use eth2::lighthouse::GlobalValidatorInclusionData;
 use eth2::types::{CommitteeData, ProposerData, StateId, ValidatorBalanceData};
-use futures::future::try_join_all;
-use lighthouse_types::{Epoch, EthSpec};
+use futures::future::{try_join_all, Either};
+use lighthouse_types::{Epoch, EthSpec, SignedBeaconBlock, Slot};
 use shared::utils::clock::Clock;
 use tokio::sync::RwLock;
 use types::epoch::{EpochExtendedModel, EpochExtendedModelWithId, EpochModel, EpochModelWithId};
@@ -21,10 +21,30 @@ pub struct ConsolidatedEpoch<E: EthSpec> {
     pub validator_balances: Vec<ValidatorBalanceData>,
     pub validator_inclusion: GlobalValidatorInclusionData,
     pub committees: Arc<Vec<CommitteeData>>,
+    pub sync_committee: Option<Arc<Vec<u64>>>,
 }
 
 impl<E: EthSpec> ConsolidatedEpoch<E> {
     pub async fn new(epoch: Epoch, client: BeaconNodeClient) -> Result<Self, IndexerError> {
+        Self::build(epoch, None, client).await
+    }
+
+    /// Builds the epoch from the blocks received from the p2p network, its slots without block
+    /// being missed. The rest of the epoch is retrieved from the beacon node.
+    pub async fn from_blocks(
+        epoch: Epoch,
+        blocks: Vec<SignedBeaconBlock<E>>,
+        client: BeaconNodeClient,
+    ) -> Result<Self, IndexerError> {
+        Self::build(epoch, Some(blocks), client).await
+    }
+
+    /// Builds the epoch from `blocks` if given, from the blocks of the beacon node otherwise.
+    async fn build(
+        epoch: Epoch,
+        blocks: Option<Vec<SignedBeaconBlock<E>>>,
+        client: BeaconNodeClient,
+    ) -> Result<Self, IndexerError> {
         let mut build_consolidated_block_futures = Vec::new();
         let proposer_duties_lock = Arc::new(RwLock::new(Option::<Vec<ProposerData>>::None));
 
@@ -36,27 +56,91 @@ impl<E: EthSpec> ConsolidatedEpoch<E> {
 
         let get_committees_handle = tokio::spawn(client.get_committees(epoch));
 
+        // The blocks include attestations made during the previous epoch too
+        let get_previous_committees_handle = match epoch.as_u64() {
+            0 => None,
+            _ => Some(tokio::spawn(client.get_committees(epoch - 1))),
+        };
+
         let committees = Arc::new(get_committees_handle.await??);
+        let previous_committees = Arc::new(match get_previous_committees_handle {
+            Some(handle) => handle.await??,
+            None => Vec::new(),
+        });
 
         for slot in epoch.slot_iter(E::slots_per_epoch()) {
-            build_consolidated_block_futures.push(ConsolidatedBlock::new(
-                epoch,
-                slot,
-                proposer_duties_lock.clone(),
-                committees.clone(),
-                client.clone(),
-            ));
+            let future = match &blocks {
+                Some(blocks) => {
+                    let block = blocks.iter().find(|x| x.slot() == slot).cloned();
+                    let block_root = block.as_ref().map(|x| x.canonical_root());
+
+                    Either::Left(ConsolidatedBlock::from_block(
+                        epoch,
+                        slot,
+                        block,
+                        block_root,
+                        proposer_duties_lock.clone(),
+                        committees.clone(),
+                        previous_committees.clone(),
+                        client.clone(),
+                    ))
+                }
+                None => Either::Right(ConsolidatedBlock::new(
+                    epoch,
+                    slot,
+                    proposer_duties_lock.clone(),
+                    committees.clone(),
+                    previous_committees.clone(),
+                    client.clone(),
+                )),
+            };
+
+            build_consolidated_block_futures.push(future);
+        }
+
+        let mut blocks = try_join_all(build_consolidated_block_futures).await?;
+
+        // Sync committees only exist from Altair on, whose blocks hold a sync aggregate
+        let sync_committee = if blocks.iter().any(|x| x.sync_participation_rate.is_some()) {
+            let state = StateId::Slot(epoch.start_slot(E::slots_per_epoch()));
+            Some(Arc::new(client.get_sync_committee(state, epoch).await?))
+        } else {
+            None
+        };
+
+        for block in &mut blocks {
+            block.sync_committee = sync_committee.clone();
+        }
+
+        // The deposits are numbered from the next one expected by the state ending the previous
+        // epoch, only retrieved if the epoch holds some
+        if blocks.iter().any(|x| x.get_deposits_count() > 0) {
+            let start_slot = epoch.start_slot(E::slots_per_epoch()).as_u64();
+            let state = StateId::Slot(Slot::new(start_slot.saturating_sub(1)));
+            let mut deposit_index = client.get_eth1_deposit_index::<E>(state).await?;
+
+            for block in &mut blocks {
+                block.first_deposit_index = deposit_index;
+                deposit_index += block.get_deposits_count() as u64;
+            }
         }
 
         Ok(ConsolidatedEpoch::<E> {
             epoch,
-            blocks: try_join_all(build_consolidated_block_futures).await?,
+            blocks,
             validator_balances: get_validator_balances_handle.await??,
             validator_inclusion: get_validator_inclusion_handle.await??,
             committees,
+            sync_committee,
         })
     }
 
+    /// Returns the sync committee period of the epoch. Its length is part of the preset of
+    /// `E`, not of the configuration of the network.
+    pub fn sync_committee_period(&self) -> u64 {
+        self.epoch.as_u64() / E::default_spec().epochs_per_sync_committee_period.as_u64()
+    }
+
     pub fn get_attestations_count(&self) -> usize {
         self.blocks.iter().map(|b| b.get_attestations_count()).sum()
     }
@@ -91,11 +175,9 @@ impl<E: EthSpec> ConsolidatedEpoch<E> {
     }
 }
 
-impl<E: EthSpec> From<&ConsolidatedEpoch<E>> for EpochModelWithId {
-    fn from(value: &ConsolidatedEpoch<E>) -> Self {
+impl<E: EthSpec> From<(&ConsolidatedEpoch<E>, &Clock)> for EpochModelWithId {
+    fn from((value, clock): (&ConsolidatedEpoch<E>, &Clock)) -> Self {
         let start_slot = value.epoch.start_slot(E::slots_per_epoch());
-        let spec = E::default_spec();
-        let clock = Clock::new(spec);
 
         let eligible_ether = value.validator_inclusion.previous_epoch_active_gwei;
         let voted_ether = value
@@ -128,6 +210,9 @@ impl<E: EthSpec> From<&ConsolidatedEpoch<E>> for EpochExtendedModelWithId {
                 .get_total_validator_balance()
                 .div(value.validator_balances.len() as u64),
             total_validator_balance: value.get_total_validator_balance(),
+            first_deposit_id: 0,
+            first_voluntary_exit_id: 0,
+            first_slashing_id: 0,
         };
 
         EpochExtendedModelWithId {
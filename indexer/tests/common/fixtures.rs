@@ -0,0 +1,679 @@
+use std::{fs, ops::Range, path::Path};
+
+use eth2::types::{
+    BlockHeaderAndSignature, BlockHeaderData, CommitteeData, FinalityCheckpointsData,
+    ForkVersionedResponse, GenericResponse, GenesisData, ProposerData, RootData,
+    ValidatorBalanceData, ValidatorData, ValidatorStatus,
+};
+use lighthouse_types::{
+    AggregateSignature, Attestation, AttestationData, AttesterSlashing, BeaconBlock,
+    BeaconBlockAltair, BeaconBlockBase, BeaconBlockHeader, BeaconState, BitList, BitVector,
+    ChainSpec, Checkpoint, Deposit, DepositData, Epoch, Eth1Data, EthSpec, FixedVector, ForkName,
+    Graffiti, Hash256, IndexedAttestation, MainnetEthSpec, ProposerSlashing, PublicKeyBytes,
+    Signature, SignatureBytes, SignedBeaconBlock, SignedBeaconBlockHeader, SignedVoluntaryExit,
+    Slot, SyncAggregate, Validator, VariableList, VoluntaryExit,
+};
+use serde::Serialize;
+use serde_json::json;
+use ssz::Encode;
+
+use super::mock_beacon_node::{fixture_path, SSZ_MEDIA_TYPE};
+
+type E = MainnetEthSpec;
+
+const BALANCE: u64 = 32_000_000_000;
+
+/// Reward earned by every validator at each epoch
+pub const REWARD: u64 = 10_000;
+
+pub const GENESIS_TIME: u64 = 1606824023;
+
+/// Slot of the block holding the only deposit, of a new validator, the only voluntary exit and
+/// the only proposer and attester slashings
+pub const OPERATIONS_SLOT: u64 = 40;
+
+/// Validator leaving the chain with the voluntary exit
+pub const EXITING_VALIDATOR: u64 = 9;
+
+/// Validator slashed for proposing two blocks for the same slot
+pub const DOUBLE_PROPOSER: u64 = 11;
+
+/// Validators slashed for voting twice for the same target, the only ones signing both
+/// attestations of the attester slashing
+pub const DOUBLE_VOTERS: [u64; 2] = [12, 13];
+
+/// Member of the sync committee whose signature is missing from every sync aggregate
+pub const SYNC_ABSENT: u64 = 7;
+
+/// Synthetic chain written as fixtures for `MockBeaconNode`: every block but the missed ones
+/// holds a single attestation, voting for the block of the previous slot. From the Altair
+/// epoch on, the blocks hold a sync aggregate too.
+pub struct SyntheticChain {
+    pub epochs: u64,
+    pub validators: u64,
+    pub missed_slots: Vec<u64>,
+    pub altair_epoch: Option<u64>,
+}
+
+impl SyntheticChain {
+    pub fn new(epochs: u64, validators: u64) -> Self {
+        SyntheticChain {
+            epochs,
+            validators,
+            missed_slots: Vec::new(),
+            altair_epoch: None,
+        }
+    }
+
+    pub fn with_missed_slot(mut self, slot: u64) -> Self {
+        self.missed_slots.push(slot);
+        self
+    }
+
+    pub fn with_altair_from(mut self, epoch: u64) -> Self {
+        self.altair_epoch = Some(epoch);
+        self
+    }
+
+    fn is_altair(&self, epoch: Epoch) -> bool {
+        self.altair_epoch
+            .map_or(false, |altair_epoch| epoch.as_u64() >= altair_epoch)
+    }
+
+    pub fn proposer(&self, slot: u64) -> u64 {
+        slot % self.validators
+    }
+
+    pub fn committee(&self, slot: u64) -> Vec<u64> {
+        (0..self.validators)
+            .filter(|validator| validator % E::slots_per_epoch() == slot % E::slots_per_epoch())
+            .collect()
+    }
+
+    /// Graffiti of the block at `slot`, set by two clients in turn
+    pub fn graffiti(&self, slot: u64) -> &'static str {
+        match slot % 2 {
+            0 => "Lighthouse/v2.1.0",
+            _ => "teku/v22.1.0",
+        }
+    }
+
+    /// Members of the sync committee, the validators in turn until the committee is full
+    pub fn sync_committee(&self) -> Vec<u64> {
+        (0..E::sync_committee_size() as u64)
+            .map(|position| position % self.validators)
+            .collect()
+    }
+
+    /// Public key of `validator`, ending with its index
+    pub fn pubkey(&self, validator: u64) -> PublicKeyBytes {
+        let mut bytes = [0; 48];
+        bytes[40..].copy_from_slice(&validator.to_be_bytes());
+
+        PublicKeyBytes::deserialize(&bytes).unwrap()
+    }
+
+    /// Withdrawal credentials of `validator`, shared by pairs of validators
+    pub fn withdrawal_credentials(&self, validator: u64) -> Hash256 {
+        Hash256::from_low_u64_be(validator / 2)
+    }
+
+    /// Returns the spec of the chain, which forks to Altair at its Altair epoch.
+    pub fn spec(&self) -> ChainSpec {
+        let mut spec = E::default_spec();
+        spec.altair_fork_epoch = self.altair_epoch.map(Epoch::new);
+
+        spec
+    }
+
+    pub fn write(&self, fixtures_dir: &Path) {
+        let spec = self.spec();
+        let mut parent_root = Hash256::zero();
+        let mut head = None;
+
+        for slot in 0..self.epochs * E::slots_per_epoch() {
+            if self.missed_slots.contains(&slot) {
+                continue;
+            }
+
+            let mut block = BeaconBlockBase::<E>::empty(&spec);
+            block.slot = Slot::new(slot);
+            block.proposer_index = self.proposer(slot);
+            block.parent_root = parent_root;
+            block.state_root = Hash256::from_low_u64_be(slot);
+
+            let mut graffiti = [0; 32];
+            let text = self.graffiti(slot).as_bytes();
+            graffiti[..text.len()].copy_from_slice(text);
+            block.body.graffiti = Graffiti::from(graffiti);
+
+            if slot > 0 {
+                let committee = self.committee(slot - 1);
+                let mut aggregation_bits = BitList::with_capacity(committee.len()).unwrap();
+                for i in 0..committee.len() {
+                    aggregation_bits.set(i, true).unwrap();
+                }
+
+                let checkpoint = Checkpoint {
+                    epoch: Slot::new(slot - 1).epoch(E::slots_per_epoch()),
+                    root: Hash256::zero(),
+                };
+
+                block
+                    .body
+                    .attestations
+                    .push(Attestation {
+                        aggregation_bits,
+                        data: AttestationData {
+                            slot: Slot::new(slot - 1),
+                            index: 0,
+                            beacon_block_root: parent_root,
+                            source: checkpoint,
+                            target: checkpoint,
+                        },
+                        signature: AggregateSignature::empty(),
+                    })
+                    .unwrap();
+            }
+
+            let epoch = Slot::new(slot).epoch(E::slots_per_epoch());
+
+            if slot == OPERATIONS_SLOT {
+                block
+                    .body
+                    .deposits
+                    .push(Deposit {
+                        proof: FixedVector::from_elem(Hash256::zero()),
+                        data: DepositData {
+                            pubkey: self.pubkey(self.validators),
+                            withdrawal_credentials: self.withdrawal_credentials(self.validators),
+                            amount: BALANCE,
+                            signature: SignatureBytes::empty(),
+                        },
+                    })
+                    .unwrap();
+                block
+                    .body
+                    .voluntary_exits
+                    .push(SignedVoluntaryExit {
+                        message: VoluntaryExit {
+                            epoch,
+                            validator_index: EXITING_VALIDATOR,
+                        },
+                        signature: Signature::empty(),
+                    })
+                    .unwrap();
+                block
+                    .body
+                    .proposer_slashings
+                    .push(ProposerSlashing {
+                        signed_header_1: SignedBeaconBlockHeader {
+                            message: conflicting_header(1),
+                            signature: Signature::empty(),
+                        },
+                        signed_header_2: SignedBeaconBlockHeader {
+                            message: conflicting_header(2),
+                            signature: Signature::empty(),
+                        },
+                    })
+                    .unwrap();
+                block
+                    .body
+                    .attester_slashings
+                    .push(AttesterSlashing {
+                        attestation_1: IndexedAttestation {
+                            attesting_indices: VariableList::new(vec![
+                                DOUBLE_PROPOSER,
+                                DOUBLE_VOTERS[0],
+                                DOUBLE_VOTERS[1],
+                            ])
+                            .unwrap(),
+                            data: conflicting_attestation_data(1),
+                            signature: AggregateSignature::empty(),
+                        },
+                        attestation_2: IndexedAttestation {
+                            attesting_indices: VariableList::new(vec![
+                                DOUBLE_VOTERS[0],
+                                DOUBLE_VOTERS[1],
+                                EXITING_VALIDATOR,
+                            ])
+                            .unwrap(),
+                            data: conflicting_attestation_data(2),
+                            signature: AggregateSignature::empty(),
+                        },
+                    })
+                    .unwrap();
+            }
+
+            let (block, fork_name) = if self.is_altair(epoch) {
+                (
+                    BeaconBlock::Altair(self.altair_block(block, &spec)),
+                    ForkName::Altair,
+                )
+            } else {
+                (BeaconBlock::Base(block), ForkName::Base)
+            };
+            let root = block.canonical_root();
+            let signed_block = SignedBeaconBlock::from_block(block.clone(), Signature::empty());
+
+            write_fixture(
+                fixtures_dir,
+                &format!("/eth/v2/beacon/blocks/{}", slot),
+                "",
+                &ForkVersionedResponse {
+                    version: Some(fork_name),
+                    data: signed_block.clone(),
+                },
+            );
+            write_ssz_fixture(
+                fixtures_dir,
+                &format!("/eth/v2/beacon/blocks/{}", slot),
+                &signed_block.as_ssz_bytes(),
+            );
+            write_fixture(
+                fixtures_dir,
+                &format!("/eth/v1/beacon/blocks/{}/root", slot),
+                "",
+                &GenericResponse::from(RootData { root }),
+            );
+
+            parent_root = root;
+            head = Some((root, block.block_header()));
+        }
+
+        for epoch in (0..self.epochs).map(Epoch::new) {
+            let slots = epoch.slot_iter(E::slots_per_epoch()).collect::<Vec<_>>();
+
+            let committees = slots
+                .iter()
+                .map(|slot| CommitteeData {
+                    index: 0,
+                    slot: *slot,
+                    validators: self.committee(slot.as_u64()),
+                })
+                .collect::<Vec<_>>();
+
+            let proposers = slots
+                .iter()
+                .map(|slot| ProposerData {
+                    pubkey: PublicKeyBytes::empty(),
+                    validator_index: self.proposer(slot.as_u64()),
+                    slot: *slot,
+                })
+                .collect::<Vec<_>>();
+
+            let balances = (0..self.validators)
+                .map(|index| ValidatorBalanceData {
+                    index,
+                    balance: BALANCE + epoch.as_u64() * REWARD,
+                })
+                .collect::<Vec<_>>();
+
+            let active_gwei = BALANCE * self.validators;
+
+            write_fixture(
+                fixtures_dir,
+                "/eth/v1/beacon/states/head/committees",
+                &format!("epoch={}", epoch),
+                &GenericResponse::from(committees),
+            );
+            write_fixture(
+                fixtures_dir,
+                &format!("/eth/v1/validator/duties/proposer/{}", epoch),
+                "",
+                &json!({ "dependent_root": Hash256::zero(), "data": proposers }),
+            );
+            write_fixture(
+                fixtures_dir,
+                &format!(
+                    "/eth/v1/beacon/states/{}/validator_balances",
+                    epoch.start_slot(E::slots_per_epoch())
+                ),
+                "",
+                &GenericResponse::from(balances),
+            );
+
+            // The state ending the epoch before the one of the deposit, having only processed
+            // the deposits of the genesis validators
+            if epoch == Slot::new(OPERATIONS_SLOT).epoch(E::slots_per_epoch()) {
+                let mut state = BeaconState::<E>::new(GENESIS_TIME, Eth1Data::default(), &spec);
+                *state.eth1_deposit_index_mut() = self.validators;
+
+                write_fixture(
+                    fixtures_dir,
+                    &format!(
+                        "/eth/v2/debug/beacon/states/{}",
+                        epoch.start_slot(E::slots_per_epoch()) - 1
+                    ),
+                    "",
+                    &ForkVersionedResponse {
+                        version: Some(ForkName::Base),
+                        data: state,
+                    },
+                );
+            }
+
+            if self.is_altair(epoch) {
+                write_fixture(
+                    fixtures_dir,
+                    &format!(
+                        "/eth/v1/beacon/states/{}/sync_committees",
+                        epoch.start_slot(E::slots_per_epoch())
+                    ),
+                    &format!("epoch={}", epoch),
+                    &json!({
+                        "data": {
+                            "validators": self
+                                .sync_committee()
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>(),
+                            "validator_aggregates": [],
+                        }
+                    }),
+                );
+            }
+
+            write_fixture(
+                fixtures_dir,
+                &format!("/lighthouse/validator_inclusion/{}/global", epoch),
+                "",
+                &json!({
+                    "data": {
+                        "current_epoch_active_gwei": active_gwei,
+                        "previous_epoch_active_gwei": active_gwei,
+                        "current_epoch_attesting_gwei": active_gwei,
+                        "current_epoch_target_attesting_gwei": active_gwei,
+                        "previous_epoch_attesting_gwei": active_gwei,
+                        "previous_epoch_target_attesting_gwei": active_gwei,
+                        "previous_epoch_head_attesting_gwei": active_gwei,
+                    }
+                }),
+            );
+        }
+
+        let validators = (0..self.validators)
+            .map(|index| ValidatorData {
+                index,
+                balance: BALANCE,
+                status: ValidatorStatus::ActiveOngoing,
+                validator: Validator {
+                    pubkey: self.pubkey(index),
+                    withdrawal_credentials: self.withdrawal_credentials(index),
+                    effective_balance: BALANCE,
+                    slashed: false,
+                    activation_eligibility_epoch: Epoch::new(0),
+                    activation_epoch: Epoch::new(0),
+                    exit_epoch: spec.far_future_epoch,
+                    withdrawable_epoch: spec.far_future_epoch,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        write_fixture(
+            fixtures_dir,
+            "/eth/v1/beacon/states/head/validators",
+            "",
+            &GenericResponse::from(validators),
+        );
+
+        write_fixture(
+            fixtures_dir,
+            "/eth/v1/beacon/genesis",
+            "",
+            &GenericResponse::from(GenesisData {
+                genesis_time: GENESIS_TIME,
+                genesis_validators_root: Hash256::zero(),
+                genesis_fork_version: spec.genesis_fork_version,
+            }),
+        );
+
+        let finalized = Checkpoint {
+            epoch: Epoch::new(self.epochs.saturating_sub(2)),
+            root: Hash256::zero(),
+        };
+
+        write_fixture(
+            fixtures_dir,
+            "/eth/v1/beacon/states/head/finality_checkpoints",
+            "",
+            &GenericResponse::from(FinalityCheckpointsData {
+                previous_justified: finalized,
+                current_justified: finalized,
+                finalized,
+            }),
+        );
+
+        if let Some((root, header)) = head {
+            write_fixture(
+                fixtures_dir,
+                "/eth/v1/beacon/headers/head",
+                "",
+                &GenericResponse::from(BlockHeaderData {
+                    root,
+                    canonical: true,
+                    header: BlockHeaderAndSignature {
+                        message: header,
+                        signature: SignatureBytes::empty(),
+                    },
+                }),
+            );
+        }
+    }
+
+    /// Turns a phase 0 block into an Altair one, whose sync aggregate holds the signatures of
+    /// the whole sync committee but `SYNC_ABSENT`.
+    fn altair_block(&self, block: BeaconBlockBase<E>, spec: &ChainSpec) -> BeaconBlockAltair<E> {
+        let mut sync_committee_bits = BitVector::new();
+        for (position, validator) in self.sync_committee().iter().enumerate() {
+            sync_committee_bits
+                .set(position, *validator != SYNC_ABSENT)
+                .unwrap();
+        }
+
+        let mut altair_block = BeaconBlockAltair::empty(spec);
+        altair_block.slot = block.slot;
+        altair_block.proposer_index = block.proposer_index;
+        altair_block.parent_root = block.parent_root;
+        altair_block.state_root = block.state_root;
+        altair_block.body.graffiti = block.body.graffiti;
+        altair_block.body.attestations = block.body.attestations;
+        altair_block.body.deposits = block.body.deposits;
+        altair_block.body.voluntary_exits = block.body.voluntary_exits;
+        altair_block.body.proposer_slashings = block.body.proposer_slashings;
+        altair_block.body.attester_slashings = block.body.attester_slashings;
+        altair_block.body.sync_aggregate = SyncAggregate {
+            sync_committee_bits,
+            sync_committee_signature: AggregateSignature::empty(),
+        };
+
+        altair_block
+    }
+}
+
+/// Returns the data of the `head` event announcing the block at `slot`, the first one of its
+/// epoch.
+pub fn head_event(slot: u64) -> serde_json::Value {
+    json!({
+        "slot": slot.to_string(),
+        "block": Hash256::from_low_u64_be(slot),
+        "state": Hash256::from_low_u64_be(slot),
+        "current_duty_dependent_root": Hash256::zero(),
+        "previous_duty_dependent_root": Hash256::zero(),
+        "epoch_transition": true,
+        "execution_optimistic": false,
+    })
+}
+
+/// Returns the data of the `chain_reorg` event replacing the last `depth` slots by the block at
+/// `slot`.
+pub fn chain_reorg_event(slot: u64, depth: u64) -> serde_json::Value {
+    json!({
+        "slot": slot.to_string(),
+        "depth": depth.to_string(),
+        "old_head_block": Hash256::from_low_u64_be(slot - 1),
+        "new_head_block": Hash256::from_low_u64_be(slot),
+        "old_head_state": Hash256::from_low_u64_be(slot - 1),
+        "new_head_state": Hash256::from_low_u64_be(slot),
+        "epoch": (slot / E::slots_per_epoch()).to_string(),
+        "execution_optimistic": false,
+    })
+}
+
+/// Removes the block at `slot` from the fixtures, whose root stays the one of an orphaned block.
+pub fn orphan_block(fixtures_dir: &Path, slot: u64) {
+    let path = format!("/eth/v2/beacon/blocks/{}", slot);
+
+    for extension in ["json", "ssz"].iter() {
+        fs::remove_file(fixture_path(fixtures_dir, &path, "", extension)).unwrap();
+    }
+}
+
+/// Reads the block at `slot` from the fixtures, unless the slot was missed.
+pub fn read_block(
+    fixtures_dir: &Path,
+    slot: u64,
+    spec: &ChainSpec,
+) -> Option<SignedBeaconBlock<E>> {
+    let path = format!("/eth/v2/beacon/blocks/{}", slot);
+    let bytes = fs::read(fixture_path(fixtures_dir, &path, "", "ssz")).ok()?;
+
+    Some(SignedBeaconBlock::from_ssz_bytes(&bytes, spec).unwrap())
+}
+
+/// Returns one of the two headers `DOUBLE_PROPOSER` proposed for the slot preceding
+/// `OPERATIONS_SLOT`, `variant` making their bodies differ.
+pub fn conflicting_header(variant: u8) -> BeaconBlockHeader {
+    BeaconBlockHeader {
+        slot: Slot::new(OPERATIONS_SLOT - 1),
+        proposer_index: DOUBLE_PROPOSER,
+        parent_root: Hash256::zero(),
+        state_root: Hash256::zero(),
+        body_root: Hash256::repeat_byte(variant),
+    }
+}
+
+/// Returns one of the two attestations of `DOUBLE_VOTERS` for the same target, `variant` making
+/// their block roots differ.
+pub fn conflicting_attestation_data(variant: u8) -> AttestationData {
+    let checkpoint = Checkpoint {
+        epoch: Epoch::new(0),
+        root: Hash256::zero(),
+    };
+
+    AttestationData {
+        slot: Slot::new(OPERATIONS_SLOT - 1),
+        index: 0,
+        beacon_block_root: Hash256::repeat_byte(variant),
+        source: checkpoint,
+        target: checkpoint,
+    }
+}
+
+/// Records the answers of a live beacon node to the requests issued while indexing `epochs`.
+pub async fn record(endpoint_url: &str, fixtures_dir: &Path, epochs: Range<u64>) {
+    let mut requests = vec![
+        ("/eth/v1/beacon/genesis".to_string(), String::new()),
+        (
+            "/eth/v1/beacon/states/head/validators".to_string(),
+            String::new(),
+        ),
+        (
+            "/eth/v1/beacon/states/head/finality_checkpoints".to_string(),
+            String::new(),
+        ),
+        ("/eth/v1/beacon/headers/head".to_string(), String::new()),
+    ];
+
+    for epoch in epochs.map(Epoch::new) {
+        for slot in epoch.slot_iter(E::slots_per_epoch()) {
+            requests.push((format!("/eth/v2/beacon/blocks/{}", slot), String::new()));
+            requests.push((
+                format!("/eth/v1/beacon/blocks/{}/root", slot),
+                String::new(),
+            ));
+        }
+
+        requests.push((
+            "/eth/v1/beacon/states/head/committees".to_string(),
+            format!("epoch={}", epoch),
+        ));
+        requests.push((
+            "/eth/v1/beacon/states/head/committees".to_string(),
+            format!("epoch={}", epoch - 1),
+        ));
+        requests.push((
+            format!("/eth/v1/validator/duties/proposer/{}", epoch),
+            String::new(),
+        ));
+        requests.push((
+            format!(
+                "/eth/v1/beacon/states/{}/validator_balances",
+                epoch.start_slot(E::slots_per_epoch())
+            ),
+            String::new(),
+        ));
+        requests.push((
+            format!("/lighthouse/validator_inclusion/{}/global", epoch),
+            String::new(),
+        ));
+        requests.push((
+            format!(
+                "/eth/v2/debug/beacon/states/{}",
+                epoch.start_slot(E::slots_per_epoch()) - 1
+            ),
+            String::new(),
+        ));
+        requests.push((
+            format!(
+                "/eth/v1/beacon/states/{}/sync_committees",
+                epoch.start_slot(E::slots_per_epoch())
+            ),
+            format!("epoch={}", epoch),
+        ));
+    }
+
+    let client = reqwest::Client::new();
+
+    for (path, query) in requests {
+        let url = if query.is_empty() {
+            format!("{}{}", endpoint_url, path)
+        } else {
+            format!("{}{}?{}", endpoint_url, path, query)
+        };
+
+        // The blocks can be requested in SSZ as well
+        let encodings = if path.starts_with("/eth/v2/beacon/blocks/") {
+            vec![("json", "application/json"), ("ssz", SSZ_MEDIA_TYPE)]
+        } else {
+            vec![("json", "application/json")]
+        };
+
+        for (extension, media_type) in encodings {
+            let response = client
+                .get(&url)
+                .header("Accept", media_type)
+                .send()
+                .await
+                .unwrap();
+
+            if response.status().is_success() {
+                let destination = fixture_path(fixtures_dir, &path, &query, extension);
+                fs::create_dir_all(destination.parent().unwrap()).unwrap();
+                fs::write(destination, response.bytes().await.unwrap()).unwrap();
+            }
+        }
+    }
+}
+
+fn write_fixture<T: Serialize>(fixtures_dir: &Path, path: &str, query: &str, value: &T) {
+    let destination = fixture_path(fixtures_dir, path, query, "json");
+    fs::create_dir_all(destination.parent().unwrap()).unwrap();
+    fs::write(destination, serde_json::to_vec(value).unwrap()).unwrap();
+}
+
+fn write_ssz_fixture(fixtures_dir: &Path, path: &str, content: &[u8]) {
+    let destination = fixture_path(fixtures_dir, path, "", "ssz");
+    fs::create_dir_all(destination.parent().unwrap()).unwrap();
+    fs::write(destination, content).unwrap();
+}
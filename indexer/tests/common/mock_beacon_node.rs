@@ -0,0 +1,127 @@
+use std::{
+    convert::Infallible,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use serde_json::Value;
+use tokio::sync::{broadcast, oneshot};
+use warp::{http::Response, hyper::Body, path::FullPath, Filter};
+
+const NOT_FOUND: &[u8] = br#"{"code":404,"message":"NOT_FOUND","stacktraces":[]}"#;
+
+const JSON_MEDIA_TYPE: &str = "application/json";
+/// Media type of the SSZ responses, asked for in the `Accept` header of the requests
+pub const SSZ_MEDIA_TYPE: &str = "application/octet-stream";
+
+/// Local stand-in for a beacon node, answering the requests of `BeaconNodeClient` with the
+/// fixtures recorded in a directory. The SSZ fixtures are served to the requests accepting SSZ,
+/// the JSON ones to the others. The events are the ones sent with `send_event`. The server is
+/// stopped when dropped.
+pub struct MockBeaconNode {
+    pub url: String,
+    events: broadcast::Sender<Vec<u8>>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl MockBeaconNode {
+    pub fn start(fixtures_dir: &Path) -> Self {
+        let fixtures_dir = Arc::new(fixtures_dir.to_path_buf());
+        let (events, _) = broadcast::channel(16);
+
+        let subscriptions = events.clone();
+        let events_route = warp::get()
+            .and(warp::path!("eth" / "v1" / "events"))
+            .map(move || stream_events(subscriptions.subscribe()));
+
+        let fixtures_route = warp::get()
+            .and(warp::path::full())
+            .and(warp::query::raw().or(warp::any().map(String::new)).unify())
+            .and(warp::header::optional::<String>("accept"))
+            .map(
+                move |path: FullPath, query: String, accept: Option<String>| {
+                    respond(&fixtures_dir, path.as_str(), &query, accept)
+                },
+            );
+
+        let (shutdown, shutdown_signal) = oneshot::channel();
+        let (address, server) = warp::serve(events_route.or(fixtures_route))
+            .bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+                shutdown_signal.await.ok();
+            });
+
+        tokio::spawn(server);
+
+        MockBeaconNode {
+            url: format!("http://{}", address),
+            events,
+            _shutdown: shutdown,
+        }
+    }
+
+    /// Waits until a client is subscribed to the events, which are otherwise lost.
+    pub async fn wait_for_subscription(&self) {
+        while self.events.receiver_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Sends an event of kind `event` to the subscribed clients.
+    pub fn send_event(&self, event: &str, data: Value) {
+        let message = format!("event:{}\ndata:{}\n\n", event, data);
+        self.events.send(message.into_bytes()).ok();
+    }
+}
+
+/// Streams the events sent from now on, one per chunk as the clients parse each chunk as an event.
+fn stream_events(mut subscription: broadcast::Receiver<Vec<u8>>) -> Response<Body> {
+    let events = async_stream::stream! {
+        while let Ok(event) = subscription.recv().await {
+            yield Ok::<_, Infallible>(event);
+        }
+    };
+
+    Response::builder()
+        .header("Content-Type", "text/event-stream")
+        .body(Body::wrap_stream(events))
+        .unwrap()
+}
+
+/// Answers a request with its fixture, in SSZ if the request accepts it.
+fn respond(
+    fixtures_dir: &Path,
+    path: &str,
+    query: &str,
+    accept: Option<String>,
+) -> Response<Vec<u8>> {
+    let (extension, media_type) = match accept {
+        Some(accept) if accept.contains(SSZ_MEDIA_TYPE) => ("ssz", SSZ_MEDIA_TYPE),
+        _ => ("json", JSON_MEDIA_TYPE),
+    };
+
+    match fs::read(fixture_path(fixtures_dir, path, query, extension)) {
+        Ok(body) => Response::builder()
+            .header("Content-Type", media_type)
+            .body(body)
+            .unwrap(),
+        Err(_) => Response::builder()
+            .status(404)
+            .header("Content-Type", JSON_MEDIA_TYPE)
+            .body(NOT_FOUND.to_vec())
+            .unwrap(),
+    }
+}
+
+/// Location of the fixture answering a request: its path relative to `fixtures_dir`, followed
+/// by `@<query>` when the request has a query string, with the extension of its encoding.
+pub fn fixture_path(fixtures_dir: &Path, path: &str, query: &str, extension: &str) -> PathBuf {
+    let path = path.trim_start_matches('/');
+
+    if query.is_empty() {
+        fixtures_dir.join(format!("{}.{}", path, extension))
+    } else {
+        fixtures_dir.join(format!("{}@{}.{}", path, query, extension))
+    }
+}
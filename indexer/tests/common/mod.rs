@@ -0,0 +1,6 @@
+// Each test crate only uses some of the helpers
+#![allow(dead_code)]
+
+pub mod fixtures;
+pub mod mock_beacon_node;
+pub mod peer_stub;
@@ -0,0 +1,267 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::StreamExt;
+use indexer::network::{
+    behaviour::{Behaviour, BehaviourEvent},
+    network_service::NetworkService,
+};
+use libp2p::{
+    core::Executor,
+    gossipsub::{GossipsubEvent, IdentTopic},
+    swarm::{SwarmBuilder, SwarmEvent},
+    Multiaddr, PeerId, Swarm,
+};
+use lighthouse_network::{
+    peer_manager::Keypair,
+    rpc::{
+        methods::{
+            MetaData, MetaDataV2, Ping, RPCCodedResponse, RPCResponse, ResponseTermination,
+            StatusMessage,
+        },
+        protocol::InboundRequest,
+        RPCReceived,
+    },
+    types::{
+        EnrAttestationBitfield, EnrSyncCommitteeBitfield, GossipEncoding, GossipKind, GossipTopic,
+    },
+    PubsubMessage,
+};
+use lighthouse_types::{
+    ChainSpec, Epoch, EthSpec, ForkContext, Hash256, MainnetEthSpec, SignedBeaconBlock, Slot,
+};
+use slog::{o, Discard, Logger};
+use tokio::sync::{mpsc, oneshot};
+
+use super::fixtures::GENESIS_TIME;
+
+type E = MainnetEthSpec;
+
+/// Interval between two attempts to publish the blocks waiting for a subscriber
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Runs the connections of the swarms on the tokio runtime of the test.
+pub struct TokioExecutor;
+
+impl Executor for TokioExecutor {
+    fn exec(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        tokio::spawn(future);
+    }
+}
+
+/// Returns the fork context of a network following `spec`, at the current slot.
+pub fn fork_context(spec: &ChainSpec) -> Arc<ForkContext> {
+    let seconds_since_genesis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        - GENESIS_TIME;
+    let current_slot = Slot::new(seconds_since_genesis / spec.seconds_per_slot);
+
+    Arc::new(ForkContext::new::<E>(current_slot, Hash256::zero(), spec))
+}
+
+/// Returns a network service on the network of `spec`, holding only the genesis block.
+pub fn network_service(spec: &ChainSpec) -> NetworkService<E> {
+    NetworkService::with_fork_context(
+        fork_context(spec),
+        spec.clone(),
+        GENESIS_TIME,
+        Hash256::zero(),
+        Box::new(TokioExecutor),
+        Logger::root(Discard, o!()),
+    )
+    .unwrap()
+}
+
+/// Local stand-in for a peer of the network, serving the blocks it is given to the
+/// `BlocksByRange` requests and publishing those sent with `publish` on the gossip topic of their
+/// fork. The peer stops when dropped.
+pub struct PeerStub {
+    pub peer_id: PeerId,
+    pub multiaddr: Multiaddr,
+    gossip: mpsc::UnboundedSender<SignedBeaconBlock<E>>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl PeerStub {
+    pub async fn start(spec: &ChainSpec, blocks: Vec<SignedBeaconBlock<E>>) -> Self {
+        let fork_context = fork_context(spec);
+        let local_key = Keypair::generate_ed25519();
+        let peer_id = PeerId::from(local_key.public());
+        let transport = NetworkService::<E>::build_transport(local_key).unwrap();
+        let behaviour = Behaviour::new(fork_context.clone(), Logger::root(Discard, o!())).unwrap();
+        let mut swarm = SwarmBuilder::new(transport, behaviour, peer_id)
+            .executor(Box::new(TokioExecutor))
+            .build();
+
+        swarm
+            .listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+            .unwrap();
+
+        let multiaddr = loop {
+            if let SwarmEvent::NewListenAddr { address, .. } = swarm.select_next_some().await {
+                break address;
+            }
+        };
+
+        let head = blocks.last();
+        let status = StatusMessage {
+            fork_digest: fork_context
+                .to_context_bytes(fork_context.current_fork())
+                .unwrap(),
+            finalized_root: Hash256::zero(),
+            finalized_epoch: Epoch::new(0),
+            head_root: head.map_or(Hash256::zero(), |block| block.canonical_root()),
+            head_slot: head.map_or(Slot::new(0), |block| block.slot()),
+        };
+
+        let (gossip, gossip_rx) = mpsc::unbounded_channel();
+        let (shutdown, shutdown_signal) = oneshot::channel();
+
+        let stub = Stub {
+            swarm,
+            fork_context,
+            spec: spec.clone(),
+            status,
+            blocks,
+            unpublished: Vec::new(),
+        };
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = stub.run(gossip_rx) => {}
+                _ = shutdown_signal => {}
+            }
+        });
+
+        PeerStub {
+            peer_id,
+            multiaddr,
+            gossip,
+            _shutdown: shutdown,
+        }
+    }
+
+    /// Publishes the block as soon as a peer subscribed to its topic.
+    pub fn publish(&self, block: SignedBeaconBlock<E>) {
+        self.gossip.send(block).unwrap();
+    }
+}
+
+struct Stub {
+    swarm: Swarm<Behaviour<E>>,
+    fork_context: Arc<ForkContext>,
+    spec: ChainSpec,
+    status: StatusMessage,
+    blocks: Vec<SignedBeaconBlock<E>>,
+    /// Blocks to publish, waiting for a peer subscribed to their topic
+    unpublished: Vec<SignedBeaconBlock<E>>,
+}
+
+impl Stub {
+    async fn run(mut self, mut gossip: mpsc::UnboundedReceiver<SignedBeaconBlock<E>>) {
+        let mut publishing = tokio::time::interval(PUBLISH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                Some(block) = gossip.recv() => self.unpublished.push(block),
+                _ = publishing.tick() => self.publish(),
+                event = self.swarm.select_next_some() => self.on_swarm_event(event),
+            }
+        }
+    }
+
+    fn publish(&mut self) {
+        for block in std::mem::take(&mut self.unpublished) {
+            let fork_name = self.spec.fork_name_at_slot::<E>(block.slot());
+            let fork_digest = self.fork_context.to_context_bytes(fork_name).unwrap();
+            let topic = IdentTopic::new(GossipTopic::new(
+                GossipKind::BeaconBlock,
+                GossipEncoding::default(),
+                fork_digest,
+            ));
+            let data = PubsubMessage::BeaconBlock(Box::new(block.clone()))
+                .encode(GossipEncoding::default());
+
+            // Fails until the subscription of a peer to the topic is received
+            if self
+                .swarm
+                .behaviour_mut()
+                .gossipsub
+                .publish(topic, data)
+                .is_err()
+            {
+                self.unpublished.push(block);
+            }
+        }
+    }
+
+    fn on_swarm_event<H>(&mut self, event: SwarmEvent<BehaviourEvent<E>, H>) {
+        let message = match event {
+            SwarmEvent::Behaviour(BehaviourEvent::Rpc(message)) => message,
+            SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(GossipsubEvent::Subscribed {
+                ..
+            })) => {
+                self.publish();
+                return;
+            }
+            _ => return,
+        };
+
+        let (id, request) = match message.event {
+            Ok(RPCReceived::Request(substream_id, request)) => {
+                ((message.conn_id, substream_id), request)
+            }
+            _ => return,
+        };
+
+        let responses = match request {
+            InboundRequest::Status(_) => vec![RPCCodedResponse::Success(RPCResponse::Status(
+                self.status.clone(),
+            ))],
+            InboundRequest::BlocksByRange(request) => {
+                let slots = request.start_slot..request.start_slot + request.count;
+
+                self.blocks
+                    .iter()
+                    .filter(|block| slots.contains(&block.slot().as_u64()))
+                    .map(|block| {
+                        RPCCodedResponse::Success(RPCResponse::BlocksByRange(Box::new(
+                            block.clone(),
+                        )))
+                    })
+                    .chain(std::iter::once(RPCCodedResponse::StreamTermination(
+                        ResponseTermination::BlocksByRange,
+                    )))
+                    .collect()
+            }
+            InboundRequest::Ping(_) => {
+                vec![RPCCodedResponse::Success(RPCResponse::Pong(Ping {
+                    data: 0,
+                }))]
+            }
+            InboundRequest::MetaData(_) => {
+                vec![RPCCodedResponse::Success(RPCResponse::MetaData(
+                    MetaData::V2(MetaDataV2 {
+                        seq_number: 0,
+                        attnets: EnrAttestationBitfield::<E>::new(),
+                        syncnets: EnrSyncCommitteeBitfield::<E>::new(),
+                    }),
+                ))]
+            }
+            _ => Vec::new(),
+        };
+
+        for response in responses {
+            self.swarm
+                .behaviour_mut()
+                .rpc
+                .send_response(message.peer_id, id, response);
+        }
+    }
+}
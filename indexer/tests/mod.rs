@@ -1,3 +0,0 @@
-extern crate indexer;
-
-mod consolidated_epoch;
@@ -0,0 +1,223 @@
+use std::{fs, future::Future, path::Path, time::Duration};
+
+use futures::{future, StreamExt};
+use indexer::{
+    direct_indexer::{index_network_blocks, BlocksIndexer},
+    network::{
+        network_service::{NetworkEvent, NetworkService},
+        peers_manager::PeersManager,
+    },
+    persistable::Restorable,
+    storage::local::LocalStorage,
+    types::consolidated_block::BlockStatus,
+};
+use lighthouse_types::{ChainSpec, EthSpec, MainnetEthSpec, SignedBeaconBlock};
+use serde::de::DeserializeOwned;
+use slog::{o, Discard, Logger};
+use tempfile::TempDir;
+use types::{block::BlockModel, epoch::EpochsMeta};
+
+use common::{
+    fixtures::{read_block, SyntheticChain},
+    mock_beacon_node::MockBeaconNode,
+    peer_stub::{network_service, PeerStub},
+};
+
+mod common;
+
+type E = MainnetEthSpec;
+
+const MISSED_SLOT: u64 = 37;
+
+const ALTAIR_EPOCH: u64 = 1;
+
+fn synthetic_chain() -> (TempDir, ChainSpec, MockBeaconNode) {
+    let fixtures_dir = TempDir::new().unwrap();
+    let chain = SyntheticChain::new(3, 64)
+        .with_missed_slot(MISSED_SLOT)
+        .with_altair_from(ALTAIR_EPOCH);
+    chain.write(fixtures_dir.path());
+
+    let node = MockBeaconNode::start(fixtures_dir.path());
+
+    (fixtures_dir, chain.spec(), node)
+}
+
+/// Returns the blocks of the first `epochs` epochs of the synthetic chain.
+fn synthetic_blocks(
+    fixtures_dir: &Path,
+    spec: &ChainSpec,
+    epochs: u64,
+) -> Vec<SignedBeaconBlock<E>> {
+    (0..epochs * E::slots_per_epoch())
+        .filter_map(|slot| read_block(fixtures_dir, slot, spec))
+        .collect()
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> T {
+    rmp_serde::from_read(fs::File::open(path).unwrap()).unwrap()
+}
+
+fn slots(blocks: &[SignedBeaconBlock<E>]) -> Vec<u64> {
+    blocks.iter().map(|x| x.slot().as_u64()).collect()
+}
+
+/// Polls the service until `future` completes, the events being dropped.
+async fn drive<T>(service: &mut NetworkService<E>, future: impl Future<Output = T>) -> T {
+    tokio::pin!(future);
+
+    let driven = async {
+        loop {
+            tokio::select! {
+                output = &mut future => return output,
+                _ = service.next() => {}
+            }
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(20), driven)
+        .await
+        .expect("Future not completed in time")
+}
+
+/// Polls `condition` until it holds, failing after a few seconds.
+async fn wait_until(condition: impl Fn() -> bool) {
+    for _ in 0..400 {
+        if condition() {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    panic!("Condition still not met");
+}
+
+/// Connects the service to the stub and polls it until the stub answered its status.
+async fn connect(service: &mut NetworkService<E>, stub: &PeerStub) {
+    service.connect(stub.peer_id, &stub.multiaddr).unwrap();
+
+    let ready = async {
+        while let Some(event) = service.next().await {
+            if let NetworkEvent::PeerReady { peer_id, .. } = event {
+                assert_eq!(peer_id, stub.peer_id);
+                return;
+            }
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(20), ready)
+        .await
+        .expect("Peer not ready in time");
+}
+
+/// Indexes the blocks of the stub into `data_dir` while `script` runs.
+async fn index_from_peer_while(
+    spec: &ChainSpec,
+    node: &MockBeaconNode,
+    stub: &PeerStub,
+    data_dir: &Path,
+    script: impl Future<Output = ()>,
+) {
+    let mut service = network_service(spec);
+    let mut peers_manager = PeersManager::new(
+        Vec::new(),
+        service.fork_digests(),
+        Logger::root(Discard, o!()),
+    )
+    .await
+    .unwrap();
+    peers_manager.add_peer(stub.peer_id, stub.multiaddr.clone());
+
+    let mut blocks_indexer =
+        BlocksIndexer::<E, _>::new(node.url.clone(), LocalStorage::new(data_dir), spec.clone())
+            .await
+            .unwrap();
+
+    tokio::select! {
+        result = index_network_blocks(&mut service, &mut peers_manager, &mut blocks_indexer) => {
+            panic!("Indexing ended: {:?}", result)
+        }
+        _ = script => {}
+    }
+}
+
+/// Returns the number of epochs indexed and committed into `data_dir`.
+fn epochs_count(data_dir: &Path) -> u64 {
+    EpochsMeta::restore(&LocalStorage::new(data_dir))
+        .unwrap()
+        .map_or(0, |meta| meta.count)
+}
+
+#[tokio::test]
+async fn blocks_by_range_through_service() {
+    let (fixtures_dir, spec, _node) = synthetic_chain();
+    let blocks = synthetic_blocks(fixtures_dir.path(), &spec, 2);
+    let stub = PeerStub::start(&spec, blocks).await;
+    let mut service = network_service(&spec);
+
+    connect(&mut service, &stub).await;
+
+    // Both requests are queued before the service is polled again
+    let first = service.blocks_by_range(stub.peer_id, 0, E::slots_per_epoch());
+    let second = service.blocks_by_range(stub.peer_id, E::slots_per_epoch(), E::slots_per_epoch());
+    let (first, second) = drive(&mut service, future::join(first, second)).await;
+
+    assert_eq!(
+        slots(&first.unwrap()),
+        (0..E::slots_per_epoch()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        slots(&second.unwrap()),
+        (E::slots_per_epoch()..2 * E::slots_per_epoch())
+            .filter(|slot| *slot != MISSED_SLOT)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn index_blocks_synced_from_peer() {
+    let (fixtures_dir, spec, node) = synthetic_chain();
+    let stub = PeerStub::start(&spec, synthetic_blocks(fixtures_dir.path(), &spec, 2)).await;
+    let data_dir = TempDir::new().unwrap();
+
+    // Both epochs served by the peer are completed by the block of their last slot
+    index_from_peer_while(&spec, &node, &stub, data_dir.path(), async {
+        wait_until(|| epochs_count(data_dir.path()) == 2).await;
+    })
+    .await;
+
+    let block = read::<BlockModel>(&data_dir.path().join("blocks/36.msg"));
+    assert_eq!(block.status, BlockStatus::Proposed.to_string());
+    assert_eq!(block.attestations_count, 1);
+
+    let missed_block =
+        read::<BlockModel>(&data_dir.path().join(format!("blocks/{}.msg", MISSED_SLOT)));
+    assert_eq!(missed_block.status, BlockStatus::Missed.to_string());
+    assert_eq!(missed_block.proposer, MISSED_SLOT % 64);
+
+    // The epoch the peer didn't serve yet isn't indexed
+    assert!(!data_dir.path().join("blocks/64.msg").exists());
+}
+
+#[tokio::test]
+async fn index_gossiped_blocks() {
+    let (fixtures_dir, spec, node) = synthetic_chain();
+    let stub = PeerStub::start(&spec, synthetic_blocks(fixtures_dir.path(), &spec, 2)).await;
+    let data_dir = TempDir::new().unwrap();
+
+    index_from_peer_while(&spec, &node, &stub, data_dir.path(), async {
+        wait_until(|| epochs_count(data_dir.path()) == 2).await;
+
+        // The epoch following the head of the peer is only published
+        for slot in 2 * E::slots_per_epoch()..3 * E::slots_per_epoch() {
+            stub.publish(read_block(fixtures_dir.path(), slot, &spec).unwrap());
+        }
+        wait_until(|| epochs_count(data_dir.path()) == 3).await;
+    })
+    .await;
+
+    let block = read::<BlockModel>(&data_dir.path().join("blocks/64.msg"));
+    assert_eq!(block.status, BlockStatus::Proposed.to_string());
+    assert_eq!(block.epoch, 2);
+}
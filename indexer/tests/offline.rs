@@ -0,0 +1,1179 @@
+use std::{
+    env, fs,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use eth2::{types::BlockId, BeaconNodeHttpClient, Timeouts};
+use indexer::{
+    beacon_node_client::BeaconNodeClient,
+    follower::Follower,
+    lookup,
+    node_to_files_indexer::{Indexer, SortChunkSizes},
+    persistable::Restorable,
+    retriever::Retriever,
+    storage::{archive::ArchiveStorage, local::LocalStorage, memory::MemoryStorage, Storage},
+    types::{consolidated_block::BlockStatus, consolidated_epoch::ConsolidatedEpoch},
+};
+use lighthouse_types::{Epoch, EthSpec, MainnetEthSpec, Slot};
+use sensitive_url::SensitiveUrl;
+use serde::de::DeserializeOwned;
+use shared::utils::clock::Clock;
+use tempfile::TempDir;
+use types::{
+    attestation::AttestationModel,
+    block::{BlockExtendedModel, BlockModel, BlocksMeta},
+    committee::SyncCommitteeModel,
+    deposit::{DepositModel, DepositsMeta},
+    duty::{AttestationDutyModel, ProposalDutyModel, SyncDutyModel, ValidatorDutiesModel},
+    epoch::{EpochModel, EpochsCheckpoint, EpochsMeta},
+    graffiti::{EpochGraffitiModel, GraffitiCountModel, GraffitiTokenShard},
+    lookup::{to_key, LookupTable},
+    search::{GraffitiEntryModel, GraffitiShard},
+    slashing::{SlashingConflictModel, SlashingModel, SlashingsMeta, ValidatorSlashingsModel},
+    validator::{ValidatorBalanceModel, ValidatorModel, ValidatorsMeta},
+    voluntary_exit::{VoluntaryExitModel, VoluntaryExitsMeta},
+    vote::VoteModel,
+};
+
+use common::{
+    fixtures::{
+        chain_reorg_event, conflicting_attestation_data, conflicting_header, head_event,
+        orphan_block, SyntheticChain, DOUBLE_PROPOSER, DOUBLE_VOTERS, EXITING_VALIDATOR,
+        GENESIS_TIME, OPERATIONS_SLOT, REWARD, SYNC_ABSENT,
+    },
+    mock_beacon_node::MockBeaconNode,
+};
+
+mod common;
+
+const MISSED_SLOT: u64 = 37;
+
+const ALTAIR_EPOCH: u64 = 1;
+
+fn synthetic_chain() -> (TempDir, MockBeaconNode) {
+    let fixtures_dir = TempDir::new().unwrap();
+
+    SyntheticChain::new(3, 64)
+        .with_missed_slot(MISSED_SLOT)
+        .with_altair_from(ALTAIR_EPOCH)
+        .write(fixtures_dir.path());
+
+    let node = MockBeaconNode::start(fixtures_dir.path());
+
+    (fixtures_dir, node)
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> T {
+    rmp_serde::from_read(fs::File::open(path).unwrap()).unwrap()
+}
+
+/// Reads a file of values appended one after the other
+fn read_appended<T: DeserializeOwned>(path: &Path) -> Vec<T> {
+    decode_appended(&fs::read(path).unwrap())
+}
+
+fn decode_appended<T: DeserializeOwned>(content: &[u8]) -> Vec<T> {
+    let mut reader = content;
+    let mut values = Vec::new();
+
+    while !reader.is_empty() {
+        values.push(rmp_serde::from_read(&mut reader).unwrap());
+    }
+
+    values
+}
+
+#[tokio::test]
+async fn consolidated_epoch_from_mock() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let client = BeaconNodeClient::new(node.url.clone());
+
+    let consolidated_epoch = ConsolidatedEpoch::<MainnetEthSpec>::new(Epoch::new(1), client)
+        .await
+        .unwrap();
+
+    assert_eq!(consolidated_epoch.epoch.as_u64(), 1);
+    assert_eq!(
+        consolidated_epoch.blocks.len(),
+        MainnetEthSpec::slots_per_epoch() as usize
+    );
+    assert_eq!(consolidated_epoch.validator_balances.len(), 64);
+    assert_eq!(
+        consolidated_epoch.get_attestations_count(),
+        MainnetEthSpec::slots_per_epoch() as usize - 1
+    );
+
+    let missed_block = &consolidated_epoch.blocks[(MISSED_SLOT % 32) as usize];
+    assert!(matches!(missed_block.status, BlockStatus::Missed));
+    assert_eq!(missed_block.proposer, MISSED_SLOT % 64);
+    assert_eq!(missed_block.sync_missed(), None);
+
+    assert_eq!(
+        consolidated_epoch.sync_committee.as_deref(),
+        Some(&SyntheticChain::new(3, 64).sync_committee())
+    );
+    assert_eq!(
+        consolidated_epoch.blocks[0].sync_missed(),
+        Some(vec![SYNC_ABSENT])
+    );
+}
+
+#[tokio::test]
+async fn ssz_block_from_mock() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let client = BeaconNodeHttpClient::new(
+        SensitiveUrl::parse(&node.url).unwrap(),
+        Timeouts::set_all(Duration::from_secs(10)),
+    );
+    let spec = SyntheticChain::new(3, 64)
+        .with_altair_from(ALTAIR_EPOCH)
+        .spec();
+
+    // A block of phase 0 and one of Altair
+    for slot in [1, MISSED_SLOT + 1]
+        .iter()
+        .map(|x| BlockId::Slot(Slot::new(*x)))
+    {
+        let ssz_block = client
+            .get_beacon_blocks_ssz::<MainnetEthSpec>(slot, &spec)
+            .await
+            .unwrap();
+        let json_block = client
+            .get_beacon_blocks::<MainnetEthSpec>(slot)
+            .await
+            .unwrap()
+            .map(|x| x.data);
+
+        assert!(ssz_block.is_some());
+        assert_eq!(ssz_block, json_block);
+    }
+
+    let missed_block = client
+        .get_beacon_blocks_ssz::<MainnetEthSpec>(BlockId::Slot(Slot::new(MISSED_SLOT)), &spec)
+        .await
+        .unwrap();
+    assert_eq!(missed_block, None);
+}
+
+#[tokio::test]
+async fn retriever_from_mock() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let epoch = retriever.retrieve_epoch(0).await.unwrap();
+    assert!(epoch
+        .blocks
+        .iter()
+        .all(|block| matches!(block.status, BlockStatus::Proposed)));
+
+    assert_eq!(retriever.retrieve_validators().await.unwrap().len(), 64);
+    assert_eq!(retriever.retrieve_head_epoch().await.unwrap(), 2);
+    assert_eq!(retriever.retrieve_finalized_epoch().await.unwrap(), 1);
+}
+
+/// Indexes the whole synthetic chain served by `node` into `storage`.
+async fn index_synthetic_chain<S: Storage>(
+    node: &MockBeaconNode,
+    storage: S,
+) -> Indexer<MainnetEthSpec, S> {
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let genesis_time = retriever.retrieve_genesis_time().await.unwrap();
+    assert_eq!(genesis_time, GENESIS_TIME);
+
+    let clock = Clock::new(MainnetEthSpec::default_spec(), genesis_time);
+    let mut indexer = Indexer::new(storage, clock).unwrap();
+    indexer
+        .set_finalized_epoch(retriever.retrieve_finalized_epoch().await.unwrap())
+        .unwrap();
+
+    for epoch in 0..3 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+
+    indexer
+        .index_validators(retriever.retrieve_validators().await.unwrap())
+        .unwrap();
+    indexer.commit().unwrap();
+
+    indexer
+}
+
+/// Synthetic chain indexed into a temporary directory, kept along with the node serving it.
+struct IndexedChain {
+    _fixtures_dir: TempDir,
+    _node: MockBeaconNode,
+    data_dir: TempDir,
+    indexer: Indexer<MainnetEthSpec, LocalStorage>,
+}
+
+impl IndexedChain {
+    async fn new() -> Self {
+        let (fixtures_dir, node) = synthetic_chain();
+        let data_dir = TempDir::new().unwrap();
+        let indexer = index_synthetic_chain(&node, LocalStorage::new(data_dir.path())).await;
+
+        IndexedChain {
+            _fixtures_dir: fixtures_dir,
+            _node: node,
+            data_dir,
+            indexer,
+        }
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.data_dir.path().join(path)
+    }
+
+    fn storage(&self) -> &LocalStorage {
+        self.indexer.storage()
+    }
+}
+
+#[tokio::test]
+async fn index_metas_to_files() {
+    let chain = IndexedChain::new().await;
+    let storage = chain.storage();
+
+    // The directories of the sort indexes are laid out from the registered fields
+    assert!(chain.path("epochs/s/global_participation_rate").is_dir());
+
+    assert_eq!(EpochsMeta::restore(storage).unwrap().unwrap().count, 3);
+    assert_eq!(BlocksMeta::restore(storage).unwrap().unwrap().count, 96);
+    assert_eq!(ValidatorsMeta::restore(storage).unwrap().unwrap().count, 64);
+    assert_eq!(
+        ValidatorsMeta::restore(storage)
+            .unwrap()
+            .unwrap()
+            .sort_chunk_size,
+        10
+    );
+
+    let checkpoint = EpochsCheckpoint::restore(storage).unwrap().unwrap();
+    assert_eq!(checkpoint.last_epoch, 2);
+    assert_eq!(checkpoint.finalized_epoch, Some(1));
+    assert_eq!(chain.indexer.start_epoch().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn index_epochs_and_blocks_to_files() {
+    let chain = IndexedChain::new().await;
+
+    let missed_block = read::<BlockModel>(&chain.path(&format!("blocks/{}.msg", MISSED_SLOT)));
+    assert_eq!(missed_block.status, "Missed");
+
+    // The sync committee appears from Altair on
+    assert_eq!(
+        read::<BlockModel>(&chain.path("blocks/31.msg")).sync_participation_rate,
+        None
+    );
+    let altair_block = read::<BlockModel>(&chain.path("blocks/32.msg"));
+    assert_eq!(altair_block.sync_participation_rate, Some(504.0 / 512.0));
+    let extended_block = read::<BlockExtendedModel>(&chain.path("blocks/e/32.msg"));
+    assert_eq!(extended_block.sync_missed, vec![SYNC_ABSENT]);
+    let sync_committee = read::<SyncCommitteeModel>(&chain.path("sync_committees/0.msg"));
+    assert_eq!(sync_committee.validators.len(), 512);
+
+    let epoch = read::<EpochModel>(&chain.path("epochs/2.msg"));
+    assert_eq!(epoch.timestamp, GENESIS_TIME + 2 * 32 * 12);
+}
+
+#[tokio::test]
+async fn index_votes_and_attestations_to_files() {
+    let chain = IndexedChain::new().await;
+
+    // The last block of the first epoch is voted for by the first block of the second one
+    let votes = read::<Vec<VoteModel>>(&chain.path("blocks/v/31.msg"));
+    assert_eq!(votes.len(), 1);
+    let extended_block = read::<BlockExtendedModel>(&chain.path("blocks/e/31.msg"));
+    assert_eq!(extended_block.votes_count, 1);
+
+    // The attestation of the first block of an epoch is resolved with the previous committees
+    let attestations = read::<Vec<AttestationModel>>(&chain.path("blocks/a/32.msg"));
+    assert_eq!(attestations[0].attesting_indices, vec![31, 63]);
+}
+
+#[tokio::test]
+async fn build_sort_indexes() {
+    let chain = IndexedChain::new().await;
+
+    let sorted = read::<Vec<u64>>(&chain.path("epochs/s/attestations_count/1.msg"));
+    assert_eq!(sorted, vec![0, 1, 2]);
+
+    // "Missed" sorts before "Proposed"
+    let sorted = read::<Vec<u64>>(&chain.path("blocks/s/status/1.msg"));
+    assert_eq!(sorted[0], MISSED_SLOT);
+
+    let sorted = read::<Vec<u64>>(&chain.path("validators/s/balance/1.msg"));
+    assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn index_validators_to_files() {
+    let chain = IndexedChain::new().await;
+    let storage = chain.storage();
+
+    let validator = read::<ValidatorModel>(&chain.path("validators/5.msg"));
+    assert_eq!(validator.balance, 32_000_000_000);
+
+    let synthetic = SyntheticChain::new(3, 64);
+    let pubkey = to_key(synthetic.pubkey(5).as_serialized());
+    assert_eq!(
+        lookup::get::<u64>(storage, LookupTable::Pubkeys, &pubkey).unwrap(),
+        Some(5)
+    );
+    let withdrawal_credentials = to_key(synthetic.withdrawal_credentials(6).as_bytes());
+    assert_eq!(
+        lookup::get::<Vec<u64>>(
+            storage,
+            LookupTable::WithdrawalCredentials,
+            &withdrawal_credentials
+        )
+        .unwrap(),
+        Some(vec![6, 7])
+    );
+}
+
+#[tokio::test]
+async fn look_up_block_roots() {
+    let chain = IndexedChain::new().await;
+    let storage = chain.storage();
+
+    let block = read::<BlockExtendedModel>(&chain.path("blocks/e/40.msg"));
+    assert_eq!(
+        lookup::get::<u64>(storage, LookupTable::BlockRoots, &to_key(&block.block_root)).unwrap(),
+        Some(40)
+    );
+    assert_eq!(
+        lookup::get::<u64>(storage, LookupTable::StateRoots, &to_key(&block.state_root)).unwrap(),
+        Some(40)
+    );
+}
+
+#[tokio::test]
+async fn index_graffiti_to_files() {
+    let chain = IndexedChain::new().await;
+
+    let block = read::<BlockExtendedModel>(&chain.path("blocks/e/40.msg"));
+    assert_eq!(block.graffiti_text, "Lighthouse/v2.1.0");
+
+    let graffiti = read::<GraffitiShard>(&chain.path("search/g/te.msg"));
+    assert_eq!(
+        graffiti.get("teku/v22.1.0"),
+        Some(&GraffitiEntryModel {
+            text: "teku/v22.1.0".to_string(),
+            last_slot: 95,
+        })
+    );
+
+    // Only "teku/v22.1.0" holds the n-gram "te"
+    let tokens = read::<GraffitiTokenShard>(&chain.path("search/t/0/te.msg"));
+    assert_eq!(
+        tokens.get("te"),
+        Some(
+            &(1..96)
+                .step_by(2)
+                .filter(|slot| *slot != MISSED_SLOT)
+                .collect::<Vec<_>>()
+        )
+    );
+
+    let epoch_graffiti = read::<EpochGraffitiModel>(&chain.path("epochs/g/1.msg"));
+    assert_eq!(
+        epoch_graffiti.clients,
+        vec![
+            GraffitiCountModel {
+                name: "Lighthouse".to_string(),
+                count: 16,
+            },
+            GraffitiCountModel {
+                name: "Teku".to_string(),
+                count: 15,
+            },
+        ]
+    );
+    assert_eq!(epoch_graffiti.graffiti[0].name, "Lighthouse/v2.1.0");
+}
+
+#[tokio::test]
+async fn index_balance_histories_to_files() {
+    let chain = IndexedChain::new().await;
+
+    let balances = read_appended::<ValidatorBalanceModel>(&chain.path("validators/b/0/5.msg"));
+    assert_eq!(
+        balances,
+        (0..3)
+            .map(|epoch| ValidatorBalanceModel {
+                epoch,
+                balance: 32_000_000_000 + epoch * REWARD,
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn index_duties_to_files() {
+    let chain = IndexedChain::new().await;
+
+    // The duties of the last epoch are persisted once the next one is indexed
+    let signed_slots = (32..64)
+        .filter(|slot| *slot != MISSED_SLOT)
+        .collect::<Vec<_>>();
+    let duties = read_appended::<ValidatorDutiesModel>(&chain.path("validators/d/0/37.msg"));
+    assert_eq!(
+        duties,
+        vec![
+            ValidatorDutiesModel {
+                epoch: 0,
+                proposals: vec![],
+                attestation: Some(AttestationDutyModel {
+                    slot: 5,
+                    committee_index: 0,
+                    inclusion_slot: Some(6),
+                }),
+                sync: None,
+            },
+            ValidatorDutiesModel {
+                epoch: 1,
+                proposals: vec![ProposalDutyModel {
+                    slot: MISSED_SLOT,
+                    status: "Missed".to_string(),
+                }],
+                attestation: Some(AttestationDutyModel {
+                    slot: MISSED_SLOT,
+                    committee_index: 0,
+                    inclusion_slot: Some(MISSED_SLOT + 1),
+                }),
+                sync: Some(SyncDutyModel {
+                    period: 0,
+                    signed_slots: signed_slots.clone(),
+                    missed_slots: vec![],
+                }),
+            },
+        ]
+    );
+
+    let duties = read_appended::<ValidatorDutiesModel>(
+        &chain.path(&format!("validators/d/0/{}.msg", SYNC_ABSENT)),
+    );
+    assert_eq!(
+        duties[1].sync,
+        Some(SyncDutyModel {
+            period: 0,
+            signed_slots: vec![],
+            missed_slots: signed_slots,
+        })
+    );
+
+    // No block votes for the slot preceding the missed one
+    let duties = read_appended::<ValidatorDutiesModel>(&chain.path("validators/d/0/4.msg"));
+    assert_eq!(duties[1].attestation.as_ref().unwrap().inclusion_slot, None);
+}
+
+#[tokio::test]
+async fn index_deposits_to_files() {
+    let chain = IndexedChain::new().await;
+    let synthetic = SyntheticChain::new(3, 64);
+
+    // The deposit follows those of the genesis validators
+    let deposit = DepositModel {
+        slot: OPERATIONS_SLOT,
+        index: 64,
+        pubkey: synthetic.pubkey(64).as_serialized().to_vec(),
+        withdrawal_credentials: synthetic.withdrawal_credentials(64).as_bytes().to_vec(),
+        amount: 32_000_000_000,
+    };
+    assert_eq!(
+        read::<Vec<DepositModel>>(&chain.path(&format!("blocks/d/{}.msg", OPERATIONS_SLOT))),
+        vec![deposit.clone()]
+    );
+    assert_eq!(read::<DepositModel>(&chain.path("deposits/0.msg")), deposit);
+    assert_eq!(
+        DepositsMeta::restore(chain.storage())
+            .unwrap()
+            .unwrap()
+            .count,
+        1
+    );
+}
+
+#[tokio::test]
+async fn index_voluntary_exits_to_files() {
+    let chain = IndexedChain::new().await;
+
+    let voluntary_exit = VoluntaryExitModel {
+        slot: OPERATIONS_SLOT,
+        validator_index: EXITING_VALIDATOR,
+        epoch: 1,
+    };
+    assert_eq!(
+        read::<Vec<VoluntaryExitModel>>(&chain.path(&format!("blocks/x/{}.msg", OPERATIONS_SLOT))),
+        vec![voluntary_exit.clone()]
+    );
+    assert_eq!(
+        read::<VoluntaryExitModel>(&chain.path("voluntary_exits/0.msg")),
+        voluntary_exit
+    );
+    assert_eq!(
+        VoluntaryExitsMeta::restore(chain.storage())
+            .unwrap()
+            .unwrap()
+            .count,
+        1
+    );
+}
+
+#[tokio::test]
+async fn index_slashings_to_files() {
+    let chain = IndexedChain::new().await;
+
+    let slashings = vec![
+        SlashingModel {
+            slot: OPERATIONS_SLOT,
+            offenders: vec![DOUBLE_PROPOSER],
+            conflict: SlashingConflictModel::Proposer(
+                (&conflicting_header(1)).into(),
+                (&conflicting_header(2)).into(),
+            ),
+        },
+        SlashingModel {
+            slot: OPERATIONS_SLOT,
+            offenders: DOUBLE_VOTERS.to_vec(),
+            conflict: SlashingConflictModel::Attester(
+                (&conflicting_attestation_data(1)).into(),
+                (&conflicting_attestation_data(2)).into(),
+            ),
+        },
+    ];
+    assert_eq!(
+        read::<Vec<SlashingModel>>(&chain.path(&format!("blocks/sl/{}.msg", OPERATIONS_SLOT))),
+        slashings
+    );
+    assert_eq!(
+        read::<SlashingModel>(&chain.path("slashings/1.msg")),
+        slashings[1]
+    );
+    assert_eq!(
+        SlashingsMeta::restore(chain.storage())
+            .unwrap()
+            .unwrap()
+            .count,
+        2
+    );
+    assert_eq!(
+        read::<ValidatorSlashingsModel>(
+            &chain.path(&format!("validators/sl/{}.msg", DOUBLE_PROPOSER))
+        )
+        .slashings,
+        vec![0]
+    );
+    assert_eq!(
+        read::<ValidatorSlashingsModel>(
+            &chain.path(&format!("validators/sl/{}.msg", DOUBLE_VOTERS[1]))
+        )
+        .slashings,
+        vec![1]
+    );
+    // Signing a single attestation of the attester slashing isn't an offence
+    assert!(!chain
+        .path(&format!("validators/sl/{}.msg", EXITING_VALIDATOR))
+        .exists());
+}
+
+#[tokio::test]
+async fn persist_each_epoch() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+    let data_dir = TempDir::new().unwrap();
+    let clock = Clock::new(MainnetEthSpec::default_spec(), GENESIS_TIME);
+
+    let mut indexer =
+        Indexer::<MainnetEthSpec, _>::new(LocalStorage::new(data_dir.path()), clock).unwrap();
+    for epoch in 0..2 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+
+    // Without any commit, the epochs are persisted as soon as they are indexed
+    assert_eq!(
+        read::<EpochsCheckpoint>(&data_dir.path().join("epochs/checkpoint.msg")).last_epoch,
+        1
+    );
+    assert!(data_dir.path().join("epochs/1.msg").exists());
+    assert!(data_dir.path().join("blocks/63.msg").exists());
+    assert!(data_dir.path().join("blocks/a/32.msg").exists());
+
+    // The extended blocks of the first epoch are final once voted for by the second one
+    let extended_block = read::<BlockExtendedModel>(&data_dir.path().join("blocks/e/31.msg"));
+    assert_eq!(extended_block.votes_count, 1);
+    assert!(!data_dir.path().join("blocks/e/63.msg").exists());
+
+    // The metas and the sort indexes are only written on commit
+    assert!(EpochsMeta::restore(indexer.storage()).unwrap().is_none());
+    assert!(!data_dir
+        .path()
+        .join("epochs/s/attestations_count/1.msg")
+        .exists());
+
+    indexer.commit().unwrap();
+
+    assert_eq!(
+        EpochsMeta::restore(indexer.storage())
+            .unwrap()
+            .unwrap()
+            .count,
+        2
+    );
+    assert!(data_dir.path().join("blocks/e/63.msg").exists());
+}
+
+#[tokio::test]
+async fn reindex_keeps_operation_ids() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+
+    for epoch in 1..3 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+    indexer.commit().unwrap();
+
+    let storage = indexer.storage();
+    assert_eq!(DepositsMeta::restore(storage).unwrap().unwrap().count, 1);
+    assert_eq!(
+        VoluntaryExitsMeta::restore(storage).unwrap().unwrap().count,
+        1
+    );
+    assert_eq!(storage.read("deposits/1.msg").unwrap(), None);
+    assert_eq!(SlashingsMeta::restore(storage).unwrap().unwrap().count, 2);
+
+    let offender_slashings = storage
+        .read(&format!("validators/sl/{}.msg", DOUBLE_PROPOSER))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        rmp_serde::from_read_ref::<_, ValidatorSlashingsModel>(&offender_slashings)
+            .unwrap()
+            .slashings,
+        vec![0]
+    );
+
+    let sorted = storage.read("deposits/s/amount/1.msg").unwrap().unwrap();
+    assert_eq!(
+        rmp_serde::from_read_ref::<_, Vec<u64>>(&sorted).unwrap(),
+        vec![0]
+    );
+}
+
+#[tokio::test]
+async fn resume_from_checkpoint() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+    let data_dir = TempDir::new().unwrap();
+    let clock = || Clock::new(MainnetEthSpec::default_spec(), GENESIS_TIME);
+
+    let mut indexer =
+        Indexer::<MainnetEthSpec, _>::new(LocalStorage::new(data_dir.path()), clock()).unwrap();
+    indexer.set_finalized_epoch(1).unwrap();
+    for epoch in 0..2 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+    indexer.commit().unwrap();
+    drop(indexer);
+
+    // The last epoch is indexed again, its blocks receiving votes from the next one
+    let mut indexer =
+        Indexer::<MainnetEthSpec, _>::new(LocalStorage::new(data_dir.path()), clock()).unwrap();
+    assert_eq!(indexer.finalized_epoch(), 1);
+    let start_epoch = indexer.start_epoch().unwrap();
+    assert_eq!(start_epoch, 1);
+
+    for epoch in start_epoch..3 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+    indexer
+        .index_validators(retriever.retrieve_validators().await.unwrap())
+        .unwrap();
+    indexer.commit().unwrap();
+
+    let storage = indexer.storage();
+    assert_eq!(EpochsMeta::restore(storage).unwrap().unwrap().count, 3);
+    assert_eq!(BlocksMeta::restore(storage).unwrap().unwrap().count, 96);
+    assert_eq!(DepositsMeta::restore(storage).unwrap().unwrap().count, 1);
+    assert_eq!(
+        read::<EpochsCheckpoint>(&data_dir.path().join("epochs/checkpoint.msg")).last_epoch,
+        2
+    );
+
+    let extended_block = read::<BlockExtendedModel>(&data_dir.path().join("blocks/e/63.msg"));
+    assert_eq!(extended_block.votes_count, 1);
+
+    let sorted = read::<Vec<u64>>(&data_dir.path().join("epochs/s/attestations_count/1.msg"));
+    assert_eq!(sorted, vec![0, 1, 2]);
+
+    assert_eq!(
+        read_appended::<ValidatorBalanceModel>(&data_dir.path().join("validators/b/0/5.msg"))
+            .iter()
+            .map(|x| x.epoch)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+}
+
+#[test]
+fn resume_from_checkpoint_without_finalized_epoch() {
+    let storage = MemoryStorage::new();
+    // Checkpoint persisted before the finalized epoch was recorded
+    storage
+        .write(
+            "epochs/checkpoint.msg",
+            &rmp_serde::to_vec(&(5_u64,)).unwrap(),
+        )
+        .unwrap();
+
+    let clock = Clock::new(MainnetEthSpec::default_spec(), GENESIS_TIME);
+    let indexer = Indexer::<MainnetEthSpec, _>::new(storage, clock).unwrap();
+
+    assert_eq!(indexer.start_epoch().unwrap(), 5);
+    assert_eq!(indexer.finalized_epoch(), 0);
+}
+
+#[tokio::test]
+async fn reindex_removes_lost_votes() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+    assert!(indexer.storage().read("blocks/v/39.msg").unwrap().is_some());
+
+    // A reorg orphans the block voting for the previous one
+    let mut epoch = retriever.retrieve_epoch(1).await.unwrap();
+    let orphaned = &mut epoch.blocks[8];
+    assert_eq!(orphaned.slot.as_u64(), 40);
+    orphaned.block = None;
+    orphaned.block_root = None;
+    orphaned.status = BlockStatus::Orphaned;
+
+    indexer.index_epoch(epoch).unwrap();
+    indexer
+        .index_epoch(retriever.retrieve_epoch(2).await.unwrap())
+        .unwrap();
+    indexer.commit().unwrap();
+
+    let storage = indexer.storage();
+    assert_eq!(storage.read("blocks/v/39.msg").unwrap(), None);
+    assert!(storage.read("blocks/v/38.msg").unwrap().is_some());
+
+    let extended_block = storage.read("blocks/e/39.msg").unwrap().unwrap();
+    assert_eq!(
+        rmp_serde::from_read_ref::<_, BlockExtendedModel>(&extended_block)
+            .unwrap()
+            .votes_count,
+        0
+    );
+}
+
+#[tokio::test]
+async fn reindex_removes_lost_operations() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+
+    // A reorg orphans the block holding the only operations
+    let mut epoch = retriever.retrieve_epoch(1).await.unwrap();
+    let orphaned = &mut epoch.blocks[(OPERATIONS_SLOT - 32) as usize];
+    assert_eq!(orphaned.slot.as_u64(), OPERATIONS_SLOT);
+    orphaned.block = None;
+    orphaned.block_root = None;
+    orphaned.status = BlockStatus::Orphaned;
+
+    indexer.index_epoch(epoch).unwrap();
+    indexer
+        .index_epoch(retriever.retrieve_epoch(2).await.unwrap())
+        .unwrap();
+    indexer.commit().unwrap();
+
+    let storage = indexer.storage();
+    assert_eq!(DepositsMeta::restore(storage).unwrap().unwrap().count, 0);
+    assert_eq!(
+        VoluntaryExitsMeta::restore(storage).unwrap().unwrap().count,
+        0
+    );
+    assert_eq!(SlashingsMeta::restore(storage).unwrap().unwrap().count, 0);
+    assert_eq!(storage.read("deposits/0.msg").unwrap(), None);
+    assert_eq!(storage.read("voluntary_exits/0.msg").unwrap(), None);
+    assert_eq!(storage.read("slashings/0.msg").unwrap(), None);
+    assert_eq!(storage.read("slashings/1.msg").unwrap(), None);
+
+    // The sort keys of the removed operations are dropped along with their chunks
+    assert_eq!(
+        storage.read("deposits/s/amount/keys.msg").unwrap(),
+        Some(Vec::new())
+    );
+    assert_eq!(storage.read("deposits/s/amount/1.msg").unwrap(), None);
+    assert_eq!(
+        storage.read("voluntary_exits/s/epoch/keys.msg").unwrap(),
+        Some(Vec::new())
+    );
+}
+
+#[tokio::test]
+async fn reindex_replaces_slashing_ids() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+    let offender_slashings = |indexer: &Indexer<MainnetEthSpec, MemoryStorage>, validator| {
+        indexer
+            .storage()
+            .read(&format!("validators/sl/{}.msg", validator))
+            .unwrap()
+            .map(|x| {
+                rmp_serde::from_read_ref::<_, ValidatorSlashingsModel>(&x)
+                    .unwrap()
+                    .slashings
+            })
+    };
+
+    // A reorg orphans the block holding the slashings
+    let mut epoch = retriever.retrieve_epoch(1).await.unwrap();
+    let orphaned = &mut epoch.blocks[(OPERATIONS_SLOT - 32) as usize];
+    orphaned.block = None;
+    orphaned.block_root = None;
+    orphaned.status = BlockStatus::Orphaned;
+
+    indexer.index_epoch(epoch).unwrap();
+    indexer
+        .index_epoch(retriever.retrieve_epoch(2).await.unwrap())
+        .unwrap();
+    indexer.commit().unwrap();
+
+    assert_eq!(offender_slashings(&indexer, DOUBLE_PROPOSER), None);
+    assert_eq!(offender_slashings(&indexer, DOUBLE_VOTERS[0]), None);
+
+    // Another one brings it back
+    for epoch in 1..3 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+    indexer.commit().unwrap();
+
+    assert_eq!(offender_slashings(&indexer, DOUBLE_PROPOSER), Some(vec![0]));
+    assert_eq!(
+        offender_slashings(&indexer, DOUBLE_VOTERS[0]),
+        Some(vec![1])
+    );
+}
+
+#[tokio::test]
+async fn reindex_removes_stale_graffiti() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+
+    // A reorg orphans a block whose graffiti was indexed
+    let mut epoch = retriever.retrieve_epoch(1).await.unwrap();
+    let orphaned = &mut epoch.blocks[8];
+    assert_eq!(orphaned.slot.as_u64(), 40);
+    orphaned.block = None;
+    orphaned.block_root = None;
+    orphaned.status = BlockStatus::Orphaned;
+
+    indexer.index_epoch(epoch).unwrap();
+    indexer
+        .index_epoch(retriever.retrieve_epoch(2).await.unwrap())
+        .unwrap();
+    indexer.commit().unwrap();
+
+    let tokens = indexer
+        .storage()
+        .read("search/t/0/li.msg")
+        .unwrap()
+        .unwrap();
+    let slots = &rmp_serde::from_read_ref::<_, GraffitiTokenShard>(&tokens).unwrap()["li"];
+    assert!(slots.contains(&38));
+    assert!(!slots.contains(&40));
+}
+
+#[tokio::test]
+async fn reindex_replaces_histories() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let retriever = Retriever::<MainnetEthSpec>::new(node.url.clone());
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+
+    for epoch in 1..3 {
+        indexer
+            .index_epoch(retriever.retrieve_epoch(epoch).await.unwrap())
+            .unwrap();
+    }
+    indexer.commit().unwrap();
+
+    let balances = indexer
+        .storage()
+        .read("validators/b/0/5.msg")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        decode_appended::<ValidatorBalanceModel>(&balances),
+        (0..3)
+            .map(|epoch| ValidatorBalanceModel {
+                epoch,
+                balance: 32_000_000_000 + epoch * REWARD,
+            })
+            .collect::<Vec<_>>()
+    );
+
+    // The duties of the last epoch are persisted once the next one is indexed
+    let duties = indexer
+        .storage()
+        .read("validators/d/0/37.msg")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        decode_appended::<ValidatorDutiesModel>(&duties)
+            .iter()
+            .map(|x| x.epoch)
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+}
+
+/// Follows the chain served by `node` into `data_dir` while `script` runs.
+async fn follow_while(node: &MockBeaconNode, data_dir: &Path, script: impl Future<Output = ()>) {
+    let mut follower = Follower::<MainnetEthSpec, _>::new(
+        node.url.clone(),
+        LocalStorage::new(data_dir),
+        MainnetEthSpec::default_spec(),
+    )
+    .await
+    .unwrap();
+
+    tokio::select! {
+        result = follower.follow() => panic!("Following ended: {:?}", result),
+        _ = script => (),
+    }
+}
+
+/// Polls `condition` until it holds, failing after a few seconds.
+async fn wait_until(condition: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    panic!("Condition still not met");
+}
+
+#[tokio::test]
+async fn follow_events_from_mock() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let data_dir = TempDir::new().unwrap();
+    let storage = LocalStorage::new(data_dir.path());
+    let epochs_count = || {
+        EpochsMeta::restore(&storage)
+            .unwrap()
+            .map_or(0, |meta| meta.count)
+    };
+
+    follow_while(&node, data_dir.path(), async {
+        // The epochs completed before the head are caught up with before subscribing
+        node.wait_for_subscription().await;
+        assert_eq!(epochs_count(), 2);
+        let checkpoint = EpochsCheckpoint::restore(&storage).unwrap().unwrap();
+        assert_eq!(checkpoint.last_epoch, 1);
+        assert_eq!(checkpoint.finalized_epoch, Some(1));
+
+        // The epoch completed by the head is indexed once announced
+        node.send_event("head", head_event(96));
+        wait_until(|| epochs_count() == 3).await;
+    })
+    .await;
+
+    assert_eq!(
+        EpochsCheckpoint::restore(&storage)
+            .unwrap()
+            .unwrap()
+            .last_epoch,
+        2
+    );
+    let extended_block = read::<BlockExtendedModel>(&data_dir.path().join("blocks/e/63.msg"));
+    assert_eq!(extended_block.votes_count, 1);
+    assert_eq!(
+        ValidatorsMeta::restore(&storage).unwrap().unwrap().count,
+        64
+    );
+}
+
+#[tokio::test]
+async fn follow_reorg_from_mock() {
+    let (fixtures_dir, node) = synthetic_chain();
+    let data_dir = TempDir::new().unwrap();
+    let storage = LocalStorage::new(data_dir.path());
+    let votes_count = |slot: u64| {
+        read::<BlockExtendedModel>(&data_dir.path().join(format!("blocks/e/{}.msg", slot)))
+            .votes_count
+    };
+
+    follow_while(&node, data_dir.path(), async {
+        node.wait_for_subscription().await;
+        node.send_event("head", head_event(96));
+        wait_until(|| {
+            EpochsMeta::restore(&storage)
+                .unwrap()
+                .map_or(false, |meta| meta.count == 3)
+        })
+        .await;
+        assert_eq!(votes_count(39), 1);
+
+        // The reorg orphans the block voting for the previous one, in the epoch following
+        // the finalized one
+        orphan_block(fixtures_dir.path(), 40);
+        node.send_event("chain_reorg", chain_reorg_event(96, 56));
+        wait_until(|| votes_count(39) == 0).await;
+    })
+    .await;
+
+    let block = read::<BlockModel>(&data_dir.path().join("blocks/40.msg"));
+    assert_eq!(block.status, BlockStatus::Orphaned.to_string());
+    assert!(!data_dir.path().join("blocks/v/39.msg").exists());
+    assert!(data_dir.path().join("blocks/v/38.msg").exists());
+    assert_eq!(EpochsMeta::restore(&storage).unwrap().unwrap().count, 3);
+}
+
+#[tokio::test]
+async fn index_to_archive_from_mock() {
+    let (_fixtures_dir, node) = synthetic_chain();
+    let data_dir = TempDir::new().unwrap();
+    let archive_path = data_dir.path().join("data.tar");
+
+    index_synthetic_chain(&node, ArchiveStorage::open(&archive_path).unwrap()).await;
+
+    let storage = ArchiveStorage::open(&archive_path).unwrap();
+    assert_eq!(EpochsMeta::restore(&storage).unwrap().unwrap().count, 3);
+
+    let sorted = storage
+        .read("epochs/s/attestations_count/1.msg")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        rmp_serde::from_read_ref::<_, Vec<u64>>(&sorted).unwrap(),
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        storage
+            .read("epochs/s/attestations_count/pending.msg")
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn archive_keeps_latest_content() {
+    let data_dir = TempDir::new().unwrap();
+    let archive_path = data_dir.path().join("data.tar");
+
+    let storage = ArchiveStorage::open(&archive_path).unwrap();
+    storage.write("./epochs/1.msg", b"first").unwrap();
+    storage.write("epochs/1.msg", b"second").unwrap();
+    storage.append("validators/b/0/1.msg", b"a").unwrap();
+    storage.append("validators/b/0/1.msg", b"b").unwrap();
+    storage.write("epochs/s/pending.msg", b"pending").unwrap();
+    drop(storage);
+
+    // The entries are written to the archive right away
+    let storage = ArchiveStorage::open(&archive_path).unwrap();
+    assert_eq!(
+        storage.read("epochs/1.msg").unwrap(),
+        Some(b"second".to_vec())
+    );
+    assert_eq!(
+        storage.read("validators/b/0/1.msg").unwrap(),
+        Some(b"ab".to_vec())
+    );
+
+    storage.remove("epochs/s/pending.msg").unwrap();
+    storage.flush().unwrap();
+    drop(storage);
+
+    let storage = ArchiveStorage::open(&archive_path).unwrap();
+    assert_eq!(storage.read("epochs/s/pending.msg").unwrap(), None);
+
+    // The archive is compacted on flush after a removal
+    let mut archive = tar::Archive::new(fs::File::open(&archive_path).unwrap());
+    assert_eq!(archive.entries().unwrap().count(), 2);
+}
+
+#[tokio::test]
+async fn index_to_memory_from_mock() {
+    let (_fixtures_dir, node) = synthetic_chain();
+
+    let indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+    let paths = indexer.storage().paths().unwrap();
+
+    assert!(paths.contains(&format!("blocks/{}.msg", MISSED_SLOT)));
+    assert!(paths.contains(&"validators/meta.msg".to_string()));
+    assert!(!paths.iter().any(|path| path.ends_with(".tmp")));
+}
+
+#[tokio::test]
+async fn rechunk_sort_indexes() {
+    let (_fixtures_dir, node) = synthetic_chain();
+
+    let mut indexer = index_synthetic_chain(&node, MemoryStorage::new()).await;
+    indexer.set_sort_chunk_sizes(SortChunkSizes {
+        validators: 32,
+        ..SortChunkSizes::default()
+    });
+    indexer.commit().unwrap();
+
+    let storage = indexer.storage();
+    let meta = ValidatorsMeta::restore(storage).unwrap().unwrap();
+    assert_eq!(meta.count, 64);
+    assert_eq!(meta.sort_chunk_size, 32);
+
+    let sorted = storage.read("validators/s/balance/2.msg").unwrap().unwrap();
+    assert_eq!(
+        rmp_serde::from_read_ref::<_, Vec<u64>>(&sorted).unwrap(),
+        (32..64).collect::<Vec<_>>()
+    );
+
+    // The chunk size of the other models is left unchanged
+    assert_eq!(
+        EpochsMeta::restore(storage)
+            .unwrap()
+            .unwrap()
+            .sort_chunk_size,
+        10
+    );
+}
+
+/// Records fixtures from the node at `ENDPOINT_URL` into `FIXTURES_DIR`
+#[tokio::test]
+#[ignore]
+async fn record_fixtures() {
+    dotenv::dotenv().ok();
+
+    let endpoint = env::var("ENDPOINT_URL").unwrap();
+    let fixtures_dir = env::var("FIXTURES_DIR").unwrap();
+
+    common::fixtures::record(&endpoint, Path::new(&fixtures_dir), 45000..45002).await;
+}
@@ -1,11 +1,80 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use eth2_network_config::Eth2NetworkConfig;
+use indexer::node_to_files_indexer::SortChunkSizes;
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 pub struct Cli {
-    #[clap(long)]
-    pub reset: bool,
-
     #[clap(long, env)]
     pub endpoint_url: String,
+
+    /// Name of the network to index, one of those built into Lighthouse (mainnet, prater, ...)
+    #[clap(long, env, default_value = "mainnet")]
+    pub network: String,
+
+    /// Directory holding the config of a custom network, used instead of `--network`
+    #[clap(long, env)]
+    pub testnet_dir: Option<PathBuf>,
+
+    /// Directory the data tree is written to
+    #[clap(long, env, default_value = "../web/public/data")]
+    pub output_dir: PathBuf,
+
+    /// Number of epochs per chunk of the epoch sort indexes
+    #[clap(long, env, default_value_t = 10)]
+    pub epochs_chunk_size: usize,
+
+    /// Number of blocks per chunk of the block sort indexes
+    #[clap(long, env, default_value_t = 10)]
+    pub blocks_chunk_size: usize,
+
+    /// Number of validators per chunk of the validator sort indexes
+    #[clap(long, env, default_value_t = 10)]
+    pub validators_chunk_size: usize,
+
+    #[clap(subcommand)]
+    pub command: Commands,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Index the chain from the beacon node into static files
+    NodeToFiles {
+        /// Remove the previously indexed data instead of resuming from it
+        #[clap(long)]
+        reset: bool,
+    },
+    /// Index the chain from the beacon node, then keep up with its head
+    Follow,
+    /// Index the chain retrieved directly from the p2p network, then keep up with its gossip
+    Direct,
+}
+
+impl Cli {
+    pub fn network_config(&self) -> Result<Eth2NetworkConfig, String> {
+        match &self.testnet_dir {
+            Some(testnet_dir) => Eth2NetworkConfig::load(testnet_dir.clone()),
+            None => Eth2NetworkConfig::constant(&self.network)?
+                .ok_or(format!("Unknown network {}", self.network)),
+        }
+    }
+
+    pub fn sort_chunk_sizes(&self) -> Result<SortChunkSizes, String> {
+        let sort_chunk_sizes = SortChunkSizes {
+            epochs: self.epochs_chunk_size,
+            blocks: self.blocks_chunk_size,
+            validators: self.validators_chunk_size,
+        };
+
+        if sort_chunk_sizes.epochs == 0
+            || sort_chunk_sizes.blocks == 0
+            || sort_chunk_sizes.validators == 0
+        {
+            return Err("Chunk sizes must be greater than 0".to_string());
+        }
+
+        Ok(sort_chunk_sizes)
+    }
 }
@@ -1,6 +1,24 @@
-use crate::cli::Cli;
-use indexer::direct_indexer::Indexer;
+use std::path::PathBuf;
 
-pub fn process(_cli: Cli) -> Result<(), String> {
-    Indexer::start()
+use environment::EnvironmentBuilder;
+use eth2_network_config::Eth2NetworkConfig;
+use indexer::{direct_indexer::Indexer, node_to_files_indexer::SortChunkSizes};
+use lighthouse_types::{ChainSpec, EthSpec};
+
+pub fn process<E: EthSpec>(
+    endpoint_url: String,
+    output_dir: PathBuf,
+    spec: ChainSpec,
+    sort_chunk_sizes: SortChunkSizes,
+    environment_builder: EnvironmentBuilder<E>,
+    network_config: Eth2NetworkConfig,
+) -> Result<(), String> {
+    Indexer::start(
+        endpoint_url,
+        output_dir,
+        spec,
+        sort_chunk_sizes,
+        environment_builder,
+        network_config,
+    )
 }
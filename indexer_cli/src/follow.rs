@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use indexer::{
+    follower::Follower, node_to_files_indexer::SortChunkSizes, storage::local::LocalStorage,
+};
+use lighthouse_types::{ChainSpec, EthSpec};
+
+pub async fn process<E: EthSpec>(
+    endpoint_url: String,
+    output_dir: PathBuf,
+    spec: ChainSpec,
+    sort_chunk_sizes: SortChunkSizes,
+) {
+    let storage = LocalStorage::new(output_dir);
+
+    let mut follower = match Follower::<E, _>::new(endpoint_url, storage, spec).await {
+        Ok(follower) => follower,
+        Err(err) => {
+            log::error!("Error while connecting to the node: {:?}", err);
+            return;
+        }
+    };
+    follower.set_sort_chunk_sizes(sort_chunk_sizes);
+
+    if let Err(err) = follower.follow().await {
+        log::error!("Error while following the chain: {:?}", err);
+    }
+}
@@ -3,12 +3,16 @@
 use clap::StructOpt;
 use dotenv::dotenv;
 use env_logger::{Builder, Env};
+use environment::EnvironmentBuilder;
+use eth2_network_config::Eth2NetworkConfig;
+use lighthouse_types::{EthSpec, EthSpecId};
 
-use crate::cli::Cli;
+use crate::cli::{Cli, Commands};
 
 mod cli;
 mod direct;
-// mod node_to_files;
+mod follow;
+mod node_to_files;
 
 fn main() {
     dotenv().ok();
@@ -16,5 +20,63 @@ fn main() {
 
     let cli = Cli::parse();
 
-    let _res = direct::process(cli);
+    if let Err(err) = run_with_network(cli) {
+        log::error!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Runs the command with the `EthSpec` the preset of the network is based on.
+fn run_with_network(cli: Cli) -> Result<(), String> {
+    let network_config = cli.network_config()?;
+
+    match network_config.eth_spec_id()? {
+        EthSpecId::Mainnet => run(cli, network_config, EnvironmentBuilder::mainnet()),
+        EthSpecId::Minimal => run(cli, network_config, EnvironmentBuilder::minimal()),
+        #[allow(unreachable_patterns)]
+        eth_spec_id => Err(format!("Unsupported preset {:?}", eth_spec_id)),
+    }
+}
+
+fn run<E: EthSpec>(
+    cli: Cli,
+    network_config: Eth2NetworkConfig,
+    environment_builder: EnvironmentBuilder<E>,
+) -> Result<(), String> {
+    let spec = network_config.chain_spec::<E>()?;
+    let sort_chunk_sizes = cli.sort_chunk_sizes()?;
+
+    match cli.command {
+        Commands::NodeToFiles { reset } => {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(node_to_files::process::<E>(
+                cli.endpoint_url,
+                cli.output_dir,
+                spec,
+                sort_chunk_sizes,
+                reset,
+            ))?;
+        }
+        Commands::Follow => {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(follow::process::<E>(
+                cli.endpoint_url,
+                cli.output_dir,
+                spec,
+                sort_chunk_sizes,
+            ));
+        }
+        Commands::Direct => {
+            direct::process(
+                cli.endpoint_url,
+                cli.output_dir,
+                spec,
+                sort_chunk_sizes,
+                environment_builder,
+                network_config,
+            )?;
+        }
+    }
+
+    Ok(())
 }
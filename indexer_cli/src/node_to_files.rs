@@ -1,81 +1,134 @@
 use std::fs;
+use std::path::PathBuf;
 
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
 
-use indexer::node_to_files_indexer::Indexer;
+use indexer::node_to_files_indexer::{Indexer, SortChunkSizes};
 use indexer::retriever::Retriever;
-
-use crate::cli::Cli;
-
-pub async fn process(cli: Cli) {
-    if cli.reset {
-        fs::remove_dir_all("../web/public/data").unwrap();
+use indexer::storage::local::LocalStorage;
+use lighthouse_types::{ChainSpec, EthSpec};
+use shared::utils::clock::Clock;
+use tokio::sync::mpsc;
+
+/// Number of retrieved epochs waiting to be persisted before the retrieval pauses
+const BUFFERED_EPOCHS: usize = 4;
+
+/// Indexes the epochs from the last indexed one up to the head, then commits the data tree.
+/// The errors are returned with the epoch or the path they occurred on, the epochs indexed
+/// before a retrieval error being committed.
+pub async fn process<E: EthSpec>(
+    endpoint_url: String,
+    output_dir: PathBuf,
+    spec: ChainSpec,
+    sort_chunk_sizes: SortChunkSizes,
+    reset: bool,
+) -> Result<(), String> {
+    if reset && output_dir.exists() {
+        fs::remove_dir_all(&output_dir)
+            .map_err(|err| format!("Unable to remove {}: {}", output_dir.display(), err))?;
     }
 
-    fs::create_dir_all("../web/public/data/epochs/e/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/attestations_count/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/deposits_count/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/attester_slashings_count/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/proposer_slashings_count/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/eligible_ether/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/voted_ether/").unwrap();
-    fs::create_dir_all("../web/public/data/epochs/s/global_participation_rate/").unwrap();
-    fs::create_dir_all("../web/public/data/blocks").unwrap();
-    fs::create_dir_all("../web/public/data/blocks/e/").unwrap();
-    fs::create_dir_all("../web/public/data/blocks/a/").unwrap();
-    fs::create_dir_all("../web/public/data/blocks/c/").unwrap();
-    fs::create_dir_all("../web/public/data/blocks/v/").unwrap();
-    fs::create_dir_all("../web/public/data/validators").unwrap();
-
     let running = Arc::new(AtomicBool::new(true));
-
-    let (sender, receiver) = oneshot::channel::<()>();
     let r = running.clone();
 
     ctrlc::set_handler(move || {
         r.store(false, Ordering::SeqCst);
     })
-    .expect("Error setting Ctrl-C handler");
-
-    tokio::spawn(async move {
-        let retriever = retrieve(running);
-        let indexer = Indexer::from(retriever);
-    
-        indexer.index("../web/public/data").unwrap();
-
-        sender.send(()).unwrap();
-    });
-
-    receiver.await.unwrap();
-
-}
-
-
-async fn retrieve(running: Arc<AtomicBool>) -> Retriever {
-    let mut retriever = Retriever::new(cli.endpoint_url);
-    let mut n = 0;
-
-    while running.load(Ordering::SeqCst) {
-        match retriever.retrieve_epoch(n).await {
-            Ok(_) => {
-                n += 1;
-            }
-            Err(err) => {
-                running.store(false, Ordering::SeqCst);
-                log::error!("Error while retrieving epoch {}: {:?}", n, err);
+    .map_err(|err| format!("Error setting Ctrl-C handler: {}", err))?;
+
+    let retriever = Arc::new(Retriever::<E>::new(endpoint_url));
+
+    let genesis_time = retriever
+        .retrieve_genesis_time()
+        .await
+        .map_err(|err| format!("Error while retrieving the genesis: {:?}", err))?;
+
+    let mut indexer = Indexer::new(
+        LocalStorage::new(output_dir.clone()),
+        Clock::new(spec, genesis_time),
+    )
+    .map_err(|err| format!("Unable to open {}: {:?}", output_dir.display(), err))?;
+    indexer.set_sort_chunk_sizes(sort_chunk_sizes);
+    let start_epoch = indexer.start_epoch().map_err(|err| {
+        format!(
+            "Unable to restore the indexing checkpoint from {}: {:?}",
+            output_dir.display(),
+            err
+        )
+    })?;
+    let (sender, mut receiver) = mpsc::channel(BUFFERED_EPOCHS);
+
+    let retrieval = {
+        let retriever = retriever.clone();
+
+        tokio::spawn(async move {
+            let mut n = start_epoch;
+
+            log::info!("Indexing from epoch {}", n);
+
+            while running.load(Ordering::SeqCst) {
+                match retriever.retrieve_epoch(n).await {
+                    Ok(epoch) => {
+                        if sender.send(epoch).await.is_err() {
+                            break;
+                        }
+                        n += 1;
+                    }
+                    Err(err) => {
+                        return Err(format!("Error while retrieving epoch {}: {:?}", n, err));
+                    }
+                }
             }
+
+            Ok(())
+        })
+    };
+
+    match retriever.retrieve_finalized_epoch().await {
+        Ok(finalized_epoch) => indexer
+            .set_finalized_epoch(finalized_epoch)
+            .map_err(|err| {
+                format!(
+                    "Error while persisting the finalized epoch {}: {:?}",
+                    finalized_epoch, err
+                )
+            })?,
+        Err(err) => {
+            log::error!("Error while retrieving the finalized epoch: {:?}", err);
         }
     }
 
+    while let Some(epoch) = receiver.recv().await {
+        let number = epoch.epoch;
+        indexer
+            .index_epoch(epoch)
+            .map_err(|err| format!("Error while indexing epoch {}: {:?}", number, err))?;
+    }
+
+    let retrieval_result = match retrieval.await {
+        Ok(result) => result,
+        Err(err) => Err(format!("The retrieval of the epochs failed: {}", err)),
+    };
+
     match retriever.retrieve_validators().await {
-        Ok(_) => (),
+        Ok(validators) => indexer
+            .index_validators(validators)
+            .map_err(|err| format!("Error while indexing the validators: {:?}", err))?,
         Err(err) => {
             log::error!("Error while retrieving validators: {:?}", err);
         }
     }
 
-    retriever
-}
\ No newline at end of file
+    indexer.commit().map_err(|err| {
+        format!(
+            "Error while committing to {}: {:?}",
+            output_dir.display(),
+            err
+        )
+    })?;
+
+    retrieval_result
+}
@@ -4,16 +4,17 @@ use chrono::{TimeZone, Utc};
 use lighthouse_types::{ChainSpec, Slot};
 use slot_clock::{SlotClock, SystemTimeSlotClock};
 
+#[derive(Clone)]
 pub struct Clock {
     clock: SystemTimeSlotClock,
 }
 
 impl Clock {
-    pub fn new(spec: ChainSpec) -> Self {
+    pub fn new(spec: ChainSpec, genesis_time: u64) -> Self {
         Clock {
             clock: SystemTimeSlotClock::new(
                 spec.genesis_slot,
-                Duration::from_secs(1606824023),
+                Duration::from_secs(genesis_time),
                 Duration::from_secs(spec.seconds_per_slot),
             ),
         }
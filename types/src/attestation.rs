@@ -12,6 +12,9 @@ pub struct AttestationModel {
     pub source: u64,
     pub target: u64,
     pub signature: String,
+    /// Indices of the validators whose attestations are aggregated, resolved from the committee
+    #[serde(default)]
+    pub attesting_indices: Vec<u64>,
 }
 
 pub type AttestationsModelWithId = ModelWithId<Vec<AttestationModel>>;
@@ -27,6 +30,7 @@ impl<T: lighthouse_types::EthSpec> From<&lighthouse_types::Attestation<T>> for A
             source: attestation.data.source.epoch.as_u64(),
             target: attestation.data.target.epoch.as_u64(),
             signature: attestation.signature.to_string(),
+            attesting_indices: Vec::new(),
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::meta::default_sort_chunk_size;
 use crate::model::ModelWithId;
 use serde::Deserialize;
 use serde::Serialize;
@@ -14,6 +15,9 @@ pub struct BlockModel {
     pub voluntary_exits_count: usize,
     pub proposer: u64,
     pub status: String,
+    /// Share of the sync committee whose signature is aggregated in the block, from Altair on
+    #[serde(default)]
+    pub sync_participation_rate: Option<f64>,
 }
 
 pub type BlockModelWithId = ModelWithId<BlockModel>;
@@ -31,6 +35,9 @@ pub struct BlockExtendedModel {
     pub eth1data_deposit_root: Vec<u8>,
     pub eth1data_deposit_count: u64,
     pub eth1data_block_hash: Vec<u8>,
+    /// Members of the sync committee whose signature is missing from the sync aggregate
+    #[serde(default)]
+    pub sync_missed: Vec<u64>,
 }
 
 pub type BlockExtendedModelWithId = ModelWithId<BlockExtendedModel>;
@@ -39,10 +46,16 @@ pub type BlockExtendedModelWithId = ModelWithId<BlockExtendedModel>;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BlocksMeta {
     pub count: usize,
+    /// Number of ids per chunk of the sort indexes
+    #[serde(default = "default_sort_chunk_size")]
+    pub sort_chunk_size: usize,
 }
 
 impl BlocksMeta {
-    pub fn new(count: usize) -> Self {
-        BlocksMeta { count }
+    pub fn new(count: usize, sort_chunk_size: usize) -> Self {
+        BlocksMeta {
+            count,
+            sort_chunk_size,
+        }
     }
 }
@@ -10,3 +10,12 @@ pub struct CommitteeModel {
 }
 
 pub type CommitteesModelWithId = ModelWithId<Vec<CommitteeModel>>;
+
+/// Members of the sync committee of a sync committee period, in the order of the bits of the
+/// sync aggregates. A validator can hold several positions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SyncCommitteeModel {
+    pub validators: Vec<u64>,
+}
+
+pub type SyncCommitteeModelWithId = ModelWithId<SyncCommitteeModel>;
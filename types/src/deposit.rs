@@ -0,0 +1,42 @@
+use serde::Deserialize;
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::meta::default_sort_chunk_size;
+use crate::model::ModelWithId;
+
+/// Deposit included in a block, `index` being its index in the deposit contract, the number of
+/// deposits processed by the chain before it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DepositModel {
+    pub slot: u64,
+    pub index: u64,
+    pub pubkey: Vec<u8>,
+    pub withdrawal_credentials: Vec<u8>,
+    pub amount: u64,
+}
+
+/// Deposits of a block, by slot
+pub type DepositsModelWithId = ModelWithId<Vec<DepositModel>>;
+
+/// Deposit of the list of all the deposits, numbered in the order of their inclusion
+pub type DepositModelWithId = ModelWithId<DepositModel>;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DepositsMeta {
+    pub count: usize,
+    /// Number of ids per chunk of the sort indexes
+    #[serde(default = "default_sort_chunk_size")]
+    pub sort_chunk_size: usize,
+}
+
+impl DepositsMeta {
+    pub fn new(count: usize, sort_chunk_size: usize) -> Self {
+        DepositsMeta {
+            count,
+            sort_chunk_size,
+        }
+    }
+}
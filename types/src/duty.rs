@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Block a validator was scheduled to propose, with the status of the slot: "Proposed",
+/// "Missed" or "Orphaned".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProposalDutyModel {
+    pub slot: u64,
+    pub status: String,
+}
+
+/// Committee a validator sat in, and the slot of the first block including its attestation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AttestationDutyModel {
+    pub slot: u64,
+    pub committee_index: u64,
+    pub inclusion_slot: Option<u64>,
+}
+
+impl AttestationDutyModel {
+    pub fn inclusion_delay(&self) -> Option<u64> {
+        self.inclusion_slot.map(|x| x - self.slot)
+    }
+}
+
+/// Sync committee a validator sat in, and the blocks whose sync aggregate holds its signature
+/// or lacks it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SyncDutyModel {
+    pub period: u64,
+    pub signed_slots: Vec<u64>,
+    pub missed_slots: Vec<u64>,
+}
+
+impl SyncDutyModel {
+    pub fn new(period: u64) -> Self {
+        SyncDutyModel {
+            period,
+            signed_slots: Vec::new(),
+            missed_slots: Vec::new(),
+        }
+    }
+}
+
+/// Duties of a validator during an epoch. Like the balances, the duties history of a validator
+/// is a sequence of them appended epoch after epoch, an epoch indexed again replacing its
+/// record.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidatorDutiesModel {
+    pub epoch: u64,
+    pub proposals: Vec<ProposalDutyModel>,
+    pub attestation: Option<AttestationDutyModel>,
+    #[serde(default)]
+    pub sync: Option<SyncDutyModel>,
+}
+
+impl ValidatorDutiesModel {
+    pub fn new(epoch: u64) -> Self {
+        ValidatorDutiesModel {
+            epoch,
+            proposals: Vec::new(),
+            attestation: None,
+            sync: None,
+        }
+    }
+}
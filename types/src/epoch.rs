@@ -3,6 +3,7 @@ use std::ops::Div;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::meta::default_sort_chunk_size;
 use crate::model::ModelWithId;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -47,6 +48,14 @@ pub struct EpochExtendedModel {
     pub validators_count: usize,
     pub average_validator_balance: u64,
     pub total_validator_balance: u64,
+    /// Ids of the first deposit, of the first voluntary exit and of the first slashing of the
+    /// epoch in the lists of all of them
+    #[serde(default)]
+    pub first_deposit_id: u64,
+    #[serde(default)]
+    pub first_voluntary_exit_id: u64,
+    #[serde(default)]
+    pub first_slashing_id: u64,
 }
 
 pub type EpochExtendedModelWithId = ModelWithId<EpochExtendedModel>;
@@ -79,10 +88,33 @@ impl From<(u64, EpochModel, EpochExtendedModel)> for EpochExtendedView {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct EpochsMeta {
     pub count: usize,
+    /// Number of ids per chunk of the sort indexes
+    #[serde(default = "default_sort_chunk_size")]
+    pub sort_chunk_size: usize,
 }
 
 impl EpochsMeta {
-    pub fn new(count: usize) -> Self {
-        EpochsMeta { count }
+    pub fn new(count: usize, sort_chunk_size: usize) -> Self {
+        EpochsMeta {
+            count,
+            sort_chunk_size,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EpochsCheckpoint {
+    pub last_epoch: u64,
+    /// Missing from the checkpoints persisted before the finalized epoch was tracked
+    #[serde(default)]
+    pub finalized_epoch: Option<u64>,
+}
+
+impl EpochsCheckpoint {
+    pub fn new(last_epoch: u64, finalized_epoch: u64) -> Self {
+        EpochsCheckpoint {
+            last_epoch,
+            finalized_epoch: Some(finalized_epoch),
+        }
     }
 }
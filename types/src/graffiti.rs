@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::model::ModelWithId;
+
+/// Number of characters of the n-grams the graffiti are indexed under, an n-gram selecting its
+/// shard of the graffiti index
+pub const TOKEN_SHARD_CHARS: usize = 2;
+
+/// Clients recognized in the graffiti, by the lowercase name they usually put there
+pub const CLIENTS: [(&str, &str); 6] = [
+    ("lighthouse", "Lighthouse"),
+    ("prysm", "Prysm"),
+    ("teku", "Teku"),
+    ("nimbus", "Nimbus"),
+    ("lodestar", "Lodestar"),
+    ("grandine", "Grandine"),
+];
+
+/// Name of the blocks whose graffiti doesn't tell the client
+pub const UNKNOWN_CLIENT: &str = "Unknown";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraffitiCountModel {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Graffiti and clients of the blocks proposed during an epoch, the most frequent first
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EpochGraffitiModel {
+    pub graffiti: Vec<GraffitiCountModel>,
+    pub clients: Vec<GraffitiCountModel>,
+}
+
+pub type EpochGraffitiModelWithId = ModelWithId<EpochGraffitiModel>;
+
+/// Slots of the blocks whose graffiti holds an n-gram, by n-gram
+pub type GraffitiTokenShard = BTreeMap<String, Vec<u64>>;
+
+/// Splits a graffiti into the lowercase words it is made of. Words shorter than
+/// `TOKEN_SHARD_CHARS` aren't indexed.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| x.chars().count() >= TOKEN_SHARD_CHARS)
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Returns the n-grams of the words of a graffiti, the sequences of `TOKEN_SHARD_CHARS`
+/// characters they hold. A graffiti can only contain a query having all of its n-grams.
+pub fn ngrams(text: &str) -> BTreeSet<String> {
+    tokenize(text)
+        .iter()
+        .flat_map(|word| {
+            let chars = word.chars().collect::<Vec<_>>();
+
+            chars
+                .windows(TOKEN_SHARD_CHARS)
+                .map(|x| x.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns the client named in a graffiti, if any.
+pub fn client_of(text: &str) -> Option<&'static str> {
+    let text = text.to_lowercase();
+
+    CLIENTS
+        .iter()
+        .find(|(token, _)| text.contains(token))
+        .map(|(_, name)| *name)
+}
+
+pub fn graffiti_tokens_dir(base: &str, chunk: u64) -> String {
+    format!("{}/search/t/{}", base, chunk)
+}
+
+/// Returns the path of the shard of the graffiti index holding the n-gram `token` for the blocks
+/// of the epochs of `chunk`, the index being split like the histories of the validators.
+pub fn graffiti_tokens_path(base: &str, chunk: u64, token: &str) -> String {
+    let prefix = token
+        .chars()
+        .take(TOKEN_SHARD_CHARS)
+        .map(|x| if x.is_ascii_alphanumeric() { x } else { '_' })
+        .collect::<String>();
+
+    format!("{}/{}.msg", graffiti_tokens_dir(base, chunk), prefix)
+}
+
+impl EpochGraffitiModel {
+    /// Counts the graffiti of the blocks of an epoch and the clients they name.
+    pub fn from_graffiti<'a>(graffiti: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut texts = BTreeMap::<&str, usize>::new();
+        let mut clients = BTreeMap::<&str, usize>::new();
+
+        for text in graffiti {
+            let text = text.trim();
+
+            if !text.is_empty() {
+                *texts.entry(text).or_default() += 1;
+            }
+            *clients
+                .entry(client_of(text).unwrap_or(UNKNOWN_CLIENT))
+                .or_default() += 1;
+        }
+
+        EpochGraffitiModel {
+            graffiti: sorted_counts(texts),
+            clients: sorted_counts(clients),
+        }
+    }
+}
+
+fn sorted_counts(counts: BTreeMap<&str, usize>) -> Vec<GraffitiCountModel> {
+    let mut counts = counts
+        .into_iter()
+        .map(|(name, count)| GraffitiCountModel {
+            name: name.to_string(),
+            count,
+        })
+        .collect::<Vec<_>>();
+
+    // Stable, so that equal counts stay in alphabetical order
+    counts.sort_by_key(|x| std::cmp::Reverse(x.count));
+
+    counts
+}
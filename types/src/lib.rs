@@ -1,11 +1,18 @@
 pub mod attestation;
 pub mod block;
 pub mod committee;
+pub mod deposit;
+pub mod duty;
 pub mod epoch;
+pub mod graffiti;
+pub mod lookup;
 pub mod meta;
 pub mod model;
 pub mod path;
+pub mod search;
+pub mod slashing;
 pub mod validator;
+pub mod voluntary_exit;
 pub mod vote;
 
 pub use serde::de::DeserializeOwned;
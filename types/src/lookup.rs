@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+/// Number of hex digits of a key selecting the shard of a lookup table holding it
+pub const SHARD_DIGITS: usize = 3;
+
+/// Static tables to look up models by a hash they hold, such as a public key or a root.
+///
+/// A table is split into shards, each one being a map from the lowercase hex keys ending with
+/// the same `SHARD_DIGITS` digits to their values. The last digits are used as the first ones
+/// of the withdrawal credentials are mostly the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupTable {
+    /// Public key to validator index
+    Pubkeys,
+    /// Withdrawal credentials to the indices of the validators sharing them
+    WithdrawalCredentials,
+    /// Block root to slot
+    BlockRoots,
+    /// State root to slot
+    StateRoots,
+}
+
+pub type LookupShard<V> = BTreeMap<String, V>;
+
+impl LookupTable {
+    pub const ALL: [LookupTable; 4] = [
+        LookupTable::Pubkeys,
+        LookupTable::WithdrawalCredentials,
+        LookupTable::BlockRoots,
+        LookupTable::StateRoots,
+    ];
+
+    pub fn dir(&self, base: &str) -> String {
+        match self {
+            LookupTable::Pubkeys => format!("{}/validators/p", base),
+            LookupTable::WithdrawalCredentials => format!("{}/validators/w", base),
+            LookupTable::BlockRoots => format!("{}/blocks/r", base),
+            LookupTable::StateRoots => format!("{}/blocks/sr", base),
+        }
+    }
+
+    /// Returns the path of the shard holding `key`, a normalized hex key.
+    pub fn path(&self, base: &str, key: &str) -> String {
+        let shard = &key[key.len().saturating_sub(SHARD_DIGITS)..];
+
+        format!("{}/{}.msg", self.dir(base), shard)
+    }
+}
+
+/// Returns the key of `bytes` in the lookup tables.
+pub fn to_key(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+/// Normalizes a hex string typed by a user into a key of the lookup tables, if it is one.
+pub fn normalize_key(input: &str) -> Option<String> {
+    let input = input.trim();
+    let hex = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+
+    if hex.len() < SHARD_DIGITS || !hex.chars().all(|x| x.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(hex.to_ascii_lowercase())
+}
@@ -1,6 +1,20 @@
 use serde::Serialize;
 
-use crate::{block::BlocksMeta, epoch::EpochsMeta, validator::ValidatorsMeta};
+use crate::{
+    block::BlocksMeta,
+    deposit::DepositsMeta,
+    epoch::{EpochsCheckpoint, EpochsMeta},
+    slashing::SlashingsMeta,
+    validator::ValidatorsMeta,
+    voluntary_exit::VoluntaryExitsMeta,
+};
+
+/// Number of ids per chunk of the sort indexes, unless configured otherwise
+pub const DEFAULT_SORT_CHUNK_SIZE: usize = 10;
+
+pub(crate) fn default_sort_chunk_size() -> usize {
+    DEFAULT_SORT_CHUNK_SIZE
+}
 
 pub trait Meta: Serialize + Send {
     fn to_path(base: &str) -> String;
@@ -18,8 +32,32 @@ impl Meta for BlocksMeta {
     }
 }
 
+impl Meta for EpochsCheckpoint {
+    fn to_path(base: &str) -> String {
+        format!("{}/epochs/checkpoint.msg", base)
+    }
+}
+
 impl Meta for ValidatorsMeta {
     fn to_path(base: &str) -> String {
         format!("{}/validators/meta.msg", base)
     }
 }
+
+impl Meta for DepositsMeta {
+    fn to_path(base: &str) -> String {
+        format!("{}/deposits/meta.msg", base)
+    }
+}
+
+impl Meta for VoluntaryExitsMeta {
+    fn to_path(base: &str) -> String {
+        format!("{}/voluntary_exits/meta.msg", base)
+    }
+}
+
+impl Meta for SlashingsMeta {
+    fn to_path(base: &str) -> String {
+        format!("{}/slashings/meta.msg", base)
+    }
+}
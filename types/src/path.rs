@@ -1,9 +1,13 @@
 use crate::{
     attestation::AttestationsModelWithId,
     block::{BlockExtendedModelWithId, BlockModelWithId},
-    committee::CommitteesModelWithId,
+    committee::{CommitteesModelWithId, SyncCommitteeModelWithId},
+    deposit::{DepositModelWithId, DepositsModelWithId},
     epoch::{EpochExtendedModelWithId, EpochModelWithId},
+    graffiti::EpochGraffitiModelWithId,
+    slashing::{SlashingModelWithId, SlashingsModelWithId, ValidatorSlashingsModelWithId},
     validator::ValidatorModelWithId,
+    voluntary_exit::{VoluntaryExitModelWithId, VoluntaryExitsModelWithId},
     vote::VotesModelWithId,
 };
 
@@ -27,6 +31,12 @@ impl ToPath<u64> for EpochExtendedModelWithId {
     }
 }
 
+impl ToPath<u64> for EpochGraffitiModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/epochs/g/{}.msg", base, id)
+    }
+}
+
 impl ToPath<u64> for BlockModelWithId {
     fn to_path(base: &str, id: u64) -> String {
         format!("{}/blocks/{}.msg", base, id)
@@ -57,8 +67,77 @@ impl ToPath<u64> for AttestationsModelWithId {
     }
 }
 
+impl ToPath<u64> for DepositsModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/blocks/d/{}.msg", base, id)
+    }
+}
+
+impl ToPath<u64> for VoluntaryExitsModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/blocks/x/{}.msg", base, id)
+    }
+}
+
+impl ToPath<u64> for SlashingsModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/blocks/sl/{}.msg", base, id)
+    }
+}
+
+impl ToPath<u64> for DepositModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/deposits/{}.msg", base, id)
+    }
+}
+
+impl ToPath<u64> for VoluntaryExitModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/voluntary_exits/{}.msg", base, id)
+    }
+}
+
+impl ToPath<u64> for SlashingModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/slashings/{}.msg", base, id)
+    }
+}
+
+impl ToPath<u64> for SyncCommitteeModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/sync_committees/{}.msg", base, id)
+    }
+}
+
 impl ToPath<u64> for ValidatorModelWithId {
     fn to_path(base: &str, id: u64) -> String {
         format!("{}/validators/{}.msg", base, id)
     }
 }
+
+impl ToPath<u64> for ValidatorSlashingsModelWithId {
+    fn to_path(base: &str, id: u64) -> String {
+        format!("{}/validators/sl/{}.msg", base, id)
+    }
+}
+
+/// Returns the directory of the balance histories of all the validators over the epochs of
+/// `chunk`.
+pub fn balance_history_dir(base: &str, chunk: u64) -> String {
+    format!("{}/validators/b/{}", base, chunk)
+}
+
+/// Returns the path of the balance history of `validator` over the epochs of `chunk`.
+pub fn balance_history_path(base: &str, chunk: u64, validator: u64) -> String {
+    format!("{}/{}.msg", balance_history_dir(base, chunk), validator)
+}
+
+/// Returns the directory of the duties of all the validators over the epochs of `chunk`.
+pub fn duties_dir(base: &str, chunk: u64) -> String {
+    format!("{}/validators/d/{}", base, chunk)
+}
+
+/// Returns the path of the duties of `validator` over the epochs of `chunk`.
+pub fn duties_path(base: &str, chunk: u64, validator: u64) -> String {
+    format!("{}/{}.msg", duties_dir(base, chunk), validator)
+}
@@ -0,0 +1,47 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Number of leading characters of a normalized graffiti selecting its shard of the search
+/// index. Shorter queries aren't matched against the graffiti.
+pub const GRAFFITI_SHARD_CHARS: usize = 2;
+
+/// Graffiti of the search index, along with the last block bearing it.
+///
+/// The rest of the search index is made of the lookup tables of the roots and public keys, and
+/// of the metas bounding the epoch numbers, slots and validator indices.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraffitiEntryModel {
+    pub text: String,
+    pub last_slot: u64,
+}
+
+/// Graffiti of a shard of the search index, by normalized graffiti
+pub type GraffitiShard = BTreeMap<String, GraffitiEntryModel>;
+
+/// Normalizes a graffiti or a query so that they match regardless of case and surrounding
+/// spaces.
+pub fn normalize_graffiti(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+pub fn graffiti_dir(base: &str) -> String {
+    format!("{}/search/g", base)
+}
+
+/// Returns the path of the shard of the search index holding the graffiti starting like
+/// `normalized`, if it is long enough to be searched.
+pub fn graffiti_shard_path(base: &str, normalized: &str) -> Option<String> {
+    let prefix = normalized
+        .chars()
+        .take(GRAFFITI_SHARD_CHARS)
+        .map(|x| if x.is_ascii_alphanumeric() { x } else { '_' })
+        .collect::<String>();
+
+    if prefix.len() < GRAFFITI_SHARD_CHARS {
+        return None;
+    }
+
+    Some(format!("{}/{}.msg", graffiti_dir(base), prefix))
+}
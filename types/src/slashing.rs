@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::model::ModelWithId;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockHeaderModel {
+    pub slot: u64,
+    pub proposer_index: u64,
+    pub parent_root: Vec<u8>,
+    pub state_root: Vec<u8>,
+    pub body_root: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AttestationDataModel {
+    pub slot: u64,
+    pub committee_index: u64,
+    pub beacon_block_root: Vec<u8>,
+    pub source: u64,
+    pub target: u64,
+}
+
+/// Pair of conflicting messages signed by the offenders of a slashing
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SlashingConflictModel {
+    /// Two different headers proposed for the same slot
+    Proposer(BlockHeaderModel, BlockHeaderModel),
+    /// Two attestations making a double vote or a surround vote
+    Attester(AttestationDataModel, AttestationDataModel),
+}
+
+/// Slashing included in the block of `slot`. The offenders are the validators signing both
+/// messages of the conflict, sorted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SlashingModel {
+    pub slot: u64,
+    pub offenders: Vec<u64>,
+    pub conflict: SlashingConflictModel,
+}
+
+/// Slashings of a block, by slot, the proposer slashings coming first
+pub type SlashingsModelWithId = ModelWithId<Vec<SlashingModel>>;
+
+/// Slashing of the list of all the slashings, numbered in the order of their inclusion
+pub type SlashingModelWithId = ModelWithId<SlashingModel>;
+
+/// Ids of the slashings of a validator in the list of all of them, sorted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ValidatorSlashingsModel {
+    pub slashings: Vec<u64>,
+}
+
+/// Slashings of a validator, by validator index
+pub type ValidatorSlashingsModelWithId = ModelWithId<ValidatorSlashingsModel>;
+
+/// The slashings have no sort indexes, they are only listed in the order of their inclusion.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlashingsMeta {
+    pub count: usize,
+}
+
+impl SlashingsMeta {
+    pub fn new(count: usize) -> Self {
+        SlashingsMeta { count }
+    }
+}
+
+#[cfg(feature = "indexing")]
+impl From<&lighthouse_types::BeaconBlockHeader> for BlockHeaderModel {
+    fn from(header: &lighthouse_types::BeaconBlockHeader) -> Self {
+        BlockHeaderModel {
+            slot: header.slot.as_u64(),
+            proposer_index: header.proposer_index,
+            parent_root: header.parent_root.as_bytes().to_vec(),
+            state_root: header.state_root.as_bytes().to_vec(),
+            body_root: header.body_root.as_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "indexing")]
+impl From<&lighthouse_types::AttestationData> for AttestationDataModel {
+    fn from(data: &lighthouse_types::AttestationData) -> Self {
+        AttestationDataModel {
+            slot: data.slot.as_u64(),
+            committee_index: data.index,
+            beacon_block_root: data.beacon_block_root.as_bytes().to_vec(),
+            source: data.source.epoch.as_u64(),
+            target: data.target.epoch.as_u64(),
+        }
+    }
+}
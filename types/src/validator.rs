@@ -1,6 +1,7 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::meta::default_sort_chunk_size;
 use crate::model::ModelWithId;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -37,13 +38,36 @@ impl From<ValidatorModelWithId> for ValidatorView {
     }
 }
 
+/// Number of epochs grouped in the same file of the histories of a validator, such as its
+/// balances and duties
+pub const HISTORY_CHUNK_EPOCHS: u64 = 1024;
+
+/// Balance of a validator at the start of an epoch. The balance history of a validator is a
+/// sequence of them, appended epoch after epoch: an epoch indexed again replaces its balance.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidatorBalanceModel {
+    pub epoch: u64,
+    pub balance: u64,
+}
+
+/// Returns the chunk of the histories of a validator holding the records of `epoch`.
+pub fn history_chunk(epoch: u64) -> u64 {
+    epoch / HISTORY_CHUNK_EPOCHS
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ValidatorsMeta {
     pub count: usize,
+    /// Number of ids per chunk of the sort indexes
+    #[serde(default = "default_sort_chunk_size")]
+    pub sort_chunk_size: usize,
 }
 
 impl ValidatorsMeta {
-    pub fn new(count: usize) -> Self {
-        ValidatorsMeta { count }
+    pub fn new(count: usize, sort_chunk_size: usize) -> Self {
+        ValidatorsMeta {
+            count,
+            sort_chunk_size,
+        }
     }
 }
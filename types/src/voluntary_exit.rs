@@ -0,0 +1,40 @@
+use serde::Deserialize;
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::meta::default_sort_chunk_size;
+use crate::model::ModelWithId;
+
+/// Voluntary exit included in a block: `validator_index` leaves the chain from `epoch`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VoluntaryExitModel {
+    pub slot: u64,
+    pub validator_index: u64,
+    pub epoch: u64,
+}
+
+/// Voluntary exits of a block, by slot
+pub type VoluntaryExitsModelWithId = ModelWithId<Vec<VoluntaryExitModel>>;
+
+/// Voluntary exit of the list of all the voluntary exits, numbered in the order of their
+/// inclusion
+pub type VoluntaryExitModelWithId = ModelWithId<VoluntaryExitModel>;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VoluntaryExitsMeta {
+    pub count: usize,
+    /// Number of ids per chunk of the sort indexes
+    #[serde(default = "default_sort_chunk_size")]
+    pub sort_chunk_size: usize,
+}
+
+impl VoluntaryExitsMeta {
+    pub fn new(count: usize, sort_chunk_size: usize) -> Self {
+        VoluntaryExitsMeta {
+            count,
+            sort_chunk_size,
+        }
+    }
+}
@@ -10,7 +10,7 @@ export function useBlocks(pageIndex: number, pageSize: number, sortId: string, s
   return useSWR(
     () => {
       return {
-        type: "blocks", pageIndex, pageSize, totalCount: meta.count, sortId, sortDesc
+        type: "blocks", pageIndex, pageSize, totalCount: meta.count, sortChunkSize: meta.sort_chunk_size, sortId, sortDesc
       }
     },
     key => Blocks.page(
@@ -18,6 +18,7 @@ export function useBlocks(pageIndex: number, pageSize: number, sortId: string, s
       key.pageIndex,
       key.pageSize,
       key.totalCount,
+      key.sortChunkSize,
       key.sortId,
       key.sortDesc
     )
@@ -4,6 +4,7 @@ import { TabPanel, useTabs } from 'react-headless-tabs';
 import cx from 'classnames';
 import Breadcrumb from "../../components/breadcrumb";
 import TabSelector from '../../components/tab-selector';
+import Percentage from "../../components/percentage";
 import { useBlock, useAttestations, useVotes, useCommittees } from "../../hooks/blocks";
 
 const Validators = ({ validators, aggregation_bits = [] }) => {
@@ -182,6 +183,14 @@ export default () => {
               <dd>{block && block.epoch}</dd>
               <dt>Slot</dt>
               <dd>{block && block.slot}</dd>
+              {block && block.sync_participation_rate != null &&
+                <>
+                  <dt>Sync participation</dt>
+                  <dd><Percentage value={block.sync_participation_rate} /></dd>
+                  <dt>Missed sync signatures</dt>
+                  <dd className="flex flex-wrap"><Validators validators={block.sync_missed} /></dd>
+                </>
+              }
             </dl>
           </TabPanel>
 
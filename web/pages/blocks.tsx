@@ -57,6 +57,7 @@ export default (props) => {
       Cell: ({ value }) => <Number value={value} />
     },
     {
+      id: "proposer_slashings_count",
       accessor: (row, rowIndex) => { return { p: row.proposer_slashings_count, a: row.attester_slashings_count } },
       Header: "Slashings P / A",
       Cell: ({ value }) =>
@@ -68,6 +69,11 @@ export default (props) => {
       accessor: "voluntary_exits_count",
       Header: "Exits",
       Cell: ({ value }) => <Number value={value} />
+    },
+    {
+      accessor: "sync_participation_rate",
+      Header: "Sync",
+      Cell: ({ value }) => value != null ? <Percentage value={value} /> : <span>-</span>
     }
   ];
 
@@ -78,7 +78,7 @@ export default (props) => {
       let sortId = sortBy.length > 0 ? sortBy[0].id : "validator_index";
       let sortDesc = sortBy.length > 0 ? sortBy[0].desc : false;
 
-      if (["validator_index", "activation_epoch"].indexOf(sortId) > -1) sortId = "default";
+      if (["validator_index"].indexOf(sortId) > -1) sortId = "default";
 
       setData(
         await validators.page(
@@ -1,19 +1,40 @@
+use std::collections::BTreeSet;
+
+use futures::future::try_join_all;
 use js_sys::Promise;
 use types::attestation::{AttestationModel, AttestationsModelWithId};
 use types::block::{
     BlockExtendedModel, BlockExtendedModelWithId, BlockModel, BlockModelWithId, BlocksMeta,
 };
 use types::committee::{CommitteeModel, CommitteesModelWithId};
+use types::deposit::{DepositModel, DepositsModelWithId};
+use types::epoch::EpochsMeta;
+use types::graffiti::{graffiti_tokens_path, ngrams, GraffitiTokenShard};
+use types::lookup::LookupTable;
 use types::meta::Meta;
 use types::path::ToPath;
+use types::slashing::{SlashingModel, SlashingsModelWithId};
+use types::validator::history_chunk;
+use types::voluntary_exit::{VoluntaryExitModel, VoluntaryExitsModelWithId};
 use types::vote::{VoteModel, VotesModelWithId};
 use wasm_bindgen::prelude::*;
 
 use crate::views::attestations::AttestationView;
 use crate::views::blocks::{BlockExtendedView, BlockView};
 use crate::views::committees::CommitteeView;
+use crate::views::deposits::DepositView;
+use crate::views::slashings::SlashingView;
+use crate::views::voluntary_exits::VoluntaryExitView;
 use crate::views::votes::VoteView;
-use crate::{fetcher::fetch, page::page, to_js};
+use crate::{
+    fetcher::{fetch, fetch_optional, lookup},
+    page::page,
+    sort::{SortBy, SortMeta},
+    to_js, DeserializeError,
+};
+
+/// Number of blocks fetched at once while checking their graffiti against a query
+const GRAFFITI_BATCH_SIZE: usize = 20;
 
 #[wasm_bindgen]
 pub struct Blocks {}
@@ -21,12 +42,38 @@ pub struct Blocks {}
 #[wasm_bindgen]
 impl Blocks {
     pub async fn get(base_url: String, block: u64) -> Result<JsValue, JsValue> {
-        let block_url = BlockModelWithId::to_path(&*base_url, block);
-        let extended_block_url = BlockExtendedModelWithId::to_path(&*base_url, block);
+        let view = fetch_block(&base_url, block).await?;
+
+        to_js(&view).map_err(Into::into)
+    }
 
-        let model = fetch::<BlockModel>(block_url).await?;
-        let extended_model = fetch::<BlockExtendedModel>(extended_block_url).await?;
-        to_js::<BlockExtendedView>(&(block, model, extended_model).into()).map_err(Into::into)
+    /// Returns the block whose root is `root`, a hex string, or null if unknown.
+    pub async fn by_root(base_url: String, root: String) -> Result<JsValue, JsValue> {
+        match lookup::<u64>(base_url.clone(), LookupTable::BlockRoots, &root).await? {
+            Some(block) => Blocks::get(base_url, block).await,
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Returns the block whose state root is `state_root`, a hex string, or null if unknown.
+    pub async fn by_state_root(base_url: String, state_root: String) -> Result<JsValue, JsValue> {
+        match lookup::<u64>(base_url.clone(), LookupTable::StateRoots, &state_root).await? {
+            Some(block) => Blocks::get(base_url, block).await,
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// Returns the last `limit` blocks whose graffiti contains `query`, the newest first. The
+    /// candidates are the blocks holding every n-gram of the query in the graffiti index, so that
+    /// the query can match anywhere in a word.
+    pub async fn by_graffiti(
+        base_url: String,
+        query: String,
+        limit: usize,
+    ) -> Result<JsValue, JsValue> {
+        let blocks = blocks_by_graffiti(&base_url, &query, limit).await?;
+
+        to_js(&blocks).map_err(Into::into)
     }
 
     pub async fn committees(base_url: String, block: u64) -> Result<JsValue, JsValue> {
@@ -63,11 +110,45 @@ impl Blocks {
         to_js(&r).map_err(Into::into)
     }
 
+    pub async fn deposits(base_url: String, block: u64) -> Result<JsValue, JsValue> {
+        let deposits_url = DepositsModelWithId::to_path(&*base_url, block);
+        let deposits = fetch::<Vec<DepositModel>>(deposits_url)
+            .await?
+            .into_iter()
+            .map(DepositView::from)
+            .collect::<Vec<_>>();
+
+        to_js(&deposits).map_err(Into::into)
+    }
+
+    pub async fn voluntary_exits(base_url: String, block: u64) -> Result<JsValue, JsValue> {
+        let voluntary_exits_url = VoluntaryExitsModelWithId::to_path(&*base_url, block);
+        let voluntary_exits = fetch::<Vec<VoluntaryExitModel>>(voluntary_exits_url)
+            .await?
+            .into_iter()
+            .map(VoluntaryExitView::from)
+            .collect::<Vec<_>>();
+
+        to_js(&voluntary_exits).map_err(Into::into)
+    }
+
+    pub async fn slashings(base_url: String, block: u64) -> Result<JsValue, JsValue> {
+        let slashings_url = SlashingsModelWithId::to_path(&*base_url, block);
+        let slashings = fetch::<Vec<SlashingModel>>(slashings_url)
+            .await?
+            .into_iter()
+            .map(SlashingView::from)
+            .collect::<Vec<_>>();
+
+        to_js(&slashings).map_err(Into::into)
+    }
+
     pub fn page(
         base_url: String,
         page_index: usize,
         page_size: usize,
         total_count: usize,
+        sort_chunk_size: usize,
         sort_id: String,
         sort_desc: bool,
     ) -> Promise {
@@ -76,9 +157,8 @@ impl Blocks {
             "blocks".to_string(),
             page_index,
             page_size,
-            sort_id,
-            sort_desc,
-            total_count,
+            SortBy::new(sort_id, sort_desc),
+            SortMeta::new(total_count, sort_chunk_size),
         )
     }
 
@@ -88,3 +168,151 @@ impl Blocks {
         to_js(&meta).map_err(Into::into)
     }
 }
+
+async fn fetch_block(base_url: &str, block: u64) -> Result<BlockExtendedView, DeserializeError> {
+    let block_url = BlockModelWithId::to_path(base_url, block);
+    let extended_block_url = BlockExtendedModelWithId::to_path(base_url, block);
+
+    let model = fetch::<BlockModel>(block_url).await?;
+    let extended_model = fetch::<BlockExtendedModel>(extended_block_url).await?;
+
+    Ok((block, model, extended_model).into())
+}
+
+async fn blocks_by_graffiti(
+    base_url: &str,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<BlockExtendedView>, DeserializeError> {
+    let query = query.trim().to_lowercase();
+    let tokens = ngrams(&query);
+
+    if tokens.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let meta = fetch::<EpochsMeta>(EpochsMeta::to_path(base_url)).await?;
+    let last_epoch = match meta.count.checked_sub(1) {
+        Some(epoch) => epoch as u64,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut blocks = Vec::new();
+
+    for chunk in (0..=history_chunk(last_epoch)).rev() {
+        let slots = graffiti_slots(base_url, chunk, &tokens).await?;
+
+        // The index only tells the blocks holding every n-gram, not in the order of the query
+        for batch in slots.chunks(GRAFFITI_BATCH_SIZE) {
+            let fetched =
+                try_join_all(batch.iter().map(|slot| fetch_block(base_url, *slot))).await?;
+
+            blocks.extend(fetched.into_iter().filter(|block| {
+                block
+                    .extended_model
+                    .graffiti_text
+                    .to_lowercase()
+                    .contains(&query)
+            }));
+
+            if blocks.len() >= limit {
+                blocks.truncate(limit);
+                return Ok(blocks);
+            }
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// Returns the slots of the blocks of `chunk` whose graffiti holds each of `tokens`, the last
+/// first.
+async fn graffiti_slots(
+    base_url: &str,
+    chunk: u64,
+    tokens: &BTreeSet<String>,
+) -> Result<Vec<u64>, DeserializeError> {
+    let shards = try_join_all(tokens.iter().map(|token| {
+        fetch_optional::<GraffitiTokenShard>(graffiti_tokens_path(base_url, chunk, token))
+    }))
+    .await?;
+
+    Ok(matching_slots(tokens, shards))
+}
+
+/// Intersects the slots of `tokens` in their shard of the graffiti index, the last first.
+fn matching_slots(tokens: &BTreeSet<String>, shards: Vec<Option<GraffitiTokenShard>>) -> Vec<u64> {
+    let mut slots: Option<BTreeSet<u64>> = None;
+
+    for (token, shard) in tokens.iter().zip(shards) {
+        let matching = shard
+            .and_then(|mut shard| shard.remove(token))
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+
+        slots = Some(match slots {
+            Some(slots) => slots.intersection(&matching).copied().collect(),
+            None => matching,
+        });
+    }
+
+    slots.unwrap_or_default().into_iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use types::graffiti::{graffiti_tokens_path, ngrams, GraffitiTokenShard};
+
+    use crate::blocks::matching_slots;
+
+    const GRAFFITI: [(&str, u64); 3] = [
+        ("Lighthouse/v2.1.0", 40),
+        ("teku/v22.1.0", 41),
+        ("Prysm", 42),
+    ];
+
+    /// Returns the shards of `tokens` in the graffiti index of `GRAFFITI`.
+    fn shards(tokens: &BTreeSet<String>) -> Vec<Option<GraffitiTokenShard>> {
+        tokens
+            .iter()
+            .map(|token| {
+                let path = graffiti_tokens_path("", 0, token);
+                let mut shard = GraffitiTokenShard::new();
+
+                for (text, slot) in GRAFFITI.iter() {
+                    for indexed in ngrams(text) {
+                        if graffiti_tokens_path("", 0, &indexed) == path {
+                            shard.entry(indexed).or_default().push(*slot);
+                        }
+                    }
+                }
+
+                Some(shard).filter(|x| !x.is_empty())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn query_in_the_middle_of_a_word() {
+        let tokens = ngrams("thouse");
+
+        assert_eq!(matching_slots(&tokens, shards(&tokens)), vec![40]);
+    }
+
+    #[test]
+    fn query_held_by_several_graffiti() {
+        let tokens = ngrams("v2");
+
+        assert_eq!(matching_slots(&tokens, shards(&tokens)), vec![41, 40]);
+    }
+
+    #[test]
+    fn query_not_held() {
+        let tokens = ngrams("nimbus");
+
+        assert!(matching_slots(&tokens, shards(&tokens)).is_empty());
+    }
+}
@@ -0,0 +1,55 @@
+use js_sys::Promise;
+use types::deposit::{DepositModel, DepositsMeta};
+use types::meta::Meta;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    fetcher::fetch,
+    page::page,
+    sort::{SortBy, SortMeta},
+    to_js,
+    views::deposits::DepositView,
+};
+
+/// Deposits of all the blocks, in the order of their inclusion
+#[wasm_bindgen]
+pub struct Deposits {
+    base_url: String,
+    meta: DepositsMeta,
+}
+
+#[wasm_bindgen]
+impl Deposits {
+    fn new(base_url: String, meta: DepositsMeta) -> Deposits {
+        Deposits { base_url, meta }
+    }
+
+    #[wasm_bindgen]
+    pub async fn build(base_url: String) -> Result<Deposits, JsValue> {
+        let url = base_url + "/data";
+        let meta = fetch(DepositsMeta::to_path(&*url)).await?;
+
+        Ok(Deposits::new(url, meta))
+    }
+
+    pub fn page(
+        &self,
+        page_index: usize,
+        page_size: usize,
+        sort_id: String,
+        sort_desc: bool,
+    ) -> Promise {
+        page::<DepositModel, DepositView>(
+            self.base_url.clone(),
+            "deposits".to_string(),
+            page_index,
+            page_size,
+            SortBy::new(sort_id, sort_desc),
+            SortMeta::new(self.meta.count, self.meta.sort_chunk_size),
+        )
+    }
+
+    pub fn meta(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.meta).map_err(Into::into)
+    }
+}
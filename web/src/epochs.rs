@@ -5,12 +5,18 @@ use types::{
         EpochExtendedModel, EpochExtendedModelWithId, EpochExtendedView, EpochModel,
         EpochModelWithId, EpochView, EpochsMeta,
     },
+    graffiti::{EpochGraffitiModel, EpochGraffitiModelWithId},
     meta::Meta,
 };
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::future_to_promise;
 
-use crate::{fetcher::fetch, page::page, to_js};
+use crate::{
+    fetcher::fetch,
+    page::page,
+    sort::{SortBy, SortMeta},
+    to_js,
+};
 
 #[wasm_bindgen]
 pub struct Epochs {
@@ -43,6 +49,17 @@ impl Epochs {
         })
     }
 
+    /// Returns the graffiti and the clients of the blocks proposed during `epoch`, the most
+    /// frequent first.
+    pub fn graffiti(&self, epoch: u64) -> Promise {
+        let graffiti_url = EpochGraffitiModelWithId::to_path(&*self.base_url, epoch);
+
+        future_to_promise(async move {
+            let model = fetch::<EpochGraffitiModel>(graffiti_url).await?;
+            to_js(&model).map_err(Into::into)
+        })
+    }
+
     pub fn page(
         &self,
         page_index: usize,
@@ -55,9 +72,8 @@ impl Epochs {
             "epochs".to_string(),
             page_index,
             page_size,
-            sort_id,
-            sort_desc,
-            self.meta.count,
+            SortBy::new(sort_id, sort_desc),
+            SortMeta::new(self.meta.count, self.meta.sort_chunk_size),
         )
     }
 
@@ -1,6 +1,12 @@
 use bytes::Buf;
 use futures::future::try_join_all;
-use types::{model::ModelWithId, path::ToPath, DeserializeOwned, Serialize};
+use reqwest::StatusCode;
+use types::{
+    lookup::{normalize_key, LookupShard, LookupTable},
+    model::ModelWithId,
+    path::ToPath,
+    DeserializeOwned, Serialize,
+};
 
 use crate::DeserializeError;
 
@@ -10,6 +16,40 @@ pub async fn fetch<T: DeserializeOwned>(url: String) -> Result<T, DeserializeErr
     rmp_serde::from_read::<_, T>(response.bytes().await?.reader()).map_err(Into::into)
 }
 
+/// Fetches a file which may not exist, such as a shard of a lookup table.
+pub async fn fetch_optional<T: DeserializeOwned>(
+    url: String,
+) -> Result<Option<T>, DeserializeError> {
+    let response = reqwest::get(url).await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+
+    rmp_serde::from_read::<_, T>(response.bytes().await?.reader())
+        .map(Some)
+        .map_err(Into::into)
+}
+
+/// Fetches a file of values encoded one after the other, as appended by the indexer. A missing
+/// file holds no value.
+pub async fn fetch_appended<T: DeserializeOwned>(url: String) -> Result<Vec<T>, DeserializeError> {
+    let response = reqwest::get(url).await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+
+    let mut reader = response.bytes().await?.reader();
+    let mut values = Vec::new();
+
+    while reader.get_ref().has_remaining() {
+        values.push(rmp_serde::from_read::<_, T>(&mut reader)?);
+    }
+
+    Ok(values)
+}
+
 pub async fn fetch_all<T>(
     base_url: String,
     range: Vec<u64>,
@@ -27,3 +67,21 @@ where
 
     try_join_all(futures).await
 }
+
+/// Returns the value of `key` in `table`. Keys which aren't hex strings have no value.
+pub async fn lookup<V: DeserializeOwned>(
+    base_url: String,
+    table: LookupTable,
+    key: &str,
+) -> Result<Option<V>, DeserializeError> {
+    let key = match normalize_key(key) {
+        Some(key) => key,
+        None => return Ok(None),
+    };
+
+    Ok(
+        fetch_optional::<LookupShard<V>>(table.path(&*base_url, &key))
+            .await?
+            .and_then(|mut shard| shard.remove(&key)),
+    )
+}
@@ -3,13 +3,17 @@ use thiserror::Error;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
 pub mod blocks;
+pub mod deposits;
 pub mod epochs;
 mod fetcher;
 mod get;
 mod page;
+pub mod search;
+pub mod slashings;
 pub mod sort;
 pub mod validators;
 pub mod views;
+pub mod voluntary_exits;
 
 #[wasm_bindgen]
 extern "C" {
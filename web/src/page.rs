@@ -1,4 +1,4 @@
-use std::cmp::min;
+use std::{cmp::min, future::Future};
 
 use futures::future::try_join_all;
 use js_sys::{Array, Promise};
@@ -8,7 +8,7 @@ use wasm_bindgen_futures::future_to_promise;
 
 use crate::{
     fetcher::{fetch, fetch_all},
-    sort::{Paginate, SortBy},
+    sort::{Paginate, SortBy, SortMeta},
     to_js, DeserializeError,
 };
 
@@ -17,73 +17,23 @@ pub fn page<M, V>(
     model_plural: String,
     page_index: usize,
     page_size: usize,
-    sort_id: String,
-    sort_desc: bool,
-    total_count: usize,
+    sort_by: SortBy,
+    meta: SortMeta,
 ) -> Promise
 where
     M: DeserializeOwned + Serialize + Send,
     V: Serialize,
     ModelWithId<M>: Into<V> + ToPath<u64>,
 {
-    let sort_by = SortBy::new(sort_id, sort_desc);
-
     future_to_promise(async move {
-        let range = match sort_by.id.as_str() {
-            "default" => {
-                let range = if sort_by.desc {
-                    let end = total_count - page_index * page_size;
-                    let start = end.saturating_sub(page_size);
-                    start..end
-                } else {
-                    let start = page_index * page_size;
-                    let end = min(start + page_size, total_count);
-                    start..end
-                };
-
-                let result = if sort_by.desc {
-                    range.map(|x| x as u64).rev().collect()
-                } else {
-                    range.map(|x| x as u64).collect()
-                };
-
-                Ok(result)
-            }
-            _ => {
-                let mut futures = vec![];
-                for page_number in Paginate::new(total_count, page_index + 1, page_size, &sort_by) {
-                    let url = format!(
-                        "{}/{}/s/{}/{}.msg",
-                        base_url.clone(),
-                        model_plural.clone(),
-                        sort_by.clone().id,
-                        page_number
-                    );
-                    futures.push(fetch::<Vec<u64>>(url));
-                }
-
-                let range = try_join_all(futures)
-                    .await
-                    .map(|x| x.into_iter().flatten().collect());
-
-                if sort_by.desc {
-                    let skip = if page_index == 0 {
-                        0_usize
-                    } else {
-                        10 - total_count as usize % 10
-                    };
-                    range.map(|x: Vec<u64>| {
-                        x.into_iter()
-                            .rev()
-                            .skip(skip)
-                            .take(page_size as usize)
-                            .collect()
-                    })
-                } else {
-                    range
-                }
-            }
-        }?;
+        let range = page_ids(page_index, page_size, &sort_by, meta, |page_number| {
+            let url = format!(
+                "{}/{}/s/{}/{}.msg",
+                base_url, model_plural, sort_by.id, page_number
+            );
+            fetch::<Vec<u64>>(url)
+        })
+        .await?;
 
         get_paginated::<M, V>(base_url, range)
             .await
@@ -91,6 +41,66 @@ where
     })
 }
 
+/// Returns the ids of the rows of a page. The ids of the default sort are the positions
+/// themselves, the others are read from the chunks of the sort index fetched by `fetch_chunk`.
+async fn page_ids<F, Fut>(
+    page_index: usize,
+    page_size: usize,
+    sort_by: &SortBy,
+    meta: SortMeta,
+    fetch_chunk: F,
+) -> Result<Vec<u64>, DeserializeError>
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future<Output = Result<Vec<u64>, DeserializeError>>,
+{
+    let total_count = meta.total_count;
+
+    if sort_by.id == "default" {
+        let range = if sort_by.desc {
+            let end = total_count - page_index * page_size;
+            let start = end.saturating_sub(page_size);
+            start..end
+        } else {
+            let start = page_index * page_size;
+            let end = min(start + page_size, total_count);
+            start..end
+        };
+
+        let result = if sort_by.desc {
+            range.map(|x| x as u64).rev().collect()
+        } else {
+            range.map(|x| x as u64).collect()
+        };
+
+        return Ok(result);
+    }
+
+    let paginate = Paginate::new(
+        total_count,
+        page_index + 1,
+        page_size,
+        meta.chunk_size,
+        sort_by,
+    );
+    let rows = paginate.rows();
+    let offset = paginate.offset();
+
+    let chunks = try_join_all(paginate.into_iter().map(fetch_chunk)).await?;
+    let mut ids = chunks
+        .into_iter()
+        .flatten()
+        .skip(offset)
+        .take(rows.len())
+        .collect::<Vec<u64>>();
+
+    if sort_by.desc {
+        ids.reverse();
+    }
+
+    Ok(ids)
+}
+
 async fn get_paginated<M, V>(base_url: String, range: Vec<u64>) -> Result<JsValue, DeserializeError>
 where
     M: DeserializeOwned + Serialize + Send,
@@ -104,3 +114,51 @@ where
         .collect::<Result<Vec<JsValue>, DeserializeError>>()
         .map(|x| x.into_iter().collect::<Array>().into())
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future};
+
+    use super::page_ids;
+    use crate::sort::{SortBy, SortMeta};
+
+    /// Ascending sort index of 25 rows, in chunks of 10 ids
+    fn chunk(page_number: usize) -> future::Ready<Result<Vec<u64>, crate::DeserializeError>> {
+        let start = (page_number as u64 - 1) * 10;
+        let end = (start + 10).min(25);
+        future::ready(Ok((start..end).map(|x| 100 + x).collect()))
+    }
+
+    #[test]
+    fn asc_sort_index() {
+        let sort_by = SortBy::new("balance".to_string(), false);
+        let meta = SortMeta::new(25, 10);
+
+        let page1 = block_on(page_ids(0, 10, &sort_by, meta, chunk)).unwrap();
+        let page3 = block_on(page_ids(2, 10, &sort_by, meta, chunk)).unwrap();
+        assert_eq!(page1, (100..110).collect::<Vec<_>>());
+        assert_eq!(page3, (120..125).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn desc_sort_index() {
+        let sort_by = SortBy::new("balance".to_string(), true);
+        let meta = SortMeta::new(25, 10);
+
+        let page1 = block_on(page_ids(0, 10, &sort_by, meta, chunk)).unwrap();
+        let page2 = block_on(page_ids(1, 10, &sort_by, meta, chunk)).unwrap();
+        let page3 = block_on(page_ids(2, 10, &sort_by, meta, chunk)).unwrap();
+        assert_eq!(page1, (115..125).rev().collect::<Vec<_>>());
+        assert_eq!(page2, (105..115).rev().collect::<Vec<_>>());
+        assert_eq!(page3, (100..105).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn desc_default_sort() {
+        let sort_by = SortBy::new("default".to_string(), true);
+        let meta = SortMeta::new(25, 10);
+
+        let page3 = block_on(page_ids(2, 10, &sort_by, meta, chunk)).unwrap();
+        assert_eq!(page3, vec![4, 3, 2, 1, 0]);
+    }
+}
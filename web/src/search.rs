@@ -0,0 +1,132 @@
+use futures::future::try_join3;
+use types::{
+    block::BlocksMeta,
+    epoch::EpochsMeta,
+    lookup::{normalize_key, LookupTable},
+    meta::Meta,
+    search::{graffiti_shard_path, normalize_graffiti, GraffitiShard},
+    validator::ValidatorsMeta,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    fetcher::{fetch, fetch_optional, lookup},
+    to_js,
+    views::search::{SearchHitKind, SearchHitView},
+    DeserializeError,
+};
+
+/// Maximum number of graffiti matching a search
+const MAX_GRAFFITI_HITS: usize = 10;
+
+/// Length of the hex keys of the roots and of the public keys
+const ROOT_KEY_LEN: usize = 64;
+const PUBKEY_KEY_LEN: usize = 96;
+
+/// Searches the data tree at `base_url` for `query`, which can be an epoch number, a slot, a
+/// validator index, a block or state root, a public key or the start of a graffiti.
+#[wasm_bindgen]
+pub async fn search(base_url: String, query: String) -> Result<JsValue, JsValue> {
+    let hits = search_hits(base_url, query.trim()).await?;
+
+    to_js(&hits).map_err(Into::into)
+}
+
+async fn search_hits(
+    base_url: String,
+    query: &str,
+) -> Result<Vec<SearchHitView>, DeserializeError> {
+    let mut hits = Vec::new();
+
+    if let Ok(number) = query.parse::<u64>() {
+        hits.extend(number_hits(&base_url, number).await?);
+    }
+
+    if let Some(key) = normalize_key(query) {
+        hits.extend(key_hits(&base_url, &key).await?);
+    }
+
+    hits.extend(graffiti_hits(&base_url, query).await?);
+
+    Ok(hits)
+}
+
+/// Returns the epoch, the block and the validator numbered `number`, those which exist.
+async fn number_hits(base_url: &str, number: u64) -> Result<Vec<SearchHitView>, DeserializeError> {
+    let (epochs, blocks, validators) = try_join3(
+        fetch::<EpochsMeta>(EpochsMeta::to_path(base_url)),
+        fetch::<BlocksMeta>(BlocksMeta::to_path(base_url)),
+        fetch::<ValidatorsMeta>(ValidatorsMeta::to_path(base_url)),
+    )
+    .await?;
+
+    let label = number.to_string();
+
+    Ok([
+        (SearchHitKind::Epoch, "epoch", epochs.count),
+        (SearchHitKind::Block, "slot", blocks.count),
+        (
+            SearchHitKind::Validator,
+            "validator_index",
+            validators.count,
+        ),
+    ]
+    .iter()
+    .filter(|(_, _, count)| number < *count as u64)
+    .map(|(kind, matched, _)| SearchHitView::new(*kind, number, *matched, label.clone()))
+    .collect())
+}
+
+/// Returns the blocks whose root or state root is `key`, or the validator whose public key it
+/// is.
+async fn key_hits(base_url: &str, key: &str) -> Result<Vec<SearchHitView>, DeserializeError> {
+    let tables = match key.len() {
+        ROOT_KEY_LEN => vec![
+            (LookupTable::BlockRoots, SearchHitKind::Block, "block_root"),
+            (LookupTable::StateRoots, SearchHitKind::Block, "state_root"),
+        ],
+        PUBKEY_KEY_LEN => vec![(LookupTable::Pubkeys, SearchHitKind::Validator, "pubkey")],
+        _ => vec![],
+    };
+
+    let mut hits = Vec::new();
+
+    for (table, kind, matched) in tables {
+        if let Some(id) = lookup::<u64>(base_url.to_string(), table, key).await? {
+            hits.push(SearchHitView::new(kind, id, matched, format!("0x{}", key)));
+        }
+    }
+
+    Ok(hits)
+}
+
+/// Returns the last blocks of the graffiti starting with `query`.
+async fn graffiti_hits(
+    base_url: &str,
+    query: &str,
+) -> Result<Vec<SearchHitView>, DeserializeError> {
+    let normalized = normalize_graffiti(query);
+
+    let path = match graffiti_shard_path(base_url, &normalized) {
+        Some(path) => path,
+        None => return Ok(Vec::new()),
+    };
+
+    let shard = fetch_optional::<GraffitiShard>(path)
+        .await?
+        .unwrap_or_default();
+
+    Ok(shard
+        .range(normalized.clone()..)
+        .take_while(|(graffiti, _)| graffiti.starts_with(&normalized))
+        .take(MAX_GRAFFITI_HITS)
+        .map(|(_, entry)| {
+            SearchHitView::new(
+                SearchHitKind::Block,
+                entry.last_slot,
+                "graffiti",
+                entry.text.clone(),
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,56 @@
+use js_sys::Promise;
+use types::meta::{Meta, DEFAULT_SORT_CHUNK_SIZE};
+use types::slashing::{SlashingModel, SlashingsMeta};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    fetcher::fetch,
+    page::page,
+    sort::{SortBy, SortMeta},
+    to_js,
+    views::slashings::SlashingView,
+};
+
+/// Proposer and attester slashings of all the blocks, in the order of their inclusion
+#[wasm_bindgen]
+pub struct Slashings {
+    base_url: String,
+    meta: SlashingsMeta,
+}
+
+#[wasm_bindgen]
+impl Slashings {
+    fn new(base_url: String, meta: SlashingsMeta) -> Slashings {
+        Slashings { base_url, meta }
+    }
+
+    #[wasm_bindgen]
+    pub async fn build(base_url: String) -> Result<Slashings, JsValue> {
+        let url = base_url + "/data";
+        let meta = fetch(SlashingsMeta::to_path(&*url)).await?;
+
+        Ok(Slashings::new(url, meta))
+    }
+
+    /// Returns a page of slashings. They have no sort indexes, `sort_id` can only be `default`.
+    pub fn page(
+        &self,
+        page_index: usize,
+        page_size: usize,
+        sort_id: String,
+        sort_desc: bool,
+    ) -> Promise {
+        page::<SlashingModel, SlashingView>(
+            self.base_url.clone(),
+            "slashings".to_string(),
+            page_index,
+            page_size,
+            SortBy::new(sort_id, sort_desc),
+            SortMeta::new(self.meta.count, DEFAULT_SORT_CHUNK_SIZE),
+        )
+    }
+
+    pub fn meta(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.meta).map_err(Into::into)
+    }
+}
@@ -1,7 +1,4 @@
-use std::{
-    cmp::{max, min},
-    ops::Range,
-};
+use std::{cmp::min, ops::Range};
 
 #[derive(Clone)]
 pub struct SortBy {
@@ -15,10 +12,29 @@ impl SortBy {
     }
 }
 
+/// Number of rows of a model list, and of ids per chunk of its sort indexes
+#[derive(Clone, Copy)]
+pub struct SortMeta {
+    pub total_count: usize,
+    pub chunk_size: usize,
+}
+
+impl SortMeta {
+    pub fn new(total_count: usize, chunk_size: usize) -> Self {
+        SortMeta {
+            total_count,
+            chunk_size,
+        }
+    }
+}
+
+/// Chunks of a sort index holding the rows of a page, the index being split into chunks of
+/// `chunk_size` ids in ascending order.
 pub struct Paginate<'a> {
     total_count: usize,
     page_number: usize,
     page_size: usize,
+    chunk_size: usize,
     sort_by: &'a SortBy,
 }
 
@@ -27,15 +43,33 @@ impl<'a> Paginate<'a> {
         total_count: usize,
         page_number: usize,
         page_size: usize,
+        chunk_size: usize,
         sort_by: &'a SortBy,
     ) -> Self {
         Paginate {
             total_count,
             page_number,
             page_size,
+            chunk_size,
             sort_by,
         }
     }
+
+    /// Returns the positions of the rows of the page in the ascending index.
+    pub fn rows(&self) -> Range<usize> {
+        let start = (self.page_number - 1) * self.page_size;
+        let end = min(start + self.page_size, self.total_count);
+
+        match self.sort_by.desc {
+            true => self.total_count.saturating_sub(end)..self.total_count.saturating_sub(start),
+            false => min(start, end)..end,
+        }
+    }
+
+    /// Returns the number of ids to skip in the first chunk to reach the rows of the page.
+    pub fn offset(&self) -> usize {
+        self.rows().start % self.chunk_size
+    }
 }
 
 impl<'a> IntoIterator for Paginate<'a> {
@@ -44,25 +78,13 @@ impl<'a> IntoIterator for Paginate<'a> {
     type IntoIter = Range<usize>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let page_count = self.page_size / 10;
-        let total_page_count =
-            self.total_count / 10 + if self.total_count % 10 != 0 { 1 } else { 0 };
-
-        let range = match self.sort_by.desc {
-            true => {
-                let mut start = total_page_count + 1 - (self.page_number) * page_count;
-                let end = total_page_count + 1 - (self.page_number - 1) * page_count;
-
-                if self.total_count % 10 != 0 {
-                    start -= 1;
-                }
+        let rows = self.rows();
 
-                start..end
-            }
-            false => 1 + (self.page_number - 1) * page_count..self.page_number * page_count + 1,
-        };
+        if rows.is_empty() {
+            return 1..1;
+        }
 
-        max(range.start, 1)..min(range.end, total_page_count + 1)
+        rows.start / self.chunk_size + 1..(rows.end - 1) / self.chunk_size + 2
     }
 }
 
@@ -73,9 +95,9 @@ mod tests {
     #[test]
     fn asc_ten_rows() {
         let sort_by = SortBy::new("attestations_count".to_string(), false);
-        let page1 = Paginate::new(33596, 1, 10, &sort_by);
-        let page2 = Paginate::new(33596, 2, 10, &sort_by);
-        let page3 = Paginate::new(33596, 3, 10, &sort_by);
+        let page1 = Paginate::new(33596, 1, 10, 10, &sort_by);
+        let page2 = Paginate::new(33596, 2, 10, 10, &sort_by);
+        let page3 = Paginate::new(33596, 3, 10, 10, &sort_by);
         assert_eq!(page1.into_iter(), 1..2);
         assert_eq!(page2.into_iter(), 2..3);
         assert_eq!(page3.into_iter(), 3..4);
@@ -84,9 +106,9 @@ mod tests {
     #[test]
     fn asc_thirty_rows() {
         let sort_by = SortBy::new("attestations_count".to_string(), false);
-        let page1 = Paginate::new(33596, 1, 30, &sort_by);
-        let page2 = Paginate::new(33596, 2, 30, &sort_by);
-        let page3 = Paginate::new(33596, 3, 30, &sort_by);
+        let page1 = Paginate::new(33596, 1, 30, 10, &sort_by);
+        let page2 = Paginate::new(33596, 2, 30, 10, &sort_by);
+        let page3 = Paginate::new(33596, 3, 30, 10, &sort_by);
         assert_eq!(page1.into_iter(), 1..4);
         assert_eq!(page2.into_iter(), 4..7);
         assert_eq!(page3.into_iter(), 7..10);
@@ -95,9 +117,9 @@ mod tests {
     #[test]
     fn desc_ten_rows() {
         let sort_by = SortBy::new("attestations_count".to_string(), true);
-        let page1 = Paginate::new(33600, 1, 10, &sort_by);
-        let page2 = Paginate::new(33600, 2, 10, &sort_by);
-        let page3 = Paginate::new(33600, 3, 10, &sort_by);
+        let page1 = Paginate::new(33600, 1, 10, 10, &sort_by);
+        let page2 = Paginate::new(33600, 2, 10, 10, &sort_by);
+        let page3 = Paginate::new(33600, 3, 10, 10, &sort_by);
         assert_eq!(page1.into_iter(), 3360..3361);
         assert_eq!(page2.into_iter(), 3359..3360);
         assert_eq!(page3.into_iter(), 3358..3359);
@@ -106,9 +128,9 @@ mod tests {
     #[test]
     fn desc_ten_rows_overlap() {
         let sort_by = SortBy::new("attestations_count".to_string(), true);
-        let page1 = Paginate::new(33596, 1, 10, &sort_by);
-        let page2 = Paginate::new(33596, 2, 10, &sort_by);
-        let page3 = Paginate::new(33596, 3, 10, &sort_by);
+        let page1 = Paginate::new(33596, 1, 10, 10, &sort_by);
+        let page2 = Paginate::new(33596, 2, 10, 10, &sort_by);
+        let page3 = Paginate::new(33596, 3, 10, 10, &sort_by);
         assert_eq!(page1.into_iter(), 3359..3361);
         assert_eq!(page2.into_iter(), 3358..3360);
         assert_eq!(page3.into_iter(), 3357..3359);
@@ -117,9 +139,9 @@ mod tests {
     #[test]
     fn desc_thirty_rows() {
         let sort_by = SortBy::new("attestations_count".to_string(), true);
-        let page1 = Paginate::new(33600, 1, 30, &sort_by);
-        let page2 = Paginate::new(33600, 2, 30, &sort_by);
-        let page3 = Paginate::new(33600, 3, 30, &sort_by);
+        let page1 = Paginate::new(33600, 1, 30, 10, &sort_by);
+        let page2 = Paginate::new(33600, 2, 30, 10, &sort_by);
+        let page3 = Paginate::new(33600, 3, 30, 10, &sort_by);
         assert_eq!(page1.into_iter(), 3358..3361);
         assert_eq!(page2.into_iter(), 3355..3358);
         assert_eq!(page3.into_iter(), 3352..3355);
@@ -128,11 +150,54 @@ mod tests {
     #[test]
     fn desc_thirty_rows_overlap() {
         let sort_by = SortBy::new("attestations_count".to_string(), true);
-        let page1 = Paginate::new(33596, 1, 30, &sort_by);
-        let page2 = Paginate::new(33596, 2, 30, &sort_by);
-        let page3 = Paginate::new(33596, 3, 30, &sort_by);
+        let page1 = Paginate::new(33596, 1, 30, 10, &sort_by);
+        let page2 = Paginate::new(33596, 2, 30, 10, &sort_by);
+        let page3 = Paginate::new(33596, 3, 30, 10, &sort_by);
         assert_eq!(page1.into_iter(), 3357..3361);
         assert_eq!(page2.into_iter(), 3354..3358);
         assert_eq!(page3.into_iter(), 3351..3355);
     }
+
+    #[test]
+    fn asc_large_chunks() {
+        let sort_by = SortBy::new("balance".to_string(), false);
+        let page1 = Paginate::new(33596, 1, 10, 100, &sort_by);
+        let page10 = Paginate::new(33596, 10, 10, 100, &sort_by);
+        let page11 = Paginate::new(33596, 11, 10, 100, &sort_by);
+        assert_eq!(page1.offset(), 0);
+        assert_eq!(page1.into_iter(), 1..2);
+        assert_eq!(page10.offset(), 90);
+        assert_eq!(page10.into_iter(), 1..2);
+        assert_eq!(page11.offset(), 0);
+        assert_eq!(page11.into_iter(), 2..3);
+    }
+
+    #[test]
+    fn desc_large_chunks_overlap() {
+        let sort_by = SortBy::new("balance".to_string(), true);
+        let page1 = Paginate::new(33596, 1, 10, 100, &sort_by);
+        let page10 = Paginate::new(33596, 10, 10, 100, &sort_by);
+        assert_eq!(page1.rows(), 33586..33596);
+        assert_eq!(page1.offset(), 86);
+        assert_eq!(page1.into_iter(), 336..337);
+        assert_eq!(page10.rows(), 33496..33506);
+        assert_eq!(page10.offset(), 96);
+        assert_eq!(page10.into_iter(), 335..337);
+    }
+
+    #[test]
+    fn desc_last_page() {
+        let sort_by = SortBy::new("balance".to_string(), true);
+        let page = Paginate::new(25, 3, 10, 100, &sort_by);
+        assert_eq!(page.rows(), 0..5);
+        assert_eq!(page.offset(), 0);
+        assert_eq!(page.into_iter(), 1..2);
+    }
+
+    #[test]
+    fn beyond_last_page() {
+        let sort_by = SortBy::new("balance".to_string(), false);
+        let page = Paginate::new(25, 4, 10, 100, &sort_by);
+        assert_eq!(page.into_iter(), 1..1);
+    }
 }
@@ -1,12 +1,35 @@
+use std::ops::RangeInclusive;
+
+use futures::future::try_join_all;
 use js_sys::Promise;
-use types::path::ToPath;
+use types::path::{balance_history_path, duties_path, ToPath};
 use types::{
+    committee::{SyncCommitteeModel, SyncCommitteeModelWithId},
+    duty::ValidatorDutiesModel,
+    lookup::LookupTable,
     meta::Meta,
-    validator::{ValidatorModel, ValidatorModelWithId, ValidatorView, ValidatorsMeta},
+    slashing::{SlashingModel, ValidatorSlashingsModel, ValidatorSlashingsModelWithId},
+    validator::{
+        history_chunk, ValidatorBalanceModel, ValidatorModel, ValidatorModelWithId, ValidatorView,
+        ValidatorsMeta,
+    },
+    DeserializeOwned,
 };
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
 
-use crate::{fetcher::fetch, get::by_id, page::page, to_js};
+use crate::{
+    fetcher::{fetch, fetch_all, fetch_appended, fetch_optional, lookup},
+    get::by_id,
+    page::page,
+    sort::{SortBy, SortMeta},
+    to_js,
+    views::{
+        slashings::SlashingView,
+        validators::{ValidatorDutiesView, ValidatorPerformanceView},
+    },
+    DeserializeError,
+};
 
 #[wasm_bindgen]
 pub struct Validators {
@@ -33,6 +56,135 @@ impl Validators {
         by_id::<ValidatorModel, ValidatorView>(validator_url, validator)
     }
 
+    /// Returns the validator whose public key is `pubkey`, a hex string, or null if unknown.
+    pub fn by_pubkey(&self, pubkey: String) -> Promise {
+        let base_url = self.base_url.clone();
+
+        future_to_promise(async move {
+            match lookup::<u64>(base_url.clone(), LookupTable::Pubkeys, &pubkey).await? {
+                Some(validator) => {
+                    let url = ValidatorModelWithId::to_path(&*base_url, validator);
+                    let model = fetch::<ValidatorModel>(url).await?;
+
+                    to_js(&ValidatorView::from(ValidatorModelWithId {
+                        id: validator,
+                        model,
+                    }))
+                    .map_err(Into::into)
+                }
+                None => Ok(JsValue::NULL),
+            }
+        })
+    }
+
+    /// Returns the validators whose withdrawal credentials are `withdrawal_credentials`, a hex
+    /// string.
+    pub fn by_withdrawal_credentials(&self, withdrawal_credentials: String) -> Promise {
+        let base_url = self.base_url.clone();
+
+        future_to_promise(async move {
+            let validators = lookup::<Vec<u64>>(
+                base_url.clone(),
+                LookupTable::WithdrawalCredentials,
+                &withdrawal_credentials,
+            )
+            .await?
+            .unwrap_or_default();
+
+            let views = fetch_all::<ValidatorModel>(base_url, validators)
+                .await?
+                .into_iter()
+                .map(ValidatorView::from)
+                .collect::<Vec<_>>();
+
+            to_js(&views).map_err(Into::into)
+        })
+    }
+
+    /// Returns the balances of `validator` at the start of the epochs from `from_epoch` to
+    /// `to_epoch` included, in ascending order of epoch.
+    pub fn balance_history(&self, validator: u64, from_epoch: u64, to_epoch: u64) -> Promise {
+        let urls = history_urls(
+            &self.base_url,
+            balance_history_path,
+            validator,
+            from_epoch,
+            to_epoch,
+        );
+
+        future_to_promise(async move {
+            let balances =
+                fetch_history::<ValidatorBalanceModel>(urls, |x| x.epoch, from_epoch..=to_epoch)
+                    .await?;
+
+            to_js(&balances).map_err(Into::into)
+        })
+    }
+
+    /// Returns the duties of `validator` during the epochs from `from_epoch` to `to_epoch`
+    /// included: the blocks it had to propose, the committee it sat in and its sync committee
+    /// signatures.
+    pub fn duties(&self, validator: u64, from_epoch: u64, to_epoch: u64) -> Promise {
+        let urls = history_urls(&self.base_url, duties_path, validator, from_epoch, to_epoch);
+
+        future_to_promise(async move {
+            let duties =
+                fetch_history::<ValidatorDutiesModel>(urls, |x| x.epoch, from_epoch..=to_epoch)
+                    .await?
+                    .into_iter()
+                    .map(ValidatorDutiesView::from)
+                    .collect::<Vec<_>>();
+
+            to_js(&duties).map_err(Into::into)
+        })
+    }
+
+    /// Returns the members of the sync committee of `period`, in the order of the bits of the
+    /// sync aggregates.
+    pub fn sync_committee(&self, period: u64) -> Promise {
+        let sync_committee_url = SyncCommitteeModelWithId::to_path(&*self.base_url, period);
+
+        future_to_promise(async move {
+            let model = fetch::<SyncCommitteeModel>(sync_committee_url).await?;
+            to_js(&model.validators).map_err(Into::into)
+        })
+    }
+
+    /// Returns the slashings `validator` is an offender of, in the order of their inclusion.
+    pub fn slashings(&self, validator: u64) -> Promise {
+        let base_url = self.base_url.clone();
+        let slashings_url = ValidatorSlashingsModelWithId::to_path(&*base_url, validator);
+
+        future_to_promise(async move {
+            let ids = fetch_optional::<ValidatorSlashingsModel>(slashings_url)
+                .await?
+                .unwrap_or_default()
+                .slashings;
+
+            let views = fetch_all::<SlashingModel>(base_url, ids)
+                .await?
+                .into_iter()
+                .map(SlashingView::from)
+                .collect::<Vec<_>>();
+
+            to_js(&views).map_err(Into::into)
+        })
+    }
+
+    /// Returns the proposals and the attestation inclusions of `validator` summed up over the
+    /// epochs from `from_epoch` to `to_epoch` included.
+    pub fn performance(&self, validator: u64, from_epoch: u64, to_epoch: u64) -> Promise {
+        let urls = history_urls(&self.base_url, duties_path, validator, from_epoch, to_epoch);
+
+        future_to_promise(async move {
+            let duties =
+                fetch_history::<ValidatorDutiesModel>(urls, |x| x.epoch, from_epoch..=to_epoch)
+                    .await?;
+
+            to_js(&ValidatorPerformanceView::from(duties.as_slice())).map_err(Into::into)
+        })
+    }
+
     pub fn page(
         &self,
         page_index: usize,
@@ -45,9 +197,8 @@ impl Validators {
             "validators".to_string(),
             page_index,
             page_size,
-            sort_id,
-            sort_desc,
-            self.meta.count,
+            SortBy::new(sort_id, sort_desc),
+            SortMeta::new(self.meta.count, self.meta.sort_chunk_size),
         )
     }
 
@@ -55,3 +206,33 @@ impl Validators {
         to_js(&self.meta).map_err(Into::into)
     }
 }
+
+/// Returns the urls of the chunks of a history of `validator`, whose path is given by `path`,
+/// holding the epochs from `from_epoch` to `to_epoch`.
+fn history_urls(
+    base_url: &str,
+    path: fn(&str, u64, u64) -> String,
+    validator: u64,
+    from_epoch: u64,
+    to_epoch: u64,
+) -> Vec<String> {
+    (history_chunk(from_epoch)..=history_chunk(to_epoch))
+        .map(|chunk| path(base_url, chunk, validator))
+        .collect()
+}
+
+/// Fetches the records of a history in `epochs`, in ascending order of epoch.
+async fn fetch_history<T: DeserializeOwned>(
+    urls: Vec<String>,
+    epoch: fn(&T) -> u64,
+    epochs: RangeInclusive<u64>,
+) -> Result<Vec<T>, DeserializeError> {
+    let records = try_join_all(urls.into_iter().map(fetch_appended::<T>))
+        .await?
+        .into_iter()
+        .flatten()
+        .filter(|x| epochs.contains(&epoch(x)))
+        .collect();
+
+    Ok(records)
+}
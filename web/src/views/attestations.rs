@@ -10,6 +10,7 @@ pub struct AttestationView {
     pub source: u64,
     pub target: u64,
     pub signature: String,
+    pub attesting_indices: Vec<u64>,
 }
 
 impl From<AttestationModel> for AttestationView {
@@ -22,6 +23,7 @@ impl From<AttestationModel> for AttestationView {
             source: model.source,
             target: model.target,
             signature: model.signature,
+            attesting_indices: model.attesting_indices,
         }
     }
 }
@@ -0,0 +1,32 @@
+use serde::Serialize;
+use types::{
+    deposit::{DepositModel, DepositModelWithId},
+    lookup::to_key,
+};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DepositView {
+    pub slot: u64,
+    pub index: u64,
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub amount: u64,
+}
+
+impl From<DepositModel> for DepositView {
+    fn from(model: DepositModel) -> Self {
+        DepositView {
+            slot: model.slot,
+            index: model.index,
+            pubkey: format!("0x{}", to_key(&model.pubkey)),
+            withdrawal_credentials: format!("0x{}", to_key(&model.withdrawal_credentials)),
+            amount: model.amount,
+        }
+    }
+}
+
+impl From<DepositModelWithId> for DepositView {
+    fn from(value: DepositModelWithId) -> Self {
+        value.model.into()
+    }
+}
@@ -1,4 +1,9 @@
 pub mod attestations;
 pub mod blocks;
 pub mod committees;
+pub mod deposits;
+pub mod search;
+pub mod slashings;
+pub mod validators;
+pub mod voluntary_exits;
 pub mod votes;
@@ -0,0 +1,31 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchHitKind {
+    Epoch,
+    Block,
+    Validator,
+}
+
+/// Model matching a search, with the id to navigate to
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchHitView {
+    pub kind: SearchHitKind,
+    pub id: u64,
+    /// Field of the model the query matched, such as "slot", "block_root" or "graffiti"
+    pub matched: &'static str,
+    /// Text to display for the hit
+    pub label: String,
+}
+
+impl SearchHitView {
+    pub fn new(kind: SearchHitKind, id: u64, matched: &'static str, label: String) -> Self {
+        SearchHitView {
+            kind,
+            id,
+            matched,
+            label,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Serialize;
+use types::{
+    lookup::to_key,
+    slashing::{
+        AttestationDataModel, BlockHeaderModel, SlashingConflictModel, SlashingModel,
+        SlashingModelWithId,
+    },
+};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BlockHeaderView {
+    pub slot: u64,
+    pub proposer_index: u64,
+    pub parent_root: String,
+    pub state_root: String,
+    pub body_root: String,
+}
+
+impl From<BlockHeaderModel> for BlockHeaderView {
+    fn from(model: BlockHeaderModel) -> Self {
+        BlockHeaderView {
+            slot: model.slot,
+            proposer_index: model.proposer_index,
+            parent_root: format!("0x{}", to_key(&model.parent_root)),
+            state_root: format!("0x{}", to_key(&model.state_root)),
+            body_root: format!("0x{}", to_key(&model.body_root)),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AttestationDataView {
+    pub slot: u64,
+    pub committee_index: u64,
+    pub beacon_block_root: String,
+    pub source: u64,
+    pub target: u64,
+}
+
+impl From<AttestationDataModel> for AttestationDataView {
+    fn from(model: AttestationDataModel) -> Self {
+        AttestationDataView {
+            slot: model.slot,
+            committee_index: model.committee_index,
+            beacon_block_root: format!("0x{}", to_key(&model.beacon_block_root)),
+            source: model.source,
+            target: model.target,
+        }
+    }
+}
+
+/// Conflicting messages of a slashing, tagged with its kind: `proposer` or `attester`
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SlashingConflictView {
+    Proposer {
+        header_1: BlockHeaderView,
+        header_2: BlockHeaderView,
+    },
+    Attester {
+        attestation_1: AttestationDataView,
+        attestation_2: AttestationDataView,
+    },
+}
+
+impl From<SlashingConflictModel> for SlashingConflictView {
+    fn from(model: SlashingConflictModel) -> Self {
+        match model {
+            SlashingConflictModel::Proposer(header_1, header_2) => SlashingConflictView::Proposer {
+                header_1: header_1.into(),
+                header_2: header_2.into(),
+            },
+            SlashingConflictModel::Attester(attestation_1, attestation_2) => {
+                SlashingConflictView::Attester {
+                    attestation_1: attestation_1.into(),
+                    attestation_2: attestation_2.into(),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SlashingView {
+    pub slot: u64,
+    pub offenders: Vec<u64>,
+    #[serde(flatten)]
+    pub conflict: SlashingConflictView,
+}
+
+impl From<SlashingModel> for SlashingView {
+    fn from(model: SlashingModel) -> Self {
+        SlashingView {
+            slot: model.slot,
+            offenders: model.offenders,
+            conflict: model.conflict.into(),
+        }
+    }
+}
+
+impl From<SlashingModelWithId> for SlashingView {
+    fn from(value: SlashingModelWithId) -> Self {
+        value.model.into()
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use types::duty::{AttestationDutyModel, ProposalDutyModel, SyncDutyModel, ValidatorDutiesModel};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AttestationDutyView {
+    pub slot: u64,
+    pub committee_index: u64,
+    pub inclusion_slot: Option<u64>,
+    pub inclusion_delay: Option<u64>,
+}
+
+impl From<AttestationDutyModel> for AttestationDutyView {
+    fn from(model: AttestationDutyModel) -> Self {
+        AttestationDutyView {
+            slot: model.slot,
+            committee_index: model.committee_index,
+            inclusion_slot: model.inclusion_slot,
+            inclusion_delay: model.inclusion_delay(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ValidatorDutiesView {
+    pub epoch: u64,
+    pub proposals: Vec<ProposalDutyModel>,
+    pub attestation: Option<AttestationDutyView>,
+    pub sync: Option<SyncDutyModel>,
+}
+
+impl From<ValidatorDutiesModel> for ValidatorDutiesView {
+    fn from(model: ValidatorDutiesModel) -> Self {
+        ValidatorDutiesView {
+            epoch: model.epoch,
+            proposals: model.proposals,
+            attestation: model.attestation.map(Into::into),
+            sync: model.sync,
+        }
+    }
+}
+
+/// Performance of a validator over a range of epochs, summed up from its duties.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ValidatorPerformanceView {
+    pub proposed: usize,
+    pub missed: usize,
+    pub orphaned: usize,
+    pub attestations: usize,
+    pub included_attestations: usize,
+    pub average_inclusion_delay: Option<f64>,
+    pub sync_signatures: usize,
+    pub missed_sync_signatures: usize,
+}
+
+impl From<&[ValidatorDutiesModel]> for ValidatorPerformanceView {
+    fn from(duties: &[ValidatorDutiesModel]) -> Self {
+        let mut performance = ValidatorPerformanceView::default();
+        let mut total_inclusion_delay = 0;
+
+        for proposal in duties.iter().flat_map(|x| x.proposals.iter()) {
+            match proposal.status.as_str() {
+                "Proposed" => performance.proposed += 1,
+                "Missed" => performance.missed += 1,
+                "Orphaned" => performance.orphaned += 1,
+                _ => {}
+            }
+        }
+
+        for attestation in duties.iter().filter_map(|x| x.attestation.as_ref()) {
+            performance.attestations += 1;
+
+            if let Some(inclusion_delay) = attestation.inclusion_delay() {
+                performance.included_attestations += 1;
+                total_inclusion_delay += inclusion_delay;
+            }
+        }
+
+        for sync in duties.iter().filter_map(|x| x.sync.as_ref()) {
+            performance.sync_signatures += sync.signed_slots.len();
+            performance.missed_sync_signatures += sync.missed_slots.len();
+        }
+
+        if performance.included_attestations > 0 {
+            performance.average_inclusion_delay =
+                Some(total_inclusion_delay as f64 / performance.included_attestations as f64);
+        }
+
+        performance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::duty::{
+        AttestationDutyModel, ProposalDutyModel, SyncDutyModel, ValidatorDutiesModel,
+    };
+
+    use super::ValidatorPerformanceView;
+
+    fn duties(
+        epoch: u64,
+        status: Option<&str>,
+        inclusion_slot: Option<u64>,
+    ) -> ValidatorDutiesModel {
+        let slot = epoch * 32;
+
+        ValidatorDutiesModel {
+            epoch,
+            proposals: status
+                .map(|status| ProposalDutyModel {
+                    slot,
+                    status: status.to_string(),
+                })
+                .into_iter()
+                .collect(),
+            attestation: Some(AttestationDutyModel {
+                slot,
+                committee_index: 0,
+                inclusion_slot,
+            }),
+            sync: None,
+        }
+    }
+
+    #[test]
+    fn performance_from_duties() {
+        let duties = vec![
+            duties(0, Some("Proposed"), Some(1)),
+            duties(1, Some("Missed"), Some(35)),
+            ValidatorDutiesModel {
+                sync: Some(SyncDutyModel {
+                    period: 0,
+                    signed_slots: vec![64, 65],
+                    missed_slots: vec![66],
+                }),
+                ..duties(2, None, None)
+            },
+        ];
+
+        assert_eq!(
+            ValidatorPerformanceView::from(duties.as_slice()),
+            ValidatorPerformanceView {
+                proposed: 1,
+                missed: 1,
+                orphaned: 0,
+                attestations: 3,
+                included_attestations: 2,
+                average_inclusion_delay: Some(2.0),
+                sync_signatures: 2,
+                missed_sync_signatures: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use serde::Serialize;
+use types::voluntary_exit::{VoluntaryExitModel, VoluntaryExitModelWithId};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct VoluntaryExitView {
+    pub slot: u64,
+    pub validator_index: u64,
+    pub epoch: u64,
+}
+
+impl From<VoluntaryExitModel> for VoluntaryExitView {
+    fn from(model: VoluntaryExitModel) -> Self {
+        VoluntaryExitView {
+            slot: model.slot,
+            validator_index: model.validator_index,
+            epoch: model.epoch,
+        }
+    }
+}
+
+impl From<VoluntaryExitModelWithId> for VoluntaryExitView {
+    fn from(value: VoluntaryExitModelWithId) -> Self {
+        value.model.into()
+    }
+}
@@ -0,0 +1,55 @@
+use js_sys::Promise;
+use types::meta::Meta;
+use types::voluntary_exit::{VoluntaryExitModel, VoluntaryExitsMeta};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    fetcher::fetch,
+    page::page,
+    sort::{SortBy, SortMeta},
+    to_js,
+    views::voluntary_exits::VoluntaryExitView,
+};
+
+/// Voluntary exits of all the blocks, in the order of their inclusion
+#[wasm_bindgen]
+pub struct VoluntaryExits {
+    base_url: String,
+    meta: VoluntaryExitsMeta,
+}
+
+#[wasm_bindgen]
+impl VoluntaryExits {
+    fn new(base_url: String, meta: VoluntaryExitsMeta) -> VoluntaryExits {
+        VoluntaryExits { base_url, meta }
+    }
+
+    #[wasm_bindgen]
+    pub async fn build(base_url: String) -> Result<VoluntaryExits, JsValue> {
+        let url = base_url + "/data";
+        let meta = fetch(VoluntaryExitsMeta::to_path(&*url)).await?;
+
+        Ok(VoluntaryExits::new(url, meta))
+    }
+
+    pub fn page(
+        &self,
+        page_index: usize,
+        page_size: usize,
+        sort_id: String,
+        sort_desc: bool,
+    ) -> Promise {
+        page::<VoluntaryExitModel, VoluntaryExitView>(
+            self.base_url.clone(),
+            "voluntary_exits".to_string(),
+            page_index,
+            page_size,
+            SortBy::new(sort_id, sort_desc),
+            SortMeta::new(self.meta.count, self.meta.sort_chunk_size),
+        )
+    }
+
+    pub fn meta(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.meta).map_err(Into::into)
+    }
+}